@@ -0,0 +1,396 @@
+//! Clustering of one list of strings by similarity.
+//!
+//! `dedupe` joins the strings scoring at least a threshold into connected
+//! components. Pairs are found with range queries on a `VPTree` over the
+//! distinct strings rather than by scoring every pair.
+//!
+//! `cluster` adds average and complete linkage, agglomerated with the
+//! nearest-neighbour chain over a condensed similarity matrix, and DBSCAN,
+//! which runs on the same range queries as `dedupe`.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::index::vptree::VPTree;
+use crate::metric::Similarity;
+use crate::process::{self, Preprocessor};
+
+/// Disjoint sets over `0..n`, each represented by its smallest member.
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        UnionFind { parents: (0..n).collect() }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parents[x] != x {
+            self.parents[x] = self.parents[self.parents[x]];
+            x = self.parents[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+/// `strings` run through `processor`, if any, which must return str.
+fn processed(processor: &Option<Preprocessor>, strings: &[String]) -> PyResult<Vec<String>> {
+    if processor.is_none() {
+        return Ok(strings.to_vec());
+    }
+    process::apply_strs(processor, strings)
+}
+
+/// Labels numbered from 0 in order of first appearance, given any cluster
+/// key per string.
+fn relabel(keys: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut labels = HashMap::new();
+    keys.map(|key| {
+        let next = labels.len();
+        *labels.entry(key).or_insert(next)
+    })
+    .collect()
+}
+
+/// The distinct strings in order of first appearance, and the position among
+/// them of each string.
+fn distinct(strings: &[String]) -> (Vec<String>, Vec<usize>) {
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let of: Vec<usize> = strings
+        .iter()
+        .map(|s| {
+            let next = ids.len();
+            *ids.entry(s.as_str()).or_insert(next)
+        })
+        .collect();
+    let mut distinct = vec![String::new(); ids.len()];
+    for (s, id) in ids {
+        distinct[id] = s.to_string();
+    }
+    (distinct, of)
+}
+
+/// Cluster labels of `strings`, joining pairs scoring at least `threshold`.
+fn components(strings: &[String], metric: Similarity, threshold: f64, exhaustive: bool) -> PyResult<Vec<usize>> {
+    let (distinct, of) = distinct(strings);
+    let tree = VPTree::new(distinct.clone(), metric, exhaustive)?;
+    let pairs: Vec<(usize, usize)> = distinct
+        .par_iter()
+        .enumerate()
+        .flat_map_iter(|(i, s)| {
+            tree.within(s, threshold)
+                .into_iter()
+                .filter(move |hit| hit.0 > i)
+                .map(move |hit| (i, hit.0))
+        })
+        .collect();
+
+    let mut sets = UnionFind::new(distinct.len());
+    for (a, b) in pairs {
+        sets.union(a, b);
+    }
+    Ok(relabel(of.into_iter().map(|id| sets.find(id))))
+}
+
+/// Ways `cluster` groups strings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Method {
+    Single,
+    Average,
+    Complete,
+    Dbscan,
+}
+
+impl Method {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "single" => Ok(Method::Single),
+            "average" => Ok(Method::Average),
+            "complete" => Ok(Method::Complete),
+            "dbscan" => Ok(Method::Dbscan),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unknown clustering method: {}",
+                name
+            ))),
+        }
+    }
+}
+
+/// Similarities of every pair `i < j` of `n` strings, row by row as in
+/// `scipy.spatial.distance.pdist`.
+struct Condensed {
+    n: usize,
+    similarities: Vec<f64>,
+}
+
+impl Condensed {
+    fn new(strings: &[String], metric: Similarity) -> PyResult<Self> {
+        let n = strings.len();
+        if n.checked_mul(n.saturating_sub(1)).is_none() {
+            return Err(exceptions::PyValueError::new_err("Too many strings to cluster"));
+        }
+        let similarities = (0..n)
+            .into_par_iter()
+            .flat_map_iter(|i| (i + 1..n).map(move |j| metric.similarity(&strings[i], &strings[j])))
+            .collect();
+        Ok(Condensed { n, similarities })
+    }
+
+    fn position(&self, i: usize, j: usize) -> usize {
+        let (i, j) = (i.min(j), i.max(j));
+        self.n * i - i * (i + 1) / 2 + j - i - 1
+    }
+
+    fn get(&self, i: usize, j: usize) -> f64 {
+        self.similarities[self.position(i, j)]
+    }
+
+    fn set(&mut self, i: usize, j: usize, similarity: f64) {
+        let position = self.position(i, j);
+        self.similarities[position] = similarity;
+    }
+}
+
+/// Cluster labels of `strings` under average or complete linkage, merging
+/// clusters while their linkage similarity is at least `threshold`.
+///
+/// Both linkages are reducible, so the nearest-neighbour chain finds the
+/// merges of the greedy agglomeration in `O(n²)`, though not in order of
+/// similarity; the merges above the threshold are then replayed.
+fn linkage(strings: &[String], metric: Similarity, method: Method, threshold: f64) -> PyResult<Vec<usize>> {
+    let n = strings.len();
+    let mut matrix = Condensed::new(strings, metric)?;
+    let mut sizes = vec![1usize; n];
+    let mut active = vec![true; n];
+    let mut merges: Vec<(usize, usize, f64)> = Vec::with_capacity(n.saturating_sub(1));
+    let mut chain: Vec<usize> = Vec::new();
+    for _ in 1..n {
+        if chain.is_empty() {
+            chain.extend(active.iter().position(|&a| a));
+        }
+        let (a, b, similarity) = loop {
+            let a = chain[chain.len() - 1];
+            // Preferring the previous link on ties keeps the chain from cycling.
+            let mut best = chain.len().checked_sub(2).map(|i| (chain[i], matrix.get(a, chain[i])));
+            for k in (0..n).filter(|&k| active[k] && k != a) {
+                let similarity = matrix.get(a, k);
+                match best {
+                    Some((_, most)) if most >= similarity => {}
+                    _ => best = Some((k, similarity)),
+                }
+            }
+            let (b, similarity) = best.expect("at least two active clusters");
+            if chain.len() >= 2 && b == chain[chain.len() - 2] {
+                chain.truncate(chain.len() - 2);
+                break (a.min(b), a.max(b), similarity);
+            }
+            chain.push(b);
+        };
+        for k in (0..n).filter(|&k| active[k] && k != a && k != b) {
+            let (ak, bk) = (matrix.get(a, k), matrix.get(b, k));
+            let merged = match method {
+                Method::Complete => ak.min(bk),
+                _ => (sizes[a] as f64 * ak + sizes[b] as f64 * bk) / (sizes[a] + sizes[b]) as f64,
+            };
+            matrix.set(a, k, merged);
+        }
+        sizes[a] += sizes[b];
+        active[b] = false;
+        merges.push((a, b, similarity));
+    }
+
+    let mut sets = UnionFind::new(n);
+    for (a, b, similarity) in merges {
+        if similarity >= threshold {
+            sets.union(a, b);
+        }
+    }
+    Ok(relabel((0..n).map(|i| sets.find(i))))
+}
+
+/// DBSCAN labels of `strings`: strings with at least `min_samples` strings,
+/// themselves included, scoring at least `threshold` are core strings; core
+/// strings within reach of each other form a cluster along with the strings
+/// they reach, and the other strings are noise, labelled `-1`.
+fn dbscan(
+    strings: &[String],
+    metric: Similarity,
+    threshold: f64,
+    min_samples: usize,
+    exhaustive: bool,
+) -> PyResult<Vec<isize>> {
+    let (distinct, of) = distinct(strings);
+    let mut counts = vec![0usize; distinct.len()];
+    for &id in &of {
+        counts[id] += 1;
+    }
+    let tree = VPTree::new(distinct.clone(), metric, exhaustive)?;
+    let neighbours: Vec<Vec<usize>> = distinct
+        .par_iter()
+        .map(|s| tree.within(s, threshold).into_iter().map(|hit| hit.0).collect())
+        .collect();
+    let core: Vec<bool> = neighbours
+        .iter()
+        .map(|ids| ids.iter().map(|&id| counts[id]).sum::<usize>() >= min_samples)
+        .collect();
+
+    let mut clusters: Vec<Option<usize>> = vec![None; distinct.len()];
+    let mut found = 0;
+    for start in 0..distinct.len() {
+        if !core[start] || clusters[start].is_some() {
+            continue;
+        }
+        clusters[start] = Some(found);
+        let mut pending = vec![start];
+        while let Some(id) = pending.pop() {
+            for &neighbour in &neighbours[id] {
+                if clusters[neighbour].is_none() {
+                    clusters[neighbour] = Some(found);
+                    if core[neighbour] {
+                        pending.push(neighbour);
+                    }
+                }
+            }
+        }
+        found += 1;
+    }
+
+    let mut labels = relabel(of.iter().filter_map(|&id| clusters[id])).into_iter();
+    Ok(of
+        .iter()
+        .map(|&id| match clusters[id] {
+            Some(_) => labels.next().map_or(-1, |label| label as isize),
+            None => -1,
+        })
+        .collect())
+}
+
+/// The most frequent string of each cluster, the first one among equally
+/// frequent strings, for every string.
+fn representatives(strings: &[String], labels: &[usize]) -> Vec<String> {
+    let clusters = labels.iter().max().map_or(0, |&max| max + 1);
+    let mut counts: HashMap<(usize, &str), usize> = HashMap::new();
+    let mut best: Vec<Option<(usize, &str)>> = vec![None; clusters];
+    for (s, &label) in strings.iter().zip(labels) {
+        let count = counts.entry((label, s.as_str())).or_insert(0);
+        *count += 1;
+        match best[label] {
+            Some((most, _)) if most >= *count => {}
+            _ => best[label] = Some((*count, s.as_str())),
+        }
+    }
+    labels
+        .iter()
+        .map(|&label| best[label].map_or_else(String::new, |(_, s)| s.to_string()))
+        .collect()
+}
+
+/// dedupe(strings, metric="normalized_levenshtein", threshold=0.8, processor=None, canonical=False, exhaustive=False)
+///
+/// Groups near-duplicate strings: any two strings scoring at least
+/// ``threshold`` end up in the same cluster, as do strings linked through a
+/// chain of such pairs. Pairs are found with a :py:class:`VPTree`, so the
+/// caveats of its ``exhaustive`` flag apply.
+///
+/// :param list strings: strings to deduplicate
+/// :param str metric: similarity name accepted by :py:meth:`VPTree.build`
+/// :param float threshold: lowest similarity joining two strings
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param bool canonical: return the representative of each string's cluster,
+///     its most frequent string and the first of those among ties, rather
+///     than a label
+/// :param bool exhaustive: compare every pair rather than prune
+/// :return: cluster label of each string, numbered from 0 in order of first
+///     appearance, or its representative
+/// :rtype: list
+/// :raises ValueError: if the metric is unknown or threshold is NaN
+#[pyfunction]
+#[pyo3(signature = (strings, metric="normalized_levenshtein", threshold=0.8, processor=None, canonical=false, exhaustive=false))]
+pub(crate) fn dedupe(
+    py: Python,
+    strings: Vec<String>,
+    metric: &str,
+    threshold: f64,
+    processor: Option<Preprocessor>,
+    canonical: bool,
+    exhaustive: bool,
+) -> PyResult<PyObject> {
+    let metric = Similarity::parse(metric)?;
+    if threshold.is_nan() {
+        return Err(exceptions::PyValueError::new_err("threshold must not be NaN"));
+    }
+    let processed = processed(&processor, &strings)?;
+    let labels = py.allow_threads(|| components(&processed, metric, threshold, exhaustive))?;
+    Ok(if canonical {
+        representatives(&strings, &labels).into_py(py)
+    } else {
+        labels.into_py(py)
+    })
+}
+
+/// cluster(strings, metric="normalized_levenshtein", method="average", threshold=0.8, min_samples=5, processor=None, exhaustive=False)
+///
+/// Clusters strings without going through a distance matrix in Python.
+/// ``"average"`` and ``"complete"`` agglomerate clusters while the mean,
+/// respectively lowest, similarity between their strings is at least
+/// ``threshold``, like cutting a ``scipy.cluster.hierarchy`` linkage of
+/// ``1 - metric`` at ``1 - threshold``; they hold every pair's similarity in
+/// memory. ``"single"`` links strings scoring at least ``threshold`` as
+/// :py:func:`dedupe` does. ``"dbscan"`` finds the strings with at least
+/// ``min_samples`` strings, themselves included, scoring at least
+/// ``threshold`` and grows clusters from them; the other strings are noise.
+/// ``"single"`` and ``"dbscan"`` search a :py:class:`VPTree`, so the caveats of
+/// its ``exhaustive`` flag apply.
+///
+/// :param list strings: strings to cluster
+/// :param str metric: similarity name accepted by :py:meth:`VPTree.build`
+/// :param str method: ``"average"``, ``"complete"``, ``"single"`` or ``"dbscan"``
+/// :param float threshold: lowest similarity joining two clusters, or two
+///     neighbours for ``"dbscan"``
+/// :param int min_samples: neighbours making a core string for ``"dbscan"``
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param bool exhaustive: compare every pair rather than prune
+/// :return: cluster label of each string, numbered from 0 in order of first
+///     appearance, -1 for noise
+/// :rtype: list
+/// :raises ValueError: if the metric or method is unknown or threshold is NaN
+#[pyfunction]
+#[pyo3(signature = (strings, metric="normalized_levenshtein", method="average", threshold=0.8, min_samples=5, processor=None, exhaustive=false))]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn cluster(
+    py: Python,
+    strings: Vec<String>,
+    metric: &str,
+    method: &str,
+    threshold: f64,
+    min_samples: usize,
+    processor: Option<Preprocessor>,
+    exhaustive: bool,
+) -> PyResult<Vec<isize>> {
+    let metric = Similarity::parse(metric)?;
+    let method = Method::parse(method)?;
+    if threshold.is_nan() {
+        return Err(exceptions::PyValueError::new_err("threshold must not be NaN"));
+    }
+    let processed = processed(&processor, &strings)?;
+    py.allow_threads(|| match method {
+        Method::Single => Ok(components(&processed, metric, threshold, exhaustive)?
+            .into_iter()
+            .map(|label| label as isize)
+            .collect()),
+        Method::Average | Method::Complete => Ok(linkage(&processed, metric, method, threshold)?
+            .into_iter()
+            .map(|label| label as isize)
+            .collect()),
+        Method::Dbscan => dbscan(&processed, metric, threshold, min_samples, exhaustive),
+    })
+}
@@ -0,0 +1,25 @@
+//! Bag distance (Bartolini, Ciaccia and Patella, 2002), a linear-time lower
+//! bound of the Levenshtein distance.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Larger of the number of elements of `a` missing from `b` and of `b`
+/// missing from `a`, counting repeated elements as often as they occur.
+pub(crate) fn generic_bag_distance<T, A, B>(a: A, b: B) -> usize
+where
+    T: Eq + Hash,
+    A: IntoIterator<Item = T>,
+    B: IntoIterator<Item = T>,
+{
+    let mut balance: HashMap<T, isize> = HashMap::new();
+    for elem in a {
+        *balance.entry(elem).or_insert(0) += 1;
+    }
+    for elem in b {
+        *balance.entry(elem).or_insert(0) -= 1;
+    }
+    let only_a: isize = balance.values().filter(|&&n| n > 0).sum();
+    let only_b: isize = -balance.values().filter(|&&n| n < 0).sum::<isize>();
+    only_a.max(only_b) as usize
+}
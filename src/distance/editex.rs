@@ -0,0 +1,87 @@
+//! Editex (Zobel and Dart, 1996), an edit distance aware of letters that
+//! sound alike.
+
+use super::Text;
+
+/// Letter groups; substituting a letter by another of one of its groups costs
+/// 1 instead of 2.
+const GROUPS: &[&[u8]] = &[
+    b"AEIOUY", b"BP", b"CKQ", b"DT", b"LR", b"MN", b"GJ", b"FPV", b"SXZ", b"CSZ",
+];
+
+/// Cost of replacing `a` by `b`.
+fn replace(a: char, b: char) -> usize {
+    if a == b {
+        return 0;
+    }
+    let same_group = a.is_ascii() && b.is_ascii() && GROUPS
+        .iter()
+        .any(|group| group.contains(&(a as u8)) && group.contains(&(b as u8)));
+    if same_group {
+        1
+    } else {
+        2
+    }
+}
+
+/// Cost of deleting `b` after `a`; "h" and "w" are often silent.
+fn delete(a: char, b: char) -> usize {
+    if a != b && (a == 'H' || a == 'W') {
+        1
+    } else {
+        replace(a, b)
+    }
+}
+
+/// Letters of `a` and `b` after a leading space, upper-cased. Bytes are
+/// taken as Latin-1 characters, and so is a `str` compared with `bytes`.
+fn letters(a: &Text, b: &Text) -> (Vec<char>, Vec<char>) {
+    let letters = |letters: &mut dyn Iterator<Item = char>| {
+        std::iter::once(' ')
+            .chain(letters.map(|c| c.to_ascii_uppercase()))
+            .collect()
+    };
+    match (a, b) {
+        (Text::Str(a), Text::Str(b)) => (letters(&mut a.chars()), letters(&mut b.chars())),
+        _ => (
+            letters(&mut a.as_bytes().iter().map(|&b| char::from(b))),
+            letters(&mut b.as_bytes().iter().map(|&b| char::from(b))),
+        ),
+    }
+}
+
+/// Editex distance between `a` and `b`, ignoring ASCII case.
+pub(crate) fn editex(a: &Text, b: &Text) -> usize {
+    let (a, b) = letters(a, b);
+    distance(&a, &b)
+}
+
+fn distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = Vec::with_capacity(b.len());
+    previous.push(0);
+    for j in 1..b.len() {
+        previous.push(previous[j - 1] + delete(b[j - 1], b[j]));
+    }
+    let mut current = vec![0; b.len()];
+    for i in 1..a.len() {
+        current[0] = previous[0] + delete(a[i - 1], a[i]);
+        for j in 1..b.len() {
+            current[j] = (previous[j] + delete(a[i - 1], a[i]))
+                .min(current[j - 1] + delete(b[j - 1], b[j]))
+                .min(previous[j - 1] + replace(a[i], b[j]));
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len() - 1]
+}
+
+/// Editex similarity in `[0, 1]`: one minus the distance over its maximum,
+/// twice the length of the longer string.
+pub(crate) fn normalized_editex(a: &Text, b: &Text) -> f64 {
+    let (a, b) = letters(a, b);
+    let longest = a.len().max(b.len()) - 1;
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - distance(&a, &b) as f64 / (2 * longest) as f64
+}
@@ -0,0 +1,120 @@
+//! Metrics over slices of any elements, on which `Unit` runs them once
+//! the strings are split and the sequence metrics once the Python elements
+//! are interned.
+
+use std::cmp::min;
+use std::hash::Hash;
+
+/// A slice as strsim's generic functions take it, by reference to something
+/// iterable.
+struct Units<'a, T>(&'a [T]);
+
+impl<'a, T> IntoIterator for &Units<'a, T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+pub(crate) fn levenshtein<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    strsim::generic_levenshtein(&Units(a), &Units(b))
+}
+
+pub(crate) fn normalized_levenshtein<T: PartialEq>(a: &[T], b: &[T]) -> f64 {
+    normalize(levenshtein(a, b), a.len(), b.len())
+}
+
+pub(crate) fn damerau_levenshtein<T: Eq + Hash + Clone>(a: &[T], b: &[T]) -> usize {
+    strsim::generic_damerau_levenshtein(a, b)
+}
+
+pub(crate) fn normalized_damerau_levenshtein<T: Eq + Hash + Clone>(a: &[T], b: &[T]) -> f64 {
+    normalize(damerau_levenshtein(a, b), a.len(), b.len())
+}
+
+pub(crate) fn jaro<T: PartialEq>(a: &[T], b: &[T]) -> f64 {
+    strsim::generic_jaro(&Units(a), &Units(b))
+}
+
+/// strsim's `generic_damerau_levenshtein` with the last row each byte was
+/// seen in kept in an array rather than a `HashMap`.
+pub(crate) fn damerau_levenshtein_bytes(a: &[u8], b: &[u8]) -> usize {
+    let (a_len, b_len) = (a.len(), b.len());
+    if a_len == 0 {
+        return b_len;
+    }
+    if b_len == 0 {
+        return a_len;
+    }
+    let width = a_len + 2;
+    let index = |i: usize, j: usize| j * width + i;
+    let mut distances = vec![0; (a_len + 2) * (b_len + 2)];
+    let max_distance = a_len + b_len;
+    distances[0] = max_distance;
+    for i in 0..=a_len {
+        distances[index(i + 1, 0)] = max_distance;
+        distances[index(i + 1, 1)] = i;
+    }
+    for j in 0..=b_len {
+        distances[index(0, j + 1)] = max_distance;
+        distances[index(1, j + 1)] = j;
+    }
+    let mut last_row = [0; 256];
+    for i in 1..=a_len {
+        let mut last_column = 0;
+        for j in 1..=b_len {
+            let k = last_row[usize::from(b[j - 1])];
+            let insertion = distances[index(i, j + 1)] + 1;
+            let deletion = distances[index(i + 1, j)] + 1;
+            let transposition = distances[index(k, last_column)] + (i - k - 1) + 1 + (j - last_column - 1);
+            let mut substitution = distances[index(i, j)] + 1;
+            if a[i - 1] == b[j - 1] {
+                last_column = j;
+                substitution -= 1;
+            }
+            distances[index(i + 1, j + 1)] = substitution.min(insertion).min(deletion).min(transposition);
+        }
+        last_row[usize::from(a[i - 1])] = i;
+    }
+    distances[index(a_len + 1, b_len + 1)]
+}
+
+/// `1 - distance / longest`, as strsim normalizes, with two empty strings
+/// equal.
+pub(crate) fn normalize(distance: usize, len_a: usize, len_b: usize) -> f64 {
+    if len_a == 0 && len_b == 0 {
+        return 1.0;
+    }
+    1.0 - distance as f64 / len_a.max(len_b) as f64
+}
+
+/// strsim's `osa_distance` over any elements.
+pub(crate) fn osa_distance<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    if a == b {
+        return 0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return a.len().max(b.len());
+    }
+    let mut prev_two_distances: Vec<usize> = (0..=b.len()).collect();
+    let mut prev_distances: Vec<usize> = (0..=b.len()).collect();
+    let mut curr_distances: Vec<usize> = vec![0; b.len() + 1];
+    for (i, a_elem) in a.iter().enumerate() {
+        curr_distances[0] = i + 1;
+        for (j, b_elem) in b.iter().enumerate() {
+            let cost = if a_elem == b_elem { 0 } else { 1 };
+            curr_distances[j + 1] = min(
+                curr_distances[j] + 1,
+                min(prev_distances[j + 1] + 1, prev_distances[j] + cost),
+            );
+            if i > 0 && j > 0 && a_elem != b_elem && *a_elem == b[j - 1] && *b_elem == a[i - 1] {
+                curr_distances[j + 1] = min(curr_distances[j + 1], prev_two_distances[j - 1] + 1);
+            }
+        }
+        prev_two_distances.clone_from(&prev_distances);
+        prev_distances.clone_from(&curr_distances);
+    }
+    curr_distances[b.len()]
+}
@@ -0,0 +1,75 @@
+//! Jaro-Winkler with Winkler's parameters exposed. strsim's version boosts
+//! every pair by its whole common prefix, while the standard one caps the
+//! prefix at four characters and only boosts pairs already similar.
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+
+use super::generic::jaro;
+use super::{Text, Unit};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct JaroWinkler {
+    /// Boost per common prefix character.
+    prefix_weight: f64,
+    /// Longest prefix counted, or None for all of it.
+    max_prefix: Option<usize>,
+    /// Jaro similarity a pair must exceed to be boosted.
+    boost_threshold: f64,
+    unit: Unit,
+}
+
+impl Default for JaroWinkler {
+    fn default() -> Self {
+        JaroWinkler {
+            prefix_weight: 0.1,
+            max_prefix: Some(4),
+            boost_threshold: 0.7,
+            unit: Unit::Char,
+        }
+    }
+}
+
+impl JaroWinkler {
+    pub(crate) fn new(
+        prefix_weight: f64,
+        max_prefix: Option<usize>,
+        boost_threshold: f64,
+        unit: Unit,
+    ) -> PyResult<Self> {
+        if !(0.0..=1.0).contains(&prefix_weight) {
+            return Err(exceptions::PyValueError::new_err(
+                "prefix_weight must be between 0 and 1",
+            ));
+        }
+        if !(0.0..=1.0).contains(&boost_threshold) {
+            return Err(exceptions::PyValueError::new_err(
+                "boost_threshold must be between 0 and 1",
+            ));
+        }
+        Ok(JaroWinkler {
+            prefix_weight,
+            max_prefix,
+            boost_threshold,
+            unit,
+        })
+    }
+
+    pub(crate) fn similarity(&self, a: &Text, b: &Text) -> f64 {
+        let max_prefix = self.max_prefix.unwrap_or(usize::MAX);
+        with_units!(self.unit.pair(a, b), |a, b| {
+            let jaro = jaro(a, b);
+            if jaro <= self.boost_threshold {
+                return jaro;
+            }
+            let prefix = a.iter().zip(b).take_while(|(a, b)| a == b).take(max_prefix).count();
+            (jaro + self.prefix_weight * prefix as f64 * (1.0 - jaro)).min(1.0)
+        })
+    }
+}
+
+/// Standard Jaro-Winkler similarity: prefix weight 0.1, prefix capped at 4,
+/// pairs boosted above a Jaro similarity of 0.7.
+pub(crate) fn jaro_winkler(a: &Text, b: &Text) -> f64 {
+    JaroWinkler::default().similarity(a, b)
+}
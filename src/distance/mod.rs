@@ -0,0 +1,23 @@
+//! Distances and similarities behind the wrapper macros: strsim's, called
+//! through `Unit` to compare chars, grapheme clusters or bytes, plus the ones
+//! strsim lacks, implemented here under the same signatures. All take
+//! `Text`, a `str` or `bytes`.
+
+#[macro_use]
+mod unit;
+mod bag;
+mod editex;
+pub(crate) mod generic;
+mod jaro_winkler;
+mod ncd;
+mod qgram;
+mod sift4;
+mod text;
+
+pub(crate) use editex::{editex, normalized_editex};
+pub(crate) use jaro_winkler::{jaro_winkler, JaroWinkler};
+pub(crate) use ncd::Compressor;
+pub(crate) use qgram::{Measure, QGram};
+pub(crate) use sift4::Sift4;
+pub(crate) use text::Text;
+pub(crate) use unit::{Pair, Unit};
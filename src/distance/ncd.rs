@@ -0,0 +1,148 @@
+//! Normalized Compression Distance (Cilibrasi and Vitányi, 2005):
+//! `(C(ab) - min(C(a), C(b))) / max(C(a), C(b))`, where `C` is the
+//! compressed size. Strings sharing content compress better together.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use lzma_rust::{CountingWriter, LZMA2Options, LZMAWriter, DICT_SIZE_MIN};
+use pyo3::exceptions;
+use pyo3::prelude::*;
+
+use super::Text;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Compressor {
+    /// Deflate at level 9, with the zlib header and checksum.
+    Zlib,
+    /// Raw LZMA at preset 6, with a dictionary no larger than the input.
+    Lzma,
+    /// Size of an adaptive arithmetic coding of the bytes under an order-2
+    /// PPM model. No bits are emitted, only their number is computed.
+    Entropy,
+}
+
+impl Compressor {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "zlib" => Ok(Compressor::Zlib),
+            "lzma" => Ok(Compressor::Lzma),
+            "entropy" => Ok(Compressor::Entropy),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unknown compressor: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Compressed size of `data` in bytes, fractional for `Entropy`.
+    pub(crate) fn size(&self, data: &[u8]) -> f64 {
+        match self {
+            Compressor::Zlib => miniz_oxide::deflate::compress_to_vec_zlib(data, 9).len() as f64,
+            Compressor::Lzma => lzma_size(data) as f64,
+            Compressor::Entropy => entropy_bits(data) / 8.0,
+        }
+    }
+
+    /// Compressed size of `a` followed by `b`, the smaller of both orders so
+    /// that the distance is symmetric.
+    fn joint_size(&self, a: &[u8], b: &[u8]) -> f64 {
+        let mut joint = Vec::with_capacity(a.len() + b.len());
+        joint.extend_from_slice(a);
+        joint.extend_from_slice(b);
+        let ab = self.size(&joint);
+        joint.clear();
+        joint.extend_from_slice(b);
+        joint.extend_from_slice(a);
+        ab.min(self.size(&joint))
+    }
+
+    /// NCD of `a` and `b` given their compressed sizes, so that callers
+    /// comparing a string many times compress it once.
+    pub(crate) fn distance_with_sizes(&self, a: &[u8], size_a: f64, b: &[u8], size_b: f64) -> f64 {
+        let largest = size_a.max(size_b);
+        if largest == 0.0 {
+            return 0.0;
+        }
+        let joint = self.joint_size(a, b);
+        ((joint - size_a.min(size_b)) / largest).max(0.0)
+    }
+
+    pub(crate) fn distance(&self, a: &Text, b: &Text) -> f64 {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        let size_a = self.size(a);
+        let size_b = self.size(b);
+        self.distance_with_sizes(a, size_a, b, size_b)
+    }
+}
+
+fn lzma_size(data: &[u8]) -> usize {
+    let mut options = LZMA2Options::with_preset(6);
+    // The default 8 MiB dictionary would be allocated for every string.
+    options.dict_size = (data.len() as u32).next_power_of_two().max(DICT_SIZE_MIN);
+    let mut out = Vec::new();
+    let mut writer = LZMAWriter::new_no_header(CountingWriter::new(&mut out), &options, false)
+        .expect("LZMA options are valid");
+    writer.write_all(data).expect("writing to a Vec cannot fail");
+    writer.finish().expect("writing to a Vec cannot fail");
+    drop(writer);
+    out.len()
+}
+
+/// Occurrences of the bytes seen after a context, few enough for a scan.
+#[derive(Clone, Default)]
+struct Counts {
+    counts: Vec<(u8, u32)>,
+    total: u32,
+}
+
+impl Counts {
+    /// Bits to code `byte` after this context, `None` with the escape bits
+    /// if it has not been seen here, then counts it.
+    fn code(&mut self, byte: u8) -> (Option<f64>, f64) {
+        let distinct = self.counts.len() as f64;
+        let denominator = f64::from(self.total) + distinct;
+        self.total += 1;
+        match self.counts.iter_mut().find(|(seen, _)| *seen == byte) {
+            Some((_, count)) => {
+                let bits = -(f64::from(*count) / denominator).log2();
+                *count += 1;
+                (Some(bits), 0.0)
+            }
+            None => {
+                self.counts.push((byte, 1));
+                let escape = if distinct == 0.0 { 0.0 } else { -(distinct / denominator).log2() };
+                (None, escape)
+            }
+        }
+    }
+}
+
+/// Bits needed to code `data` with PPM method C over orders 2, 1 and 0,
+/// escaping to a uniform distribution over bytes. Every order is updated.
+fn entropy_bits(data: &[u8]) -> f64 {
+    let mut order2: HashMap<[u8; 2], Counts> = HashMap::new();
+    let mut order1 = vec![Counts::default(); 256];
+    let mut order0 = Counts::default();
+    let mut bits = 0.0;
+    for (i, &byte) in data.iter().enumerate() {
+        let mut models: Vec<&mut Counts> = Vec::with_capacity(3);
+        if i >= 2 {
+            models.push(order2.entry([data[i - 2], data[i - 1]]).or_default());
+        }
+        if i >= 1 {
+            models.push(&mut order1[usize::from(data[i - 1])]);
+        }
+        models.push(&mut order0);
+        let mut coded = None;
+        for counts in models {
+            let (hit, escape) = counts.code(byte);
+            if coded.is_none() {
+                bits += escape;
+                coded = hit;
+            }
+        }
+        bits += coded.unwrap_or(8.0);
+    }
+    bits
+}
@@ -0,0 +1,171 @@
+//! Similarities between the q-gram profiles of two strings.
+//!
+//! Whitespace is ignored, as in strsim's `sorensen_dice`, and lengths are
+//! counted in units, chars by default. With `multiset` a q-gram occurring
+//! twice counts twice, otherwise the profiles are sets. Sørensen-Dice over
+//! multisets divides by UTF-8 lengths as strsim does, unless `count_grams`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+
+use super::{Text, Unit};
+
+/// Start and end markers padding the units, of every kind of unit.
+trait Pad: Copy {
+    const START: Self;
+    const END: Self;
+}
+
+impl Pad for u8 {
+    const START: Self = 0x02;
+    const END: Self = 0x03;
+}
+
+impl Pad for char {
+    const START: Self = '\u{2}';
+    const END: Self = '\u{3}';
+}
+
+impl Pad for &str {
+    const START: Self = "\u{2}";
+    const END: Self = "\u{3}";
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Measure {
+    /// Twice the shared q-grams over the sizes of both profiles, each taken
+    /// as the q-grams of the UTF-8 bytes for multisets unless `count_grams`.
+    SorensenDice { count_grams: bool },
+    Jaccard,
+    Overlap,
+    Cosine,
+    /// Jaccard weighting the q-grams only in `a` by `alpha` and those only in
+    /// `b` by `beta`; `alpha = beta = 0.5` is Sørensen-Dice with `count_grams`.
+    Tversky { alpha: f64, beta: f64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct QGram {
+    measure: Measure,
+    q: usize,
+    /// Whether `q - 1` start and end markers are added, so that the first and
+    /// last characters appear in as many q-grams as the others.
+    pad: bool,
+    multiset: bool,
+    unit: Unit,
+}
+
+impl QGram {
+    pub(crate) fn new(measure: Measure, q: usize, pad: bool, multiset: bool, unit: Unit) -> PyResult<Self> {
+        if q == 0 {
+            return Err(exceptions::PyValueError::new_err("q must be at least 1"));
+        }
+        if let Measure::Tversky { alpha, beta } = measure {
+            if !(alpha >= 0.0 && beta >= 0.0) {
+                return Err(exceptions::PyValueError::new_err(
+                    "alpha and beta must be non-negative",
+                ));
+            }
+        }
+        Ok(QGram {
+            measure,
+            q,
+            pad,
+            multiset,
+            unit,
+        })
+    }
+
+    /// `measure` over bigrams counted as a multiset, the defaults.
+    pub(crate) fn bigrams(measure: Measure) -> Self {
+        QGram {
+            measure,
+            q: 2,
+            pad: false,
+            multiset: true,
+            unit: Unit::Char,
+        }
+    }
+
+    /// `units` padded with `q - 1` start and end markers if needed.
+    fn padded<T: Pad>(&self, units: &[T]) -> Vec<T> {
+        let padding = if self.pad { self.q - 1 } else { 0 };
+        let mut padded = vec![T::START; padding];
+        padded.extend_from_slice(units);
+        padded.resize(padded.len() + padding, T::END);
+        padded
+    }
+
+    fn profile<'a, T: Eq + Hash>(&self, units: &'a [T]) -> HashMap<&'a [T], f64> {
+        let mut grams = HashMap::new();
+        for gram in units.windows(self.q) {
+            let count = grams.entry(gram).or_insert(0.0);
+            if self.multiset || *count == 0.0 {
+                *count += 1.0;
+            }
+        }
+        grams
+    }
+
+    /// Similarity in `[0, 1]`. Equal strings score 1 and strings too short
+    /// for a single q-gram 0 otherwise.
+    pub(crate) fn similarity(&self, a: &Text, b: &Text) -> f64 {
+        let (a, b) = (a.without_whitespace(), b.without_whitespace());
+        let bytes = (a.as_bytes().len(), b.as_bytes().len());
+        with_units!(self.unit.pair(&a, &b), |a, b| self.score(&self.padded(a), &self.padded(b), bytes))
+    }
+
+    /// Number of q-grams in `bytes` UTF-8 bytes once padded, the size strsim
+    /// gives a bigram profile.
+    fn byte_size(&self, bytes: usize) -> f64 {
+        let padding = if self.pad { self.q - 1 } else { 0 };
+        (bytes + 2 * padding + 1 - self.q) as f64
+    }
+
+    fn score<T: Eq + Hash>(&self, a: &[T], b: &[T], bytes: (usize, usize)) -> f64 {
+        if a == b {
+            return 1.0;
+        }
+        let a = self.profile(a);
+        let b = self.profile(b);
+        if a.is_empty() || b.is_empty() {
+            return 0.0;
+        }
+        let size_a: f64 = a.values().sum();
+        let size_b: f64 = b.values().sum();
+        let shared = a
+            .iter()
+            .filter_map(|(gram, count)| b.get(gram).map(|other| count.min(*other)))
+            .fold(0.0, |shared, count| shared + count);
+        match self.measure {
+            Measure::SorensenDice { count_grams } => {
+                if count_grams || !self.multiset {
+                    2.0 * shared / (size_a + size_b)
+                } else {
+                    2.0 * shared / (self.byte_size(bytes.0) + self.byte_size(bytes.1))
+                }
+            }
+            Measure::Jaccard => shared / (size_a + size_b - shared),
+            Measure::Overlap => shared / size_a.min(size_b),
+            Measure::Cosine => {
+                let dot = a
+                    .iter()
+                    .filter_map(|(gram, count)| b.get(gram).map(|other| count * other))
+                    .fold(0.0, |dot, product| dot + product);
+                let norm = |profile: &HashMap<&[T], f64>| profile.values().map(|c| c * c).sum::<f64>().sqrt();
+                dot / (norm(&a) * norm(&b))
+            }
+            Measure::Tversky { alpha, beta } => {
+                let denominator = shared + alpha * (size_a - shared) + beta * (size_b - shared);
+                if denominator == 0.0 {
+                    0.0
+                } else {
+                    shared / denominator
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Sift4 (Siderite Zackwehdex, 2014), a linear-time approximation of edit
+//! distance: characters are matched while walking both strings, looking
+//! ahead at most `max_offset` characters after a mismatch.
+
+use super::{Text, Unit};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Sift4 {
+    max_offset: usize,
+    /// Distance from which the walk stops early, returning a value above it.
+    max_distance: Option<usize>,
+    unit: Unit,
+}
+
+struct Offset {
+    c1: isize,
+    c2: isize,
+    transposition: bool,
+}
+
+impl Sift4 {
+    pub(crate) fn new(max_offset: usize, max_distance: Option<usize>, unit: Unit) -> Self {
+        Sift4 {
+            max_offset,
+            max_distance,
+            unit,
+        }
+    }
+
+    fn exceeds(&self, distance: usize) -> bool {
+        self.max_distance.is_some_and(|max_distance| distance > max_distance)
+    }
+
+    /// Sift4 without transpositions: the length of the longest string minus
+    /// the characters matched.
+    pub(crate) fn simple(&self, a: &Text, b: &Text) -> usize {
+        with_units!(self.unit.pair(a, b), |a, b| self.generic_simple(a, b))
+    }
+
+    /// Sift4 counting transpositions, i.e. matched characters that appear in
+    /// a different order in the two strings, once each.
+    pub(crate) fn general(&self, a: &Text, b: &Text) -> usize {
+        with_units!(self.unit.pair(a, b), |a, b| self.generic_general(a, b))
+    }
+
+    fn generic_simple<T: PartialEq>(&self, a: &[T], b: &[T]) -> usize {
+        let (l1, l2) = (a.len(), b.len());
+        if l1 == 0 || l2 == 0 {
+            return l1.max(l2);
+        }
+        let (mut c1, mut c2) = (0, 0);
+        let mut lcss = 0;
+        let mut local_cs = 0;
+        while c1 < l1 && c2 < l2 {
+            if a[c1] == b[c2] {
+                local_cs += 1;
+            } else {
+                lcss += local_cs;
+                local_cs = 0;
+                if c1 != c2 {
+                    c1 = c1.max(c2);
+                    c2 = c1;
+                }
+                let distance = c1.max(c2).saturating_sub(lcss);
+                if self.exceeds(distance) {
+                    return distance;
+                }
+                for i in 0..self.max_offset {
+                    if c1 + i >= l1 && c2 + i >= l2 {
+                        break;
+                    }
+                    if c1 + i < l1 && c2 < l2 && a[c1 + i] == b[c2] {
+                        c1 += i;
+                        local_cs += 1;
+                        break;
+                    }
+                    if c2 + i < l2 && c1 < l1 && a[c1] == b[c2 + i] {
+                        c2 += i;
+                        local_cs += 1;
+                        break;
+                    }
+                }
+            }
+            c1 += 1;
+            c2 += 1;
+        }
+        lcss += local_cs;
+        l1.max(l2).saturating_sub(lcss)
+    }
+
+    fn generic_general<T: PartialEq>(&self, a: &[T], b: &[T]) -> usize {
+        let (l1, l2) = (a.len() as isize, b.len() as isize);
+        if l1 == 0 || l2 == 0 {
+            return l1.max(l2) as usize;
+        }
+        let max_offset = self.max_offset as isize;
+        let (mut c1, mut c2): (isize, isize) = (0, 0);
+        let mut lcss: isize = 0;
+        let mut local_cs: isize = 0;
+        let mut transpositions: isize = 0;
+        let mut offsets: Vec<Offset> = Vec::new();
+        let distance = |c1: isize, c2: isize, lcss: isize, transpositions: isize| {
+            (c1.max(c2) - lcss + transpositions).max(0) as usize
+        };
+        while c1 < l1 && c2 < l2 {
+            if a[c1 as usize] == b[c2 as usize] {
+                local_cs += 1;
+                let mut transposition = false;
+                let mut i = 0;
+                while i < offsets.len() {
+                    let offset = &mut offsets[i];
+                    if c1 <= offset.c1 || c2 <= offset.c2 {
+                        transposition = (c2 - c1).abs() >= (offset.c2 - offset.c1).abs();
+                        if transposition {
+                            transpositions += 1;
+                        } else if !offset.transposition {
+                            offset.transposition = true;
+                            transpositions += 1;
+                        }
+                        break;
+                    } else if c1 > offset.c2 && c2 > offset.c1 {
+                        offsets.remove(i);
+                    } else {
+                        i += 1;
+                    }
+                }
+                offsets.push(Offset {
+                    c1,
+                    c2,
+                    transposition,
+                });
+            } else {
+                lcss += local_cs;
+                local_cs = 0;
+                if c1 != c2 {
+                    c1 = c1.min(c2);
+                    c2 = c1;
+                }
+                let current = distance(c1, c2, lcss, transpositions);
+                if self.exceeds(current) {
+                    return current;
+                }
+                for i in 0..max_offset {
+                    if c1 + i >= l1 && c2 + i >= l2 {
+                        break;
+                    }
+                    if c1 + i < l1 && a[(c1 + i) as usize] == b[c2 as usize] {
+                        c1 += i - 1;
+                        c2 -= 1;
+                        break;
+                    }
+                    if c2 + i < l2 && a[c1 as usize] == b[(c2 + i) as usize] {
+                        c1 -= 1;
+                        c2 += i - 1;
+                        break;
+                    }
+                }
+            }
+            c1 += 1;
+            c2 += 1;
+            let current = distance(c1, c2, lcss, transpositions);
+            if self.exceeds(current) {
+                return current;
+            }
+            // Flush the last match so that its transpositions are counted.
+            if c1 >= l1 || c2 >= l2 {
+                lcss += local_cs;
+                local_cs = 0;
+                c1 = c1.min(c2);
+                c2 = c1;
+            }
+        }
+        lcss += local_cs;
+        distance(l1, l2, lcss, transpositions)
+    }
+}
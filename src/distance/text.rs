@@ -0,0 +1,77 @@
+//! What the metrics compare: a `str`, or a `bytes` object compared byte by
+//! byte, e.g. binary identifiers. A `str` compared with `bytes` is taken as
+//! its UTF-8 encoding.
+
+use std::borrow::Cow;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyString};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Text<'a> {
+    Str(Cow<'a, str>),
+    Bytes(Cow<'a, [u8]>),
+}
+
+impl<'a> Text<'a> {
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        match self {
+            Text::Str(s) => s.as_bytes(),
+            Text::Bytes(bytes) => bytes,
+        }
+    }
+
+    /// The same text, borrowed from `self`.
+    pub(crate) fn borrowed(&self) -> Text<'_> {
+        match self {
+            Text::Str(s) => Text::Str(Cow::Borrowed(s)),
+            Text::Bytes(bytes) => Text::Bytes(Cow::Borrowed(bytes)),
+        }
+    }
+
+    pub(crate) fn into_owned(self) -> Text<'static> {
+        match self {
+            Text::Str(s) => Text::Str(Cow::Owned(s.into_owned())),
+            Text::Bytes(bytes) => Text::Bytes(Cow::Owned(bytes.into_owned())),
+        }
+    }
+
+    pub(crate) fn to_object(&self, py: Python) -> PyObject {
+        match self {
+            Text::Str(s) => s.as_ref().into_py(py),
+            Text::Bytes(bytes) => PyBytes::new(py, bytes).into(),
+        }
+    }
+
+    /// The text without whitespace, ASCII whitespace for bytes.
+    pub(crate) fn without_whitespace(&self) -> Text<'static> {
+        match self {
+            Text::Str(s) => Text::Str(Cow::Owned(s.chars().filter(|c| !c.is_whitespace()).collect())),
+            Text::Bytes(bytes) => Text::Bytes(Cow::Owned(
+                bytes.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect(),
+            )),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Text<'a> {
+    fn from(s: &'a str) -> Self {
+        Text::Str(Cow::Borrowed(s))
+    }
+}
+
+impl<'a> FromPyObject<'a> for Text<'a> {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if let Ok(s) = ob.downcast::<PyString>() {
+            return Ok(Text::Str(Cow::Borrowed(s.to_str()?)));
+        }
+        if let Ok(bytes) = ob.downcast::<PyBytes>() {
+            return Ok(Text::Bytes(Cow::Borrowed(bytes.as_bytes())));
+        }
+        Err(exceptions::PyTypeError::new_err(format!(
+            "expected str or bytes, got {}",
+            ob.get_type().name()?
+        )))
+    }
+}
@@ -0,0 +1,118 @@
+//! The unit strings are compared by. strsim works on `char`s, so an emoji
+//! with a skin-tone modifier or a Devanagari conjunct counts as several
+//! edits; comparing extended grapheme clusters counts them as one.
+//!
+//! Two strings are split into the cheapest units that compare as the unit
+//! asked for: all-ASCII strings are compared byte by byte whatever the unit,
+//! without decoding UTF-8, and so are `bytes`.
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use strsim::StrSimError;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{generic, Text};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Unit {
+    Char,
+    Grapheme,
+    Byte,
+}
+
+/// Units of two texts, of the same kind for both.
+pub(crate) enum Pair<'a> {
+    Bytes(&'a [u8], &'a [u8]),
+    Chars(Vec<char>, Vec<char>),
+    Graphemes(Vec<&'a str>, Vec<&'a str>),
+}
+
+/// Evaluates `$body` with `$a` and `$b` bound to the units of `$pair` as
+/// slices, so that generic code runs on whichever kind they are.
+macro_rules! with_units {
+    ($pair:expr, |$a:ident, $b:ident| $body:expr) => {
+        match $pair {
+            $crate::distance::Pair::Bytes($a, $b) => $body,
+            $crate::distance::Pair::Chars($a, $b) => {
+                let ($a, $b): (&[char], &[char]) = (&$a, &$b);
+                $body
+            }
+            $crate::distance::Pair::Graphemes($a, $b) => {
+                let ($a, $b): (&[&str], &[&str]) = (&$a, &$b);
+                $body
+            }
+        }
+    };
+}
+
+impl Unit {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "char" => Ok(Unit::Char),
+            "grapheme" => Ok(Unit::Grapheme),
+            "byte" => Ok(Unit::Byte),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unknown unit: {}",
+                name
+            ))),
+        }
+    }
+
+    /// `a` and `b` split into units. ASCII chars are single bytes, and so
+    /// are ASCII grapheme clusters but for "\r\n".
+    pub(crate) fn pair<'a>(&self, a: &'a Text, b: &'a Text) -> Pair<'a> {
+        let (a, b) = match (a, b) {
+            (Text::Str(a), Text::Str(b)) => (a.as_ref(), b.as_ref()),
+            _ => return Pair::Bytes(a.as_bytes(), b.as_bytes()),
+        };
+        let ascii = a.is_ascii() && b.is_ascii();
+        match self {
+            Unit::Char if !ascii => Pair::Chars(a.chars().collect(), b.chars().collect()),
+            Unit::Grapheme if !ascii || a.contains("\r\n") || b.contains("\r\n") => {
+                Pair::Graphemes(a.graphemes(true).collect(), b.graphemes(true).collect())
+            }
+            _ => Pair::Bytes(a.as_bytes(), b.as_bytes()),
+        }
+    }
+
+    pub(crate) fn hamming(&self, a: &Text, b: &Text) -> Result<usize, StrSimError> {
+        with_units!(self.pair(a, b), |a, b| strsim::generic_hamming(a, b))
+    }
+
+    pub(crate) fn levenshtein(&self, a: &Text, b: &Text) -> usize {
+        with_units!(self.pair(a, b), |a, b| generic::levenshtein(a, b))
+    }
+
+    pub(crate) fn normalized_levenshtein(&self, a: &Text, b: &Text) -> f64 {
+        with_units!(self.pair(a, b), |a, b| generic::normalized_levenshtein(a, b))
+    }
+
+    pub(crate) fn osa_distance(&self, a: &Text, b: &Text) -> usize {
+        with_units!(self.pair(a, b), |a, b| generic::osa_distance(a, b))
+    }
+
+    pub(crate) fn damerau_levenshtein(&self, a: &Text, b: &Text) -> usize {
+        damerau_levenshtein(self.pair(a, b))
+    }
+
+    pub(crate) fn normalized_damerau_levenshtein(&self, a: &Text, b: &Text) -> f64 {
+        let pair = self.pair(a, b);
+        let (len_a, len_b) = with_units!(&pair, |a, b| (a.len(), b.len()));
+        generic::normalize(damerau_levenshtein(pair), len_a, len_b)
+    }
+
+    pub(crate) fn jaro(&self, a: &Text, b: &Text) -> f64 {
+        with_units!(self.pair(a, b), |a, b| generic::jaro(a, b))
+    }
+
+    pub(crate) fn bag_distance(&self, a: &Text, b: &Text) -> usize {
+        with_units!(self.pair(a, b), |a, b| super::bag::generic_bag_distance(a, b))
+    }
+}
+
+fn damerau_levenshtein(pair: Pair) -> usize {
+    match pair {
+        Pair::Bytes(a, b) => generic::damerau_levenshtein_bytes(a, b),
+        pair => with_units!(pair, |a, b| generic::damerau_levenshtein(a, b)),
+    }
+}
@@ -0,0 +1,139 @@
+//! Best matches of a query among choices given as a list, a dict or any
+//! mapping with ``items()`` such as a pandas Series, returned with the key
+//! of each choice: its position, dict key or index label.
+
+use std::cmp::Ordering;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use pyo3::types::PyFloat;
+
+use crate::distance::Text;
+use crate::metric::{Scorer, Similarity};
+use crate::process::{self, Preprocessor};
+
+/// A match: the choice as given, its score and its key.
+type Match = (PyObject, f64, PyObject);
+
+/// Choices with their keys. Missing values, None or NaN as pandas has them,
+/// are skipped.
+fn choices<'py>(py: Python<'py>, choices: &'py PyAny) -> PyResult<Vec<(PyObject, &'py PyAny)>> {
+    let pairs: Vec<(PyObject, &PyAny)> = if choices.hasattr("items")? {
+        choices
+            .call_method0("items")?
+            .iter()?
+            .map(|item| item?.extract::<(PyObject, &PyAny)>())
+            .collect::<PyResult<_>>()?
+    } else {
+        choices
+            .iter()?
+            .enumerate()
+            .map(|(i, choice)| Ok((i.into_py(py), choice?)))
+            .collect::<PyResult<_>>()?
+    };
+    Ok(pairs
+        .into_iter()
+        .filter(|(_, choice)| !is_missing(choice))
+        .collect())
+}
+
+fn is_missing(choice: &PyAny) -> bool {
+    choice.is_none() || choice.downcast::<PyFloat>().is_ok_and(|f| f.value().is_nan())
+}
+
+/// Choices scoring at least `min_similarity` against `query`, best first and
+/// in the order given among equal scores.
+fn matches(
+    py: Python,
+    query: Text,
+    choices: &PyAny,
+    scorer: Scorer,
+    processor: Option<Preprocessor>,
+    min_similarity: f64,
+) -> PyResult<Vec<Match>> {
+    if min_similarity.is_nan() {
+        return Err(exceptions::PyValueError::new_err("min_similarity must not be NaN"));
+    }
+    let choices = self::choices(py, choices)?;
+    let texts = choices
+        .iter()
+        .map(|(_, choice)| choice.extract())
+        .collect::<PyResult<Vec<Text>>>()?;
+    let query = [query];
+    let (query, texts) = process::apply_pair(&processor, &query, &texts)?;
+    let scores = scorer.score_all(py, &query, &texts)?.remove(0);
+    let mut matches: Vec<Match> = choices
+        .into_iter()
+        .zip(scores)
+        .filter(|&(_, score)| score >= min_similarity)
+        .map(|((key, choice), score)| (choice.into_py(py), score, key))
+        .collect();
+    matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    Ok(matches)
+}
+
+/// extract_one(query, choices, scorer="normalized_levenshtein", processor=None, min_similarity=0.0)
+///
+/// Finds the choice most similar to the query. Choices are a list, a dict or
+/// a pandas Series; None and NaN choices are skipped.
+///
+/// :param str|bytes query: string to match
+/// :param choices: list of strings, dict of strings or pandas Series
+/// :param scorer: similarity name accepted by :py:meth:`VPTree.build`, or a
+///     Python callable taking two strings and returning a float, higher for
+///     more similar strings
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param float min_similarity: lowest score returned
+/// :return: ``(choice, score, key)`` where key is the position in a list, the
+///     dict key or the Series index label, None if no choice scores enough
+/// :rtype: tuple
+/// :raises ValueError: if the scorer is unknown or min_similarity is NaN
+#[pyfunction]
+#[pyo3(signature = (query, choices, scorer=Scorer::Rust(Similarity::NormalizedLevenshtein), processor=None, min_similarity=0.0))]
+pub(crate) fn extract_one(
+    py: Python,
+    query: Text,
+    choices: &PyAny,
+    scorer: Scorer,
+    processor: Option<Preprocessor>,
+    min_similarity: f64,
+) -> PyResult<Option<Match>> {
+    Ok(matches(py, query, choices, scorer, processor, min_similarity)?
+        .into_iter()
+        .next())
+}
+
+/// extract(query, choices, limit=5, scorer="normalized_levenshtein", processor=None, min_similarity=0.0)
+///
+/// Finds the ``limit`` choices most similar to the query, like
+/// :py:func:`extract_one`.
+///
+/// :param str|bytes query: string to match
+/// :param choices: list of strings, dict of strings or pandas Series
+/// :param int limit: matches returned, None for all
+/// :param scorer: similarity name accepted by :py:meth:`VPTree.build`, or a
+///     Python callable taking two strings and returning a float, higher for
+///     more similar strings
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param float min_similarity: lowest score returned
+/// :return: ``(choice, score, key)`` tuples, best first and in the order of
+///     the choices among equal scores
+/// :rtype: list
+/// :raises ValueError: if the scorer is unknown or min_similarity is NaN
+#[pyfunction]
+#[pyo3(signature = (query, choices, limit=5, scorer=Scorer::Rust(Similarity::NormalizedLevenshtein), processor=None, min_similarity=0.0))]
+pub(crate) fn extract(
+    py: Python,
+    query: Text,
+    choices: &PyAny,
+    limit: Option<usize>,
+    scorer: Scorer,
+    processor: Option<Preprocessor>,
+    min_similarity: f64,
+) -> PyResult<Vec<Match>> {
+    let mut matches = matches(py, query, choices, scorer, processor, min_similarity)?;
+    if let Some(limit) = limit {
+        matches.truncate(limit);
+    }
+    Ok(matches)
+}
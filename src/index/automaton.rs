@@ -0,0 +1,250 @@
+//! Levenshtein automata for enumerating matches in sorted dictionaries.
+//!
+//! The DFA accepts every string within `max_distance` of the query and reports
+//! its exact distance, so a sorted word list or an `fst` set can be intersected
+//! with it while skipping whole subtrees whose prefix is already too far away.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use fst::{IntoStreamer, Streamer};
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder, DFA, SINK_STATE};
+use pyo3::exceptions;
+use pyo3::prelude::*;
+
+use super::storage::Buffer;
+use super::{Hit, Score};
+
+/// Largest distance accepted; building the parametric automaton grows
+/// exponentially with it.
+const MAX_DISTANCE: u8 = 4;
+
+type Builders = HashMap<(u8, bool), Arc<LevenshteinAutomatonBuilder>>;
+
+/// Parametric automata are independent of the query, so they are built once
+/// per `(max_distance, transpositions)` and shared.
+fn builder(max_distance: u8, transpositions: bool) -> Arc<LevenshteinAutomatonBuilder> {
+    static BUILDERS: OnceLock<Mutex<Builders>> = OnceLock::new();
+    let mut builders = BUILDERS
+        .get_or_init(Default::default)
+        .lock()
+        .expect("builder cache poisoned");
+    builders
+        .entry((max_distance, transpositions))
+        .or_insert_with(|| Arc::new(LevenshteinAutomatonBuilder::new(max_distance, transpositions)))
+        .clone()
+}
+
+fn exact(distance: Distance) -> Option<usize> {
+    match distance {
+        Distance::Exact(d) => Some(d as usize),
+        Distance::AtLeast(_) => None,
+    }
+}
+
+/// LevenshteinAutomaton(query, max_distance, transpositions=False, prefix=False)
+///
+/// Deterministic automaton accepting every string within ``max_distance`` of
+/// ``query``. Distances match :py:func:`levenshtein`, or
+/// :py:func:`osa_distance` when ``transpositions`` is set. With ``prefix``
+/// set, a string matches if any of its prefixes is within ``max_distance``,
+/// which is what fuzzy autocomplete needs.
+///
+/// :param str query: string to match against
+/// :param int max_distance: maximum distance, at most 4
+/// :param bool transpositions: count adjacent transpositions as one edit
+/// :param bool prefix: match strings starting with something close to the query
+/// :raises ValueError: if max_distance is larger than 4
+#[pyclass(module = "xdistances")]
+pub(crate) struct LevenshteinAutomaton {
+    pub(crate) query: String,
+    pub(crate) max_distance: u8,
+    pub(crate) transpositions: bool,
+    pub(crate) prefix: bool,
+    dfa: DFA,
+}
+
+impl LevenshteinAutomaton {
+    pub(crate) fn new(query: &str, max_distance: usize, transpositions: bool, prefix: bool) -> PyResult<Self> {
+        if max_distance > MAX_DISTANCE as usize {
+            return Err(exceptions::PyValueError::new_err(format!(
+                "max_distance must be at most {}",
+                MAX_DISTANCE
+            )));
+        }
+        let max_distance = max_distance as u8;
+        let builder = builder(max_distance, transpositions);
+        let dfa = if prefix {
+            builder.build_prefix_dfa(query)
+        } else {
+            builder.build_dfa(query)
+        };
+        Ok(LevenshteinAutomaton { query: query.to_string(), max_distance, transpositions, prefix, dfa })
+    }
+
+    /// Walks a sorted list, reusing the states of the prefix shared with the
+    /// previous word and skipping every word below a dead prefix.
+    fn intersect_sorted(&self, words: &[&str]) -> Vec<Hit> {
+        let mut hits = Vec::new();
+        // states[i] is the state reached after the first i bytes of `previous`.
+        let mut states = vec![self.dfa.initial_state()];
+        let mut previous: &[u8] = b"";
+        let mut i = 0;
+        while i < words.len() {
+            let word = words[i].as_bytes();
+            let shared = previous
+                .iter()
+                .zip(word)
+                .take_while(|(a, b)| a == b)
+                .count()
+                .min(states.len() - 1);
+            states.truncate(shared + 1);
+            let mut dead = None;
+            for (offset, &byte) in word[shared..].iter().enumerate() {
+                let state = self.dfa.transition(*states.last().expect("initial state"), byte);
+                states.push(state);
+                if state == SINK_STATE {
+                    dead = Some(shared + offset + 1);
+                    break;
+                }
+            }
+            previous = word;
+            if let Some(length) = dead {
+                let dead_prefix = &word[..length];
+                i += 1 + words[i + 1..].partition_point(|w| w.as_bytes().starts_with(dead_prefix));
+                continue;
+            }
+            let state = *states.last().expect("initial state");
+            if let Some(distance) = exact(self.dfa.distance(state)) {
+                hits.push((i, words[i].to_string(), Score::Distance(distance)));
+            }
+            i += 1;
+        }
+        hits
+    }
+}
+
+#[pymethods]
+impl LevenshteinAutomaton {
+    #[new]
+    #[pyo3(signature = (query, max_distance, transpositions=false, prefix=false))]
+    fn py_new(query: &str, max_distance: usize, transpositions: bool, prefix: bool) -> PyResult<Self> {
+        LevenshteinAutomaton::new(query, max_distance, transpositions, prefix)
+    }
+
+    /// distance(word)
+    ///
+    /// Runs the automaton over a single string.
+    ///
+    /// :param str word: string to compare
+    /// :return: distance, or None if it is larger than max_distance
+    /// :rtype: int
+    fn distance(&self, word: &str) -> Option<usize> {
+        exact(self.dfa.eval(word))
+    }
+
+    /// search_sorted(words)
+    ///
+    /// Enumerates the matches in a list sorted in ascending order. Words sharing
+    /// a prefix that can no longer match are skipped without being read.
+    ///
+    /// :param list words: sorted list of strings
+    /// :return: list of ``(index, word, distance)`` tuples in list order
+    /// :rtype: list
+    /// :raises ValueError: if the list is not sorted
+    fn search_sorted(&self, py: Python, words: Vec<&str>) -> PyResult<Vec<Hit>> {
+        if words.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(exceptions::PyValueError::new_err("words must be sorted"));
+        }
+        Ok(py.allow_threads(|| self.intersect_sorted(&words)))
+    }
+
+    /// search_fst(path)
+    ///
+    /// Enumerates the matching keys of an ``fst`` set or map stored on disk,
+    /// e.g. one written by :py:func:`build_fst_set`. The file is mapped
+    /// read-only rather than read, so a search only pages in the states it
+    /// visits; the file must not change while in use.
+    ///
+    /// :param str path: path to the fst file
+    /// :return: list of ``(key, distance)`` tuples in key order
+    /// :rtype: list
+    /// :raises IOError: if the file cannot be read
+    /// :raises ValueError: if the file is not a valid fst
+    fn search_fst(&self, py: Python, path: &str) -> PyResult<Vec<(String, usize)>> {
+        let set = fst::raw::Fst::new(Buffer::open(path, true)?)
+            .map_err(|e| exceptions::PyValueError::new_err(e.to_string()))?;
+        Ok(py.allow_threads(|| {
+            let mut hits = Vec::new();
+            let mut stream = set.search_with_state(&self.dfa).into_stream();
+            while let Some((key, _, state)) = stream.next() {
+                if let Some(distance) = exact(self.dfa.distance(state)) {
+                    hits.push((String::from_utf8_lossy(key).into_owned(), distance));
+                }
+            }
+            hits
+        }))
+    }
+
+    fn __getstate__(&self) -> (String, u8, bool, bool) {
+        (self.query.clone(), self.max_distance, self.transpositions, self.prefix)
+    }
+
+    fn __setstate__(&mut self, state: (&str, usize, bool, bool)) -> PyResult<()> {
+        let (query, max_distance, transpositions, prefix) = state;
+        *self = LevenshteinAutomaton::new(query, max_distance, transpositions, prefix)?;
+        Ok(())
+    }
+
+    // The DFA is rebuilt from the constructor arguments rather than pickled.
+    fn __reduce__(slf: &PyCell<Self>) -> (PyObject, (String, u8, bool, bool)) {
+        (slf.get_type().into(), slf.borrow().__getstate__())
+    }
+
+    #[getter]
+    fn query(&self) -> &str {
+        &self.query
+    }
+
+    #[getter]
+    fn max_distance(&self) -> u8 {
+        self.max_distance
+    }
+
+    #[getter]
+    fn transpositions(&self) -> bool {
+        self.transpositions
+    }
+
+    #[getter]
+    fn prefix(&self) -> bool {
+        self.prefix
+    }
+}
+
+/// build_fst_set(words, path)
+///
+/// Writes an ``fst`` set containing ``words`` to ``path``, sorting and
+/// removing duplicates first, for use with
+/// :py:meth:`LevenshteinAutomaton.search_fst`.
+///
+/// :param list words: strings to store
+/// :param str path: destination file
+/// :return: number of distinct words written
+/// :rtype: int
+/// :raises IOError: if the file cannot be written
+#[pyfunction]
+pub(crate) fn build_fst_set(py: Python, mut words: Vec<&str>, path: &str) -> PyResult<usize> {
+    py.allow_threads(|| {
+        words.sort_unstable();
+        words.dedup();
+        let writer = BufWriter::new(File::create(path)?);
+        let to_io_error = |e: fst::Error| exceptions::PyIOError::new_err(e.to_string());
+        let mut builder = fst::SetBuilder::new(writer).map_err(to_io_error)?;
+        builder.extend_iter(words.iter()).map_err(to_io_error)?;
+        builder.finish().map_err(to_io_error)?;
+        Ok(words.len())
+    })
+}
@@ -0,0 +1,552 @@
+//! MinHash signatures and banded locality-sensitive hashing.
+//!
+//! Each document is reduced to the set of its shingles, and each of the
+//! `num_perm` hash functions keeps the minimum hash over that set. Two
+//! documents agree on a signature position with probability equal to their
+//! Jaccard similarity, so splitting signatures into `bands` and bucketing each
+//! band yields candidate pairs that are then verified exactly.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rayon::prelude::*;
+
+use super::storage::{self, Array, Buffer, IntKeys, Kind, Reader, Strings, Writer};
+use super::{fnv1a, sort_hits, Hit, Score};
+use crate::distance::{Measure, QGram};
+
+/// Mersenne prime 2^61 - 1, the modulus of the universal hash family.
+const PRIME: u64 = (1 << 61) - 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Metric {
+    Jaccard,
+    SorensenDice,
+}
+
+impl Metric {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "jaccard" => Ok(Metric::Jaccard),
+            "sorensen_dice" => Ok(Metric::SorensenDice),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unsupported metric for MinHashLSH: {}",
+                name
+            ))),
+        }
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Shingling and hashing parameters shared by signatures and indexes.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MinHasher {
+    pub(crate) num_perm: usize,
+    pub(crate) shingle_size: usize,
+    pub(crate) words: bool,
+    pub(crate) seed: u64,
+    /// `(a, b)` coefficients of the permutations `(a * x + b) mod PRIME`.
+    permutations: Vec<(u64, u64)>,
+}
+
+impl MinHasher {
+    pub(crate) fn new(num_perm: usize, shingle_size: usize, words: bool, seed: u64) -> PyResult<Self> {
+        if num_perm == 0 || shingle_size == 0 {
+            return Err(exceptions::PyValueError::new_err(
+                "num_perm and shingle_size must be at least 1",
+            ));
+        }
+        let mut state = seed;
+        let permutations = (0..num_perm)
+            .map(|_| {
+                let a = splitmix64(&mut state) % (PRIME - 1) + 1;
+                let b = splitmix64(&mut state) % PRIME;
+                (a, b)
+            })
+            .collect();
+        Ok(MinHasher { num_perm, shingle_size, words, seed, permutations })
+    }
+
+    /// Hashes of the character or word shingles of `text`. Texts shorter
+    /// than one shingle are a single shingle.
+    pub(crate) fn shingles(&self, text: &str) -> HashSet<u64> {
+        let mut shingles = HashSet::new();
+        if self.words {
+            let tokens: Vec<&str> = text.split_whitespace().collect();
+            if tokens.len() <= self.shingle_size {
+                shingles.insert(fnv1a(tokens.join(" ").as_bytes()));
+            } else {
+                for window in tokens.windows(self.shingle_size) {
+                    shingles.insert(fnv1a(window.join(" ").as_bytes()));
+                }
+            }
+        } else {
+            let boundaries: Vec<usize> = text
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain(std::iter::once(text.len()))
+                .collect();
+            if boundaries.len() <= self.shingle_size + 1 {
+                shingles.insert(fnv1a(text.as_bytes()));
+            } else {
+                for window in boundaries.windows(self.shingle_size + 1) {
+                    shingles.insert(fnv1a(&text.as_bytes()[window[0]..window[self.shingle_size]]));
+                }
+            }
+        }
+        shingles
+    }
+
+    pub(crate) fn signature(&self, text: &str) -> Vec<u64> {
+        let shingles = self.shingles(text);
+        self.permutations
+            .iter()
+            .map(|&(a, b)| {
+                shingles
+                    .iter()
+                    .map(|&x| ((a as u128 * (x % PRIME) as u128 + b as u128) % PRIME as u128) as u64)
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect()
+    }
+
+    pub(crate) fn jaccard(&self, a: &str, b: &str) -> f64 {
+        let (a, b) = (self.shingles(a), self.shingles(b));
+        let union = a.union(&b).count();
+        if union == 0 {
+            return 1.0;
+        }
+        a.intersection(&b).count() as f64 / union as f64
+    }
+}
+
+/// minhash(text, num_perm=128, shingle_size=3, words=False, seed=1)
+///
+/// Calculates the MinHash signature of a string over its character (or, with
+/// ``words`` set, word) shingles. The fraction of positions on which two
+/// signatures agree estimates the Jaccard similarity of the shingle sets.
+///
+/// :param str text: string to hash
+/// :param int num_perm: number of hash functions
+/// :param int shingle_size: characters (or words) per shingle
+/// :param bool words: shingle over whitespace-separated words
+/// :param int seed: seed of the hash functions
+/// :return: signature
+/// :rtype: list
+#[pyfunction]
+#[pyo3(signature = (text, num_perm=128, shingle_size=3, words=false, seed=1))]
+pub(crate) fn minhash(text: &str, num_perm: usize, shingle_size: usize, words: bool, seed: u64) -> PyResult<Vec<u64>> {
+    Ok(MinHasher::new(num_perm, shingle_size, words, seed)?.signature(text))
+}
+
+/// minhash_parallel(texts, num_perm=128, shingle_size=3, words=False, seed=1)
+///
+/// Calculates MinHash signatures. Operates in parallel over a list of strings.
+///
+/// :param list texts: strings to hash
+/// :param int num_perm: number of hash functions
+/// :param int shingle_size: characters (or words) per shingle
+/// :param bool words: shingle over whitespace-separated words
+/// :param int seed: seed of the hash functions
+/// :return: one signature per string
+/// :rtype: list
+#[pyfunction]
+#[pyo3(signature = (texts, num_perm=128, shingle_size=3, words=false, seed=1))]
+pub(crate) fn minhash_parallel(
+    py: Python,
+    texts: Vec<&str>,
+    num_perm: usize,
+    shingle_size: usize,
+    words: bool,
+    seed: u64,
+) -> PyResult<Vec<Vec<u64>>> {
+    let hasher = MinHasher::new(num_perm, shingle_size, words, seed)?;
+    Ok(py.allow_threads(|| texts.par_iter().map(|t| hasher.signature(t)).collect()))
+}
+
+/// MinHashLSH
+///
+/// Banded locality-sensitive hashing index over MinHash signatures. Build it
+/// with :py:meth:`MinHashLSH.build`, then list near-duplicate pairs with
+/// :py:meth:`MinHashLSH.near_duplicates` or look up new strings with
+/// :py:meth:`MinHashLSH.query`. A pair becomes a candidate when all
+/// ``num_perm / bands`` rows of at least one band agree, i.e. with
+/// probability ``1 - (1 - s^rows)^bands`` for Jaccard similarity ``s``.
+#[pyclass(module = "xdistances")]
+pub(crate) struct MinHashLSH {
+    pub(crate) hasher: MinHasher,
+    pub(crate) bands: usize,
+    /// File or pickle the index was read from, `None` when built in memory.
+    buffer: Option<Buffer>,
+    documents: Strings,
+    /// Bucket keys, the hashes of a band's rows, sorted within each band.
+    buckets: IntKeys,
+    bucket_ids: Array<u32>,
+    /// Range of `buckets` belonging to each band.
+    band_offsets: Array<u64>,
+}
+
+impl MinHashLSH {
+    pub(crate) fn new(documents: Vec<String>, hasher: MinHasher, bands: usize) -> PyResult<Self> {
+        if bands == 0 || hasher.num_perm / bands * bands != hasher.num_perm {
+            return Err(exceptions::PyValueError::new_err("bands must divide num_perm"));
+        }
+        if documents.len() > u32::MAX as usize {
+            return Err(exceptions::PyValueError::new_err("Too many documents to index"));
+        }
+        let signatures: Vec<Vec<u64>> = documents.par_iter().map(|d| hasher.signature(d)).collect();
+        let rows = hasher.num_perm / bands;
+        let buckets: Vec<Vec<(u64, Vec<u32>)>> = (0..bands)
+            .into_par_iter()
+            .map(|band| {
+                let mut buckets: HashMap<u64, Vec<u32>> = HashMap::new();
+                for (id, signature) in signatures.iter().enumerate() {
+                    let key = band_key(&signature[band * rows..(band + 1) * rows]);
+                    buckets.entry(key).or_default().push(id as u32);
+                }
+                let mut buckets: Vec<(u64, Vec<u32>)> = buckets.into_iter().collect();
+                buckets.sort_unstable_by_key(|bucket| bucket.0);
+                buckets
+            })
+            .collect();
+
+        let all = || buckets.iter().flatten();
+        let keys: Vec<u64> = all().map(|bucket| bucket.0).collect();
+        let bucket_ids: Vec<u32> = all().flat_map(|bucket| bucket.1.iter().copied()).collect();
+        Ok(MinHashLSH {
+            hasher,
+            bands,
+            buffer: None,
+            documents: documents.into(),
+            buckets: IntKeys {
+                keys: keys.into(),
+                offsets: storage::offsets(all().map(|bucket| bucket.1.len())).into(),
+            },
+            bucket_ids: bucket_ids.into(),
+            band_offsets: storage::offsets(buckets.iter().map(Vec::len)).into(),
+        })
+    }
+
+    fn from_buffer(buffer: Buffer, verify: bool) -> PyResult<Self> {
+        let mut reader = Reader::new(buffer.clone(), Kind::MinHashLSH, verify)?;
+        let params = storage::params(&mut reader, 5)?;
+        let hasher = MinHasher::new(params[0] as usize, params[1] as usize, params[2] != 0, params[3])
+            .map_err(|_| storage::invalid_param("num_perm"))?;
+        let bands = params[4] as usize;
+        if bands == 0 || hasher.num_perm / bands * bands != hasher.num_perm {
+            return Err(storage::invalid_param("bands"));
+        }
+        let documents = reader.strings()?;
+        let buckets = reader.int_keys()?;
+        let bucket_ids = reader.values(buckets.total())?;
+        reader.check_ids(&bucket_ids, documents.len())?;
+        let band_offsets: Array<u64> = reader.array()?;
+        let mut previous = 0;
+        for offset in band_offsets.range(1..band_offsets.len()) {
+            if offset < previous {
+                return Err(storage::invalid_param("band offsets"));
+            }
+            previous = offset;
+        }
+        if band_offsets.len() != bands + 1 || previous as usize != buckets.keys.len() {
+            return Err(storage::invalid_param("band offsets"));
+        }
+        Ok(MinHashLSH { hasher, bands, buffer: Some(buffer), documents, buckets, bucket_ids, band_offsets })
+    }
+
+    /// The index in the format read by `from_buffer`.
+    fn buffer(&self) -> Buffer {
+        if let Some(buffer) = &self.buffer {
+            return buffer.clone();
+        }
+        let mut writer = Writer::default();
+        writer.array(vec![
+            self.hasher.num_perm as u64,
+            self.hasher.shingle_size as u64,
+            self.hasher.words as u64,
+            self.hasher.seed,
+            self.bands as u64,
+        ]);
+        writer.strings(self.documents.iter());
+        writer.int_keys(&self.buckets);
+        writer.array(self.bucket_ids.iter());
+        writer.array(self.band_offsets.iter());
+        writer.finish(Kind::MinHashLSH)
+    }
+
+    fn band(&self, band: usize) -> Range<usize> {
+        self.band_offsets.get(band) as usize..self.band_offsets.get(band + 1) as usize
+    }
+
+    fn rows(&self) -> usize {
+        self.hasher.num_perm / self.bands
+    }
+
+    fn score(&self, metric: Metric, a: &str, b: &str) -> f64 {
+        match metric {
+            Metric::Jaccard => self.hasher.jaccard(a, b),
+            Metric::SorensenDice => QGram::bigrams(Measure::SorensenDice { count_grams: false }).similarity(&a.into(), &b.into()),
+        }
+    }
+
+    /// Pairs of documents sharing a bucket, sorted. Bands are merged one at a
+    /// time into a set, so a pair found by every band is held once rather than
+    /// once per band; buckets of more than `max_bucket_size` documents, whose
+    /// pairs grow quadratically, are skipped.
+    pub(crate) fn candidates(&self, max_bucket_size: Option<usize>) -> Vec<(u32, u32)> {
+        let mut seen: HashSet<(u32, u32)> = HashSet::new();
+        for band in 0..self.bands {
+            let pairs: Vec<(u32, u32)> = self
+                .band(band)
+                .into_par_iter()
+                .flat_map_iter(|bucket| {
+                    let range = self.buckets.nth(bucket);
+                    let ids: Vec<u32> = match max_bucket_size {
+                        Some(max) if range.len() > max => Vec::new(),
+                        _ => self.bucket_ids.range(range).collect(),
+                    };
+                    let mut pairs = Vec::new();
+                    for (k, &i) in ids.iter().enumerate() {
+                        pairs.extend(ids[k + 1..].iter().map(|&j| (i, j)));
+                    }
+                    pairs
+                })
+                .collect();
+            seen.extend(pairs);
+        }
+        let mut pairs: Vec<(u32, u32)> = seen.into_iter().collect();
+        pairs.par_sort_unstable();
+        pairs
+    }
+
+    fn query_one(&self, text: &str, threshold: f64, metric: Metric) -> Vec<Hit> {
+        let signature = self.hasher.signature(text);
+        let rows = self.rows();
+        let mut candidates: Vec<u32> = (0..self.bands)
+            .filter_map(|band| {
+                let key = band_key(&signature[band * rows..(band + 1) * rows]);
+                self.buckets.range_within(key, self.band(band))
+            })
+            .flat_map(|range| self.bucket_ids.range(range))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        let mut hits: Vec<Hit> = candidates
+            .into_iter()
+            .filter_map(|id| {
+                let document = self.documents.get(id as usize);
+                let score = self.score(metric, text, document);
+                (score >= threshold).then(|| (id as usize, document.to_string(), Score::Similarity(score)))
+            })
+            .collect();
+        sort_hits(&mut hits);
+        hits
+    }
+}
+
+fn band_key(rows: &[u64]) -> u64 {
+    let bytes: Vec<u8> = rows.iter().flat_map(|r| r.to_le_bytes()).collect();
+    fnv1a(&bytes)
+}
+
+#[pymethods]
+impl MinHashLSH {
+    /// build(documents, num_perm=128, bands=32, shingle_size=3, words=False, seed=1)
+    ///
+    /// Hashes every document and buckets each band of its signature. More
+    /// bands with fewer rows each catch pairs of lower similarity at the cost
+    /// of more false candidates.
+    ///
+    /// :param list documents: strings to index
+    /// :param int num_perm: number of hash functions
+    /// :param int bands: number of bands, must divide num_perm
+    /// :param int shingle_size: characters (or words) per shingle
+    /// :param bool words: shingle over whitespace-separated words
+    /// :param int seed: seed of the hash functions
+    /// :return: index
+    /// :rtype: MinHashLSH
+    /// :raises ValueError: if bands does not divide num_perm
+    #[staticmethod]
+    #[pyo3(signature = (documents, num_perm=128, bands=32, shingle_size=3, words=false, seed=1))]
+    fn build(
+        py: Python,
+        documents: Vec<String>,
+        num_perm: usize,
+        bands: usize,
+        shingle_size: usize,
+        words: bool,
+        seed: u64,
+    ) -> PyResult<Self> {
+        let hasher = MinHasher::new(num_perm, shingle_size, words, seed)?;
+        py.allow_threads(|| MinHashLSH::new(documents, hasher, bands))
+    }
+
+    /// candidate_pairs(max_bucket_size=None)
+    ///
+    /// Lists the unverified pairs of documents sharing at least one bucket.
+    /// Memory grows with the number of distinct pairs, which a bucket shared
+    /// by many documents makes quadratic; ``max_bucket_size`` skips such
+    /// buckets, at the cost of the pairs found only there.
+    ///
+    /// :param int max_bucket_size: largest bucket paired up, None for all
+    /// :return: list of ``(i, j)`` tuples with ``i < j``
+    /// :rtype: list
+    #[pyo3(signature = (max_bucket_size=None))]
+    fn candidate_pairs(&self, py: Python, max_bucket_size: Option<usize>) -> Vec<(u32, u32)> {
+        py.allow_threads(|| self.candidates(max_bucket_size))
+    }
+
+    /// near_duplicates(threshold, metric="jaccard", max_bucket_size=None)
+    ///
+    /// Verifies the candidate pairs with the exact Jaccard similarity of the
+    /// shingle sets, or with :py:func:`sorensen_dice`, and keeps the pairs
+    /// scoring at least ``threshold``. Operates in parallel over the pairs.
+    ///
+    /// :param float threshold: minimum similarity
+    /// :param str metric: ``"jaccard"`` or ``"sorensen_dice"``
+    /// :param int max_bucket_size: largest bucket paired up, None for all, as
+    ///     in :py:meth:`MinHashLSH.candidate_pairs`
+    /// :return: list of ``(i, j, similarity)`` tuples with ``i < j``
+    /// :rtype: list
+    #[pyo3(signature = (threshold, metric="jaccard", max_bucket_size=None))]
+    fn near_duplicates(
+        &self,
+        py: Python,
+        threshold: f64,
+        metric: &str,
+        max_bucket_size: Option<usize>,
+    ) -> PyResult<Vec<(u32, u32, f64)>> {
+        let metric = Metric::parse(metric)?;
+        Ok(py.allow_threads(|| {
+            self.candidates(max_bucket_size)
+                .into_par_iter()
+                .filter_map(|(i, j)| {
+                    let score = self.score(metric, self.documents.get(i as usize), self.documents.get(j as usize));
+                    (score >= threshold).then_some((i, j, score))
+                })
+                .collect()
+        }))
+    }
+
+    /// query(text, threshold, metric="jaccard")
+    ///
+    /// Finds the indexed documents sharing a bucket with ``text`` and scoring
+    /// at least ``threshold`` against it, best first.
+    ///
+    /// :param str text: string to look up
+    /// :param float threshold: minimum similarity
+    /// :param str metric: ``"jaccard"`` or ``"sorensen_dice"``
+    /// :return: list of ``(index, document, similarity)`` tuples
+    /// :rtype: list
+    #[pyo3(signature = (text, threshold, metric="jaccard"))]
+    fn query(&self, py: Python, text: &str, threshold: f64, metric: &str) -> PyResult<Vec<Hit>> {
+        let metric = Metric::parse(metric)?;
+        Ok(py.allow_threads(|| self.query_one(text, threshold, metric)))
+    }
+
+    /// query_parallel(texts, threshold, metric="jaccard")
+    ///
+    /// Like :py:meth:`MinHashLSH.query`, for many strings at once. Operates in
+    /// parallel over the list of strings.
+    ///
+    /// :param list texts: strings to look up
+    /// :param float threshold: minimum similarity
+    /// :param str metric: ``"jaccard"`` or ``"sorensen_dice"``
+    /// :return: one list of ``(index, document, similarity)`` tuples per string
+    /// :rtype: list
+    #[pyo3(signature = (texts, threshold, metric="jaccard"))]
+    fn query_parallel(&self, py: Python, texts: Vec<&str>, threshold: f64, metric: &str) -> PyResult<Vec<Vec<Hit>>> {
+        let metric = Metric::parse(metric)?;
+        Ok(py.allow_threads(|| {
+            texts
+                .par_iter()
+                .map(|text| self.query_one(text, threshold, metric))
+                .collect()
+        }))
+    }
+
+    /// save(path)
+    ///
+    /// Stores the documents and buckets in ``path``. The hashing parameters go
+    /// with them, so a loaded index hashes queries exactly as this one does.
+    ///
+    /// :param str path: destination file
+    /// :raises IOError: if the file cannot be written
+    fn save(&self, py: Python, path: &str) -> PyResult<()> {
+        py.allow_threads(|| self.buffer().save(path))
+    }
+
+    /// load(path, mmap=True, verify=False)
+    ///
+    /// Opens an index written by :py:meth:`MinHashLSH.save`. By default the
+    /// file is memory-mapped and queried in place rather than read, and only
+    /// its header is checked unless ``verify`` is set.
+    ///
+    /// :param str path: index file
+    /// :param bool mmap: memory-map the file instead of reading it
+    /// :param bool verify: check the whole file before using it
+    /// :return: index
+    /// :rtype: MinHashLSH
+    /// :raises IOError: if the file cannot be read
+    /// :raises ValueError: if the file is not a MinHashLSH of a supported version,
+    ///     or fails verification
+    #[staticmethod]
+    #[pyo3(signature = (path, mmap=true, verify=false))]
+    fn load(py: Python, path: &str, mmap: bool, verify: bool) -> PyResult<Self> {
+        py.allow_threads(|| MinHashLSH::from_buffer(Buffer::open(path, mmap)?, verify))
+    }
+
+    /// __getstate__()
+    ///
+    /// Serialized documents, buckets and hashing parameters, as pickled.
+    fn __getstate__<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, self.buffer().bytes())
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = MinHashLSH::from_buffer(Buffer::from_bytes(state.to_vec()), true)?;
+        Ok(())
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, (PyObject,))> {
+        let py = slf.py();
+        let state = slf.borrow().__getstate__(py).into();
+        Ok((slf.get_type().getattr("_from_state")?.into(), (state,)))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "_from_state")]
+    fn from_state(state: &[u8]) -> PyResult<Self> {
+        MinHashLSH::from_buffer(Buffer::from_bytes(state.to_vec()), true)
+    }
+
+    #[getter]
+    fn mapped(&self) -> bool {
+        self.buffer.as_ref().is_some_and(Buffer::is_mapped)
+    }
+
+    #[getter]
+    fn num_perm(&self) -> usize {
+        self.hasher.num_perm
+    }
+
+    #[getter]
+    fn bands(&self) -> usize {
+        self.bands
+    }
+
+    fn __len__(&self) -> usize {
+        self.documents.len()
+    }
+}
@@ -0,0 +1,61 @@
+//! Search indexes built on top of the metrics exposed by the crate root.
+//!
+//! Each index prunes the candidate set with a cheap filter and then verifies
+//! the survivors with the corresponding scalar metric, so hits always carry
+//! the exact score the scalar function would have returned.
+
+use pyo3::prelude::*;
+
+pub(crate) mod automaton;
+pub(crate) mod minhash;
+pub(crate) mod qgram;
+pub(crate) mod storage;
+pub(crate) mod symspell;
+pub(crate) mod vptree;
+
+/// 64-bit FNV-1a, used instead of `DefaultHasher` so that hashes are stable
+/// across processes and Rust versions.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Score attached to an index hit: an edit distance for distance metrics, a
+/// similarity for normalized ones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Score {
+    Distance(usize),
+    Similarity(f64),
+}
+
+impl Score {
+    /// Orders scores so that the best hit comes first.
+    pub(crate) fn best_first(&self, other: &Score) -> std::cmp::Ordering {
+        match (self, other) {
+            (Score::Distance(a), Score::Distance(b)) => a.cmp(b),
+            (Score::Similarity(a), Score::Similarity(b)) => b
+                .partial_cmp(a)
+                .unwrap_or(std::cmp::Ordering::Equal),
+            _ => std::cmp::Ordering::Equal,
+        }
+    }
+}
+
+impl IntoPy<PyObject> for Score {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            Score::Distance(d) => d.into_py(py),
+            Score::Similarity(s) => s.into_py(py),
+        }
+    }
+}
+
+/// A verified index hit: position in the indexed list, the indexed string and
+/// its score against the query.
+pub(crate) type Hit = (usize, String, Score);
+
+/// Sorts hits best first, breaking ties by insertion order.
+pub(crate) fn sort_hits(hits: &mut [Hit]) {
+    hits.sort_by(|a, b| a.2.best_first(&b.2).then(a.0.cmp(&b.0)));
+}
@@ -0,0 +1,481 @@
+//! Q-gram inverted index with count filtering.
+//!
+//! Two strings within Levenshtein distance `k` share at least
+//! `max(|G(a)|, |G(b)|) - k * q` q-grams (the q-gram count lemma), so only
+//! strings whose posting lists overlap the query often enough are verified.
+//! For Sørensen-Dice the postings hold the same whitespace-stripped bigrams
+//! the `sorensen_dice` scalar counts, which makes the overlap count the exact
+//! intersection size.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rayon::prelude::*;
+
+use super::storage::{self, Array, Buffer, IntKeys, Kind, Reader, StrKeys, Strings, Writer};
+use super::{sort_hits, Hit, Score};
+use crate::distance::{Measure, QGram, Unit};
+
+const PAD_START: char = '\u{2}';
+const PAD_END: char = '\u{3}';
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Metric {
+    Levenshtein,
+    SorensenDice,
+}
+
+impl Metric {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "levenshtein" => Ok(Metric::Levenshtein),
+            "sorensen_dice" => Ok(Metric::SorensenDice),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unsupported metric for QGramIndex: {}",
+                name
+            ))),
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Metric::Levenshtein => "levenshtein",
+            Metric::SorensenDice => "sorensen_dice",
+        }
+    }
+}
+
+/// Q-gram multiset of `s` together with the length the filters reason about:
+/// chars for Levenshtein, whitespace-stripped bytes for Sørensen-Dice.
+fn profile(s: &str, q: usize, pad: bool, metric: Metric) -> (HashMap<String, u32>, usize) {
+    let (chars, length): (Vec<char>, usize) = match metric {
+        Metric::Levenshtein => {
+            let padding = if pad { q - 1 } else { 0 };
+            let mut chars = vec![PAD_START; padding];
+            chars.extend(s.chars());
+            let length = chars.len() - padding;
+            chars.resize(chars.len() + padding, PAD_END);
+            (chars, length)
+        }
+        Metric::SorensenDice => {
+            let stripped: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+            (stripped.chars().collect(), stripped.len())
+        }
+    };
+    let mut grams = HashMap::new();
+    for window in chars.windows(q) {
+        *grams.entry(window.iter().collect::<String>()).or_insert(0) += 1;
+    }
+    (grams, length)
+}
+
+/// QGramIndex
+///
+/// Inverted index from q-grams to the strings containing them. Build it with
+/// :py:meth:`QGramIndex.build` and query it with :py:meth:`QGramIndex.search`.
+#[pyclass(module = "xdistances")]
+pub(crate) struct QGramIndex {
+    pub(crate) q: usize,
+    pub(crate) pad: bool,
+    pub(crate) metric: Metric,
+    /// File or pickle the index was read from, `None` when built in memory.
+    buffer: Option<Buffer>,
+    strings: Strings,
+    /// Filter length of each indexed string, see `profile`.
+    lengths: Array<u32>,
+    /// Gram -> ids of the strings containing it and the gram's multiplicity.
+    postings: StrKeys,
+    posting_ids: Array<u32>,
+    posting_counts: Array<u32>,
+    /// Filter length -> ids of the strings with that length.
+    by_length: IntKeys,
+    length_ids: Array<u32>,
+    /// Ids of the strings too short to contain a single gram.
+    gramless: Array<u32>,
+}
+
+impl QGramIndex {
+    pub(crate) fn new(strings: Vec<String>, q: usize, pad: bool, metric: Metric) -> PyResult<Self> {
+        if q == 0 {
+            return Err(exceptions::PyValueError::new_err("q must be at least 1"));
+        }
+        if metric == Metric::SorensenDice && (q != 2 || pad) {
+            return Err(exceptions::PyValueError::new_err(
+                "sorensen_dice requires q=2 without padding",
+            ));
+        }
+        if strings.len() > u32::MAX as usize {
+            return Err(exceptions::PyValueError::new_err("Too many strings to index"));
+        }
+        let profiles: Vec<_> = strings
+            .par_iter()
+            .map(|s| profile(s, q, pad, metric))
+            .collect();
+        let mut lengths = Vec::with_capacity(strings.len());
+        let mut postings: HashMap<String, Vec<(u32, u32)>> = HashMap::new();
+        let mut by_length: HashMap<u64, Vec<u32>> = HashMap::new();
+        let mut gramless = Vec::new();
+        for (id, (grams, length)) in profiles.into_iter().enumerate() {
+            let id = id as u32;
+            if grams.is_empty() {
+                gramless.push(id);
+            }
+            for (gram, count) in grams {
+                postings.entry(gram).or_default().push((id, count));
+            }
+            by_length.entry(length as u64).or_default().push(id);
+            lengths.push(length as u32);
+        }
+
+        // Postings are laid out in gram order, as `save` writes them.
+        let mut postings: Vec<_> = postings.into_iter().collect();
+        postings.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let posting_ids: Vec<u32> = postings.iter().flat_map(|p| p.1.iter().map(|e| e.0)).collect();
+        let posting_counts: Vec<u32> = postings.iter().flat_map(|p| p.1.iter().map(|e| e.1)).collect();
+        let mut by_length: Vec<_> = by_length.into_iter().collect();
+        by_length.sort_unstable_by_key(|e| e.0);
+        let length_keys: Vec<u64> = by_length.iter().map(|e| e.0).collect();
+        let length_offsets = storage::offsets(by_length.iter().map(|e| e.1.len()));
+        let length_ids: Vec<u32> = by_length.into_iter().flat_map(|e| e.1).collect();
+        Ok(QGramIndex {
+            q,
+            pad,
+            metric,
+            buffer: None,
+            strings: strings.into(),
+            lengths: lengths.into(),
+            postings: StrKeys::owned(postings.into_iter().map(|(gram, ids)| (gram, ids.len()))),
+            posting_ids: posting_ids.into(),
+            posting_counts: posting_counts.into(),
+            by_length: IntKeys { keys: length_keys.into(), offsets: length_offsets.into() },
+            length_ids: length_ids.into(),
+            gramless: gramless.into(),
+        })
+    }
+
+    pub(crate) fn from_buffer(buffer: Buffer, verify: bool) -> PyResult<Self> {
+        let mut reader = Reader::new(buffer.clone(), Kind::QGramIndex, verify)?;
+        let params = storage::params(&mut reader, 3)?;
+        let q = params[0] as usize;
+        let metric = match params[2] {
+            0 => Metric::Levenshtein,
+            1 => Metric::SorensenDice,
+            _ => return Err(storage::invalid_param("metric")),
+        };
+        if q == 0 {
+            return Err(storage::invalid_param("q"));
+        }
+        let strings = reader.strings()?;
+        let lengths = reader.array()?;
+        let postings = reader.str_keys()?;
+        let posting_ids = reader.values(postings.total())?;
+        let posting_counts = reader.values(postings.total())?;
+        let by_length = reader.int_keys()?;
+        let length_ids = reader.values(by_length.total())?;
+        let gramless = reader.array()?;
+        if lengths.len() != strings.len() {
+            return Err(storage::invalid_param("lengths"));
+        }
+        for ids in [&posting_ids, &length_ids, &gramless] {
+            reader.check_ids(ids, strings.len())?;
+        }
+        Ok(QGramIndex {
+            q,
+            pad: params[1] != 0,
+            metric,
+            buffer: Some(buffer),
+            strings,
+            lengths,
+            postings,
+            posting_ids,
+            posting_counts,
+            by_length,
+            length_ids,
+            gramless,
+        })
+    }
+
+    /// The index in the format read by `from_buffer`.
+    fn buffer(&self) -> Buffer {
+        if let Some(buffer) = &self.buffer {
+            return buffer.clone();
+        }
+        let mut writer = Writer::default();
+        writer.array(vec![self.q as u64, self.pad as u64, self.metric as u64]);
+        writer.strings(self.strings.iter());
+        writer.array(self.lengths.iter());
+        writer.str_keys(&self.postings);
+        writer.array(self.posting_ids.iter());
+        writer.array(self.posting_counts.iter());
+        writer.int_keys(&self.by_length);
+        writer.array(self.length_ids.iter());
+        writer.array(self.gramless.iter());
+        writer.finish(Kind::QGramIndex)
+    }
+
+    fn length(&self, id: u32) -> usize {
+        self.lengths.get(id as usize) as usize
+    }
+
+    /// Number of q-grams shared by the query and each string it overlaps with.
+    fn overlaps(&self, grams: &HashMap<String, u32>) -> HashMap<u32, u32> {
+        let mut counts: HashMap<u32, u32> = HashMap::new();
+        for (gram, query_count) in grams {
+            if let Some(range) = self.postings.range(gram) {
+                for (id, count) in self.posting_ids.range(range.clone()).zip(self.posting_counts.range(range)) {
+                    *counts.entry(id).or_insert(0) += count.min(*query_count);
+                }
+            }
+        }
+        counts
+    }
+
+    fn gram_count(&self, length: usize) -> i64 {
+        if self.pad {
+            (length + self.q - 1) as i64
+        } else {
+            length as i64 - self.q as i64 + 1
+        }
+    }
+
+    fn levenshtein_candidates(&self, query: &str, max_distance: usize) -> Vec<u32> {
+        let (grams, length) = profile(query, self.q, self.pad, self.metric);
+        // No indexed string is longer than the last length key, which bounds
+        // the window whatever the threshold, infinite ones included.
+        let keys = &self.by_length.keys;
+        let longest = keys.len().checked_sub(1).map_or(0, |last| keys.get(last) as usize);
+        let lower = length.saturating_sub(max_distance);
+        let upper = length.saturating_add(max_distance).min(longest);
+        let slack = max_distance.saturating_mul(self.q).min(i64::MAX as usize) as i64;
+        let needed = |other: usize| self.gram_count(length).max(self.gram_count(other)) - slack;
+
+        // Lengths for which the lemma gives no bound are scanned wholesale.
+        let mut candidates: Vec<u32> = (lower..=upper)
+            .filter(|&l| needed(l) <= 0)
+            .filter_map(|l| self.by_length.range(l as u64))
+            .flat_map(|range| self.length_ids.range(range))
+            .collect();
+        candidates.extend(
+            self.overlaps(&grams)
+                .into_iter()
+                .filter(|&(id, shared)| {
+                    let other = self.length(id);
+                    let need = needed(other);
+                    other >= lower && other <= upper && need > 0 && shared as i64 >= need
+                })
+                .map(|(id, _)| id),
+        );
+        candidates
+    }
+
+    fn sorensen_dice_candidates(&self, query: &str, min_similarity: f64) -> Vec<u32> {
+        if min_similarity <= 0.0 {
+            return (0..self.strings.len() as u32).collect();
+        }
+        let (grams, length) = profile(query, self.q, self.pad, self.metric);
+        // Strings without a single bigram only match on equality, which the
+        // overlap count cannot see, so they are always verified.
+        let mut candidates: Vec<u32> = self.gramless.iter().collect();
+        if grams.is_empty() {
+            return candidates;
+        }
+        // The scalar divides by byte lengths, which can push equal non-ASCII
+        // strings below the bound; containing every query gram keeps them.
+        let total: u32 = grams.values().sum();
+        candidates.extend(
+            self.overlaps(&grams)
+                .into_iter()
+                .filter(|&(id, shared)| {
+                    let denominator = (length + self.length(id)) as f64 - 2.0;
+                    shared == total || 2.0 * shared as f64 + 1e-9 >= min_similarity * denominator
+                })
+                .map(|(id, _)| id),
+        );
+        candidates
+    }
+
+    pub(crate) fn search_one(&self, query: &str, threshold: f64) -> Vec<Hit> {
+        let mut candidates = match self.metric {
+            Metric::Levenshtein => self.levenshtein_candidates(query, threshold as usize),
+            Metric::SorensenDice => self.sorensen_dice_candidates(query, threshold),
+        };
+        candidates.sort_unstable();
+        candidates.dedup();
+        let mut hits: Vec<Hit> = candidates
+            .into_iter()
+            .filter_map(|id| {
+                let s = self.strings.get(id as usize);
+                let score = match self.metric {
+                    Metric::Levenshtein => {
+                        let distance = Unit::Char.levenshtein(&query.into(), &s.into());
+                        if distance > threshold as usize {
+                            return None;
+                        }
+                        Score::Distance(distance)
+                    }
+                    Metric::SorensenDice => {
+                        let similarity = QGram::bigrams(Measure::SorensenDice { count_grams: false }).similarity(&query.into(), &s.into());
+                        if similarity < threshold {
+                            return None;
+                        }
+                        Score::Similarity(similarity)
+                    }
+                };
+                Some((id as usize, s.to_string(), score))
+            })
+            .collect();
+        sort_hits(&mut hits);
+        hits
+    }
+
+    fn check_threshold(&self, threshold: f64) -> PyResult<()> {
+        if threshold.is_nan() || (self.metric == Metric::Levenshtein && threshold < 0.0) {
+            return Err(exceptions::PyValueError::new_err("Invalid threshold"));
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl QGramIndex {
+    /// build(strings, q=2, pad=True, metric="levenshtein")
+    ///
+    /// Builds an index over a list of strings. ``metric`` selects the function
+    /// used to verify candidates: ``"levenshtein"`` works with any ``q`` and
+    /// padding, ``"sorensen_dice"`` requires the bigrams :py:func:`sorensen_dice`
+    /// compares by default
+    /// (``q=2``, ``pad=False``).
+    ///
+    /// :param list strings: strings to index
+    /// :param int q: gram size
+    /// :param bool pad: pad strings with ``q - 1`` start and end markers
+    /// :param str metric: ``"levenshtein"`` or ``"sorensen_dice"``
+    /// :return: index
+    /// :rtype: QGramIndex
+    /// :raises ValueError: if the metric is unknown or incompatible with q and pad
+    #[staticmethod]
+    #[pyo3(signature = (strings, q=2, pad=true, metric="levenshtein"))]
+    fn build(py: Python, strings: Vec<String>, q: usize, pad: bool, metric: &str) -> PyResult<Self> {
+        let metric = Metric::parse(metric)?;
+        py.allow_threads(|| QGramIndex::new(strings, q, pad, metric))
+    }
+
+    /// search(query, threshold)
+    ///
+    /// Finds the indexed strings within ``threshold`` of ``query``: at most
+    /// ``threshold`` edits for Levenshtein, a similarity of at least
+    /// ``threshold`` for Sørensen-Dice. Hits are sorted best first.
+    ///
+    /// :param str query: string to look up
+    /// :param threshold: maximum distance or minimum similarity
+    /// :return: list of ``(index, string, score)`` tuples
+    /// :rtype: list
+    /// :raises ValueError: if the threshold is negative for Levenshtein
+    fn search(&self, py: Python, query: &str, threshold: f64) -> PyResult<Vec<Hit>> {
+        self.check_threshold(threshold)?;
+        Ok(py.allow_threads(|| self.search_one(query, threshold)))
+    }
+
+    /// search_parallel(queries, threshold)
+    ///
+    /// Like :py:meth:`QGramIndex.search`, for many queries at once. Operates in
+    /// parallel over the list of queries.
+    ///
+    /// :param list queries: strings to look up
+    /// :param threshold: maximum distance or minimum similarity
+    /// :return: one list of ``(index, string, score)`` tuples per query
+    /// :rtype: list
+    fn search_parallel(&self, py: Python, queries: Vec<&str>, threshold: f64) -> PyResult<Vec<Vec<Hit>>> {
+        self.check_threshold(threshold)?;
+        Ok(py.allow_threads(|| {
+            queries
+                .par_iter()
+                .map(|query| self.search_one(query, threshold))
+                .collect()
+        }))
+    }
+
+    /// save(path)
+    ///
+    /// Writes the index to ``path`` in the versioned binary format read by
+    /// :py:meth:`QGramIndex.load`.
+    ///
+    /// :param str path: destination file
+    /// :raises IOError: if the file cannot be written
+    fn save(&self, py: Python, path: &str) -> PyResult<()> {
+        py.allow_threads(|| self.buffer().save(path))
+    }
+
+    /// load(path, mmap=True, verify=False)
+    ///
+    /// Reads an index written by :py:meth:`QGramIndex.save`. With ``mmap`` the
+    /// file is mapped read-only rather than copied, so every process loading
+    /// it shares the same pages; the file must not change while in use.
+    /// Only the header is read up front; ``verify`` checks the checksum and
+    /// contents of the whole file first, for files that may be corrupt.
+    ///
+    /// :param str path: index file
+    /// :param bool mmap: memory-map the file instead of reading it
+    /// :param bool verify: check the whole file before using it
+    /// :return: index
+    /// :rtype: QGramIndex
+    /// :raises IOError: if the file cannot be read
+    /// :raises ValueError: if the file is not a QGramIndex of a supported version,
+    ///     or fails verification
+    #[staticmethod]
+    #[pyo3(signature = (path, mmap=true, verify=false))]
+    fn load(py: Python, path: &str, mmap: bool, verify: bool) -> PyResult<Self> {
+        py.allow_threads(|| QGramIndex::from_buffer(Buffer::open(path, mmap)?, verify))
+    }
+
+    /// __getstate__()
+    ///
+    /// The index in the format written by :py:meth:`QGramIndex.save`, which is
+    /// what pickling sends to other processes.
+    fn __getstate__<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, self.buffer().bytes())
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = QGramIndex::from_buffer(Buffer::from_bytes(state.to_vec()), true)?;
+        Ok(())
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, (PyObject,))> {
+        let py = slf.py();
+        let state = slf.borrow().__getstate__(py).into();
+        Ok((slf.get_type().getattr("_from_state")?.into(), (state,)))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "_from_state")]
+    fn from_state(state: &[u8]) -> PyResult<Self> {
+        QGramIndex::from_buffer(Buffer::from_bytes(state.to_vec()), true)
+    }
+
+    #[getter]
+    fn mapped(&self) -> bool {
+        self.buffer.as_ref().is_some_and(Buffer::is_mapped)
+    }
+
+    #[getter]
+    fn q(&self) -> usize {
+        self.q
+    }
+
+    #[getter]
+    fn pad(&self) -> bool {
+        self.pad
+    }
+
+    #[getter]
+    fn metric(&self) -> &'static str {
+        self.metric.name()
+    }
+
+    fn __len__(&self) -> usize {
+        self.strings.len()
+    }
+}
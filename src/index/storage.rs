@@ -0,0 +1,603 @@
+//! Versioned binary format shared by the search indexes.
+//!
+//! An index file is a header followed by 8-byte aligned sections:
+//!
+//! | bytes  | content                                  |
+//! |--------|------------------------------------------|
+//! | 8      | magic `XDIDX\0\0\0`                      |
+//! | 4      | format version                           |
+//! | 4      | index kind                               |
+//! | 8      | number of sections `n`                   |
+//! | 8      | FNV-1a checksum of everything after it   |
+//! | 16 * n | `(offset, length)` of each section       |
+//!
+//! Sections hold little-endian arrays that the indexes read in place, so a
+//! file can be served straight from a shared read-only memory map. Indexes
+//! built in memory hold the same arrays and keys in owned variants, which
+//! keeps a single query path, and are only laid out in this format when they
+//! are saved or pickled.
+//!
+//! Opening a file only reads its header and section table. The checksum and
+//! the contents are checked up front when the caller asks for it; otherwise
+//! strings are checked as they are read and out-of-range ids or offsets
+//! panic on use instead of being trusted.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::sync::Arc;
+
+use memmap2::Mmap;
+use pyo3::exceptions;
+use pyo3::prelude::*;
+
+use super::fnv1a;
+
+/// Bumped whenever the layout of any index changes.
+pub(crate) const FORMAT_VERSION: u32 = 2;
+
+const MAGIC: &[u8; 8] = b"XDIDX\0\0\0";
+const HEADER: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    QGramIndex = 1,
+    SymSpell = 2,
+    MinHashLSH = 3,
+    VPTree = 4,
+}
+
+/// Rounds `offset` up to the next multiple of 8.
+fn align(offset: usize) -> usize {
+    (offset + 7) & !7
+}
+
+fn invalid(reason: &str) -> PyErr {
+    exceptions::PyValueError::new_err(format!("Invalid index file: {}", reason))
+}
+
+enum Backing {
+    Heap(Vec<u8>),
+    Mapped(Mmap),
+}
+
+/// Shared bytes of an index, either owned or memory-mapped.
+#[derive(Clone)]
+pub(crate) struct Buffer(Arc<Backing>);
+
+impl Buffer {
+    pub(crate) fn bytes(&self) -> &[u8] {
+        match &*self.0 {
+            Backing::Heap(bytes) => bytes,
+            Backing::Mapped(map) => map,
+        }
+    }
+
+    /// Wraps bytes produced by `Writer::finish`, e.g. a pickled index.
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Buffer {
+        Buffer(Arc::new(Backing::Heap(bytes)))
+    }
+
+    /// Reads an index file, mapping it into memory if `mmap` is set.
+    pub(crate) fn open(path: &str, mmap: bool) -> PyResult<Buffer> {
+        let backing = if mmap {
+            let file = File::open(path)?;
+            // SAFETY: the map is read-only; like every mmap-based reader we
+            // require that index files are not modified while loaded.
+            Backing::Mapped(unsafe { Mmap::map(&file)? })
+        } else {
+            Backing::Heap(std::fs::read(path)?)
+        };
+        Ok(Buffer(Arc::new(backing)))
+    }
+
+    /// Writes the buffer through a temporary file, so that saving over the
+    /// file an index was mapped from never truncates it under the map.
+    pub(crate) fn save(&self, path: &str) -> PyResult<()> {
+        let partial = format!("{}.partial", path);
+        let mut file = File::create(&partial)?;
+        file.write_all(self.bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    pub(crate) fn is_mapped(&self) -> bool {
+        matches!(&*self.0, Backing::Mapped(_))
+    }
+}
+
+impl AsRef<[u8]> for Buffer {
+    fn as_ref(&self) -> &[u8] {
+        self.bytes()
+    }
+}
+
+/// Fixed-size little-endian values stored in sections.
+pub(crate) trait Element: Copy {
+    const SIZE: usize;
+    fn read(bytes: &[u8]) -> Self;
+    fn write(self, out: &mut Vec<u8>);
+}
+
+impl Element for u32 {
+    const SIZE: usize = 4;
+    fn read(bytes: &[u8]) -> Self {
+        u32::from_le_bytes(bytes.try_into().expect("4 bytes"))
+    }
+    fn write(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Element for u64 {
+    const SIZE: usize = 8;
+    fn read(bytes: &[u8]) -> Self {
+        u64::from_le_bytes(bytes.try_into().expect("8 bytes"))
+    }
+    fn write(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Element for f64 {
+    const SIZE: usize = 8;
+    fn read(bytes: &[u8]) -> Self {
+        f64::from_le_bytes(bytes.try_into().expect("8 bytes"))
+    }
+    fn write(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+/// Array of `T`, read in place from a section or owned by an index built in
+/// memory.
+#[derive(Clone)]
+pub(crate) enum Array<T> {
+    Mapped {
+        buffer: Buffer,
+        start: usize,
+        len: usize,
+        element: PhantomData<T>,
+    },
+    Owned(Vec<T>),
+}
+
+impl<T: Element> Array<T> {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Array::Mapped { len, .. } => *len,
+            Array::Owned(values) => values.len(),
+        }
+    }
+
+    pub(crate) fn get(&self, i: usize) -> T {
+        match self {
+            Array::Mapped { buffer, start, len, .. } => {
+                assert!(i < *len, "index out of bounds");
+                let at = start + i * T::SIZE;
+                T::read(&buffer.bytes()[at..at + T::SIZE])
+            }
+            Array::Owned(values) => values[i],
+        }
+    }
+
+    pub(crate) fn range(&self, range: Range<usize>) -> impl Iterator<Item = T> + '_ {
+        range.map(move |i| self.get(i))
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.range(0..self.len())
+    }
+}
+
+impl<T> From<Vec<T>> for Array<T> {
+    fn from(values: Vec<T>) -> Self {
+        Array::Owned(values)
+    }
+}
+
+impl Array<u64> {
+    /// Position of `key` in a sorted array.
+    pub(crate) fn find(&self, key: u64, within: Range<usize>) -> Option<usize> {
+        let (mut low, mut high) = (within.start, within.end);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.get(middle).cmp(&key) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(middle),
+            }
+        }
+        None
+    }
+}
+
+/// List of strings, stored in a file as end offsets followed by the
+/// concatenated bytes.
+#[derive(Clone)]
+pub(crate) enum Strings {
+    Mapped {
+        ends: Array<u64>,
+        buffer: Buffer,
+        data: Range<usize>,
+        /// Set once `Reader::strings` has checked the whole list.
+        validated: bool,
+    },
+    Owned(Vec<String>),
+}
+
+impl Strings {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Strings::Mapped { ends, .. } => ends.len(),
+            Strings::Owned(strings) => strings.len(),
+        }
+    }
+
+    pub(crate) fn get(&self, i: usize) -> &str {
+        let (ends, buffer, data, validated) = match self {
+            Strings::Mapped { ends, buffer, data, validated } => (ends, buffer, data, *validated),
+            Strings::Owned(strings) => return &strings[i],
+        };
+        let from = if i == 0 { 0 } else { ends.get(i - 1) as usize };
+        let bytes = &buffer.bytes()[data.clone()][from..ends.get(i) as usize];
+        if validated {
+            // SAFETY: `Reader::strings` checked that the data is UTF-8 and
+            // that every end offset falls on a char boundary within it.
+            unsafe { std::str::from_utf8_unchecked(bytes) }
+        } else {
+            std::str::from_utf8(bytes).expect("corrupt index, load it with verify=True to check it")
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        (0..self.len()).map(move |i| self.get(i))
+    }
+
+    /// Position of `key` in a list sorted by bytes.
+    pub(crate) fn find(&self, key: &str) -> Option<usize> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.get(middle).cmp(key) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(middle),
+            }
+        }
+        None
+    }
+}
+
+impl From<Vec<String>> for Strings {
+    fn from(strings: Vec<String>) -> Self {
+        Strings::Owned(strings)
+    }
+}
+
+/// String keys, each owning a range of a parallel value array. Read from a
+/// file they are sorted and searched in place; built in memory they are
+/// hashed, with their ranges still laid out in key order.
+#[derive(Clone)]
+pub(crate) enum StrKeys {
+    Mapped { keys: Strings, offsets: Array<u64> },
+    Owned(HashMap<String, Range<usize>>),
+}
+
+impl StrKeys {
+    /// Keys of an index built in memory, given in sorted order with the
+    /// number of values each one owns.
+    pub(crate) fn owned(entries: impl IntoIterator<Item = (String, usize)>) -> StrKeys {
+        let mut total = 0;
+        let ranges = entries
+            .into_iter()
+            .map(|(key, len)| {
+                total += len;
+                (key, total - len..total)
+            })
+            .collect();
+        StrKeys::Owned(ranges)
+    }
+
+    pub(crate) fn range(&self, key: &str) -> Option<Range<usize>> {
+        match self {
+            StrKeys::Mapped { keys, offsets } => keys
+                .find(key)
+                .map(|i| offsets.get(i) as usize..offsets.get(i + 1) as usize),
+            StrKeys::Owned(ranges) => ranges.get(key).cloned(),
+        }
+    }
+
+    /// Every key with its value range, in key order.
+    pub(crate) fn entries(&self) -> Vec<(&str, Range<usize>)> {
+        match self {
+            StrKeys::Mapped { keys, offsets } => keys
+                .iter()
+                .enumerate()
+                .map(|(i, key)| (key, offsets.get(i) as usize..offsets.get(i + 1) as usize))
+                .collect(),
+            StrKeys::Owned(ranges) => {
+                let mut entries: Vec<_> = ranges.iter().map(|(key, range)| (key.as_str(), range.clone())).collect();
+                entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                entries
+            }
+        }
+    }
+
+    /// Number of values owned by all keys.
+    pub(crate) fn total(&self) -> usize {
+        match self {
+            StrKeys::Mapped { offsets, .. } => offsets.get(offsets.len() - 1) as usize,
+            StrKeys::Owned(ranges) => ranges.values().map(Range::len).sum(),
+        }
+    }
+}
+
+/// Sorted integer keys, each owning a range of a parallel value array.
+#[derive(Clone)]
+pub(crate) struct IntKeys {
+    pub(crate) keys: Array<u64>,
+    pub(crate) offsets: Array<u64>,
+}
+
+impl IntKeys {
+    pub(crate) fn range(&self, key: u64) -> Option<Range<usize>> {
+        self.range_within(key, 0..self.keys.len())
+    }
+
+    pub(crate) fn range_within(&self, key: u64, within: Range<usize>) -> Option<Range<usize>> {
+        self.keys
+            .find(key, within)
+            .map(|i| self.nth(i))
+    }
+
+    /// Value range of the `i`-th key.
+    pub(crate) fn nth(&self, i: usize) -> Range<usize> {
+        self.offsets.get(i) as usize..self.offsets.get(i + 1) as usize
+    }
+
+    /// Number of values owned by all keys.
+    pub(crate) fn total(&self) -> usize {
+        self.offsets.get(self.offsets.len() - 1) as usize
+    }
+}
+
+/// Accumulates sections and lays them out behind the header.
+#[derive(Default)]
+pub(crate) struct Writer {
+    sections: Vec<Vec<u8>>,
+}
+
+impl Writer {
+    pub(crate) fn array<T: Element>(&mut self, values: impl IntoIterator<Item = T>) {
+        let mut bytes = Vec::new();
+        for value in values {
+            value.write(&mut bytes);
+        }
+        self.sections.push(bytes);
+    }
+
+    pub(crate) fn strings<'a>(&mut self, values: impl IntoIterator<Item = &'a str>) {
+        let mut ends = Vec::new();
+        let mut data = Vec::new();
+        for value in values {
+            data.extend_from_slice(value.as_bytes());
+            (data.len() as u64).write(&mut ends);
+        }
+        self.sections.push(ends);
+        self.sections.push(data);
+    }
+
+    /// Writes `map` sorted by key: the keys, the offsets of each key's values
+    /// and the concatenated values themselves.
+    pub(crate) fn str_multimap<'a, V: Element + 'a>(&mut self, map: impl IntoIterator<Item = (&'a str, &'a [V])>) {
+        let mut entries: Vec<_> = map.into_iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        self.strings(entries.iter().map(|e| e.0));
+        self.offsets(entries.iter().map(|e| e.1.len()));
+        self.array(entries.iter().flat_map(|e| e.1.iter().copied()));
+    }
+
+    /// Writes keys in the layout `Reader::str_keys` reads, leaving their
+    /// values to the caller.
+    pub(crate) fn str_keys(&mut self, keys: &StrKeys) {
+        let entries = keys.entries();
+        self.strings(entries.iter().map(|e| e.0));
+        self.offsets(entries.iter().map(|e| e.1.len()));
+    }
+
+    /// Like `str_keys`, for integer keys.
+    pub(crate) fn int_keys(&mut self, keys: &IntKeys) {
+        self.array(keys.keys.iter());
+        self.array(keys.offsets.iter());
+    }
+
+    /// Writes the offsets delimiting consecutive runs of the given lengths.
+    pub(crate) fn offsets(&mut self, lengths: impl Iterator<Item = usize>) {
+        self.array(offsets(lengths));
+    }
+
+    pub(crate) fn finish(self, kind: Kind) -> Buffer {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        FORMAT_VERSION.write(&mut out);
+        (kind as u32).write(&mut out);
+        (self.sections.len() as u64).write(&mut out);
+        0u64.write(&mut out);
+        let mut offset = HEADER + 16 * self.sections.len();
+        for section in &self.sections {
+            offset = align(offset);
+            (offset as u64).write(&mut out);
+            (section.len() as u64).write(&mut out);
+            offset += section.len();
+        }
+        for section in &self.sections {
+            out.resize(align(out.len()), 0);
+            out.extend_from_slice(section);
+        }
+        let checksum = fnv1a(&out[HEADER..]);
+        out[24..HEADER].copy_from_slice(&checksum.to_le_bytes());
+        Buffer::from_bytes(out)
+    }
+}
+
+/// Offsets delimiting consecutive runs of the given lengths.
+pub(crate) fn offsets(lengths: impl Iterator<Item = usize>) -> Vec<u64> {
+    let mut total = 0u64;
+    std::iter::once(0)
+        .chain(lengths.map(|l| {
+            total += l as u64;
+            total
+        }))
+        .collect()
+}
+
+/// Hands out the sections of a buffer in the order they were written.
+pub(crate) struct Reader {
+    buffer: Buffer,
+    sections: Vec<Range<usize>>,
+    next: usize,
+    /// Check the contents of the sections, not just their bounds.
+    verify: bool,
+}
+
+impl Reader {
+    /// Reads the header of `buffer`. With `verify` the checksum is compared
+    /// and every section is checked as it is read, which touches the whole
+    /// buffer; without it opening costs the same for any file size.
+    pub(crate) fn new(buffer: Buffer, kind: Kind, verify: bool) -> PyResult<Reader> {
+        let bytes = buffer.bytes();
+        if bytes.len() < HEADER || &bytes[..8] != MAGIC {
+            return Err(invalid("not an xdistances index"));
+        }
+        let version = u32::read(&bytes[8..12]);
+        if version != FORMAT_VERSION {
+            return Err(invalid(&format!(
+                "format version {} is not supported, expected {}",
+                version, FORMAT_VERSION
+            )));
+        }
+        if u32::read(&bytes[12..16]) != kind as u32 {
+            return Err(invalid(&format!("not a {:?}", kind)));
+        }
+        let count = u64::read(&bytes[16..24]) as usize;
+        if count > (bytes.len() - HEADER) / 16 {
+            return Err(invalid("truncated header"));
+        }
+        if verify && u64::read(&bytes[24..HEADER]) != fnv1a(&bytes[HEADER..]) {
+            return Err(invalid("checksum mismatch"));
+        }
+        let mut sections = Vec::with_capacity(count);
+        for i in 0..count {
+            let at = HEADER + 16 * i;
+            let start = u64::read(&bytes[at..at + 8]) as usize;
+            let len = u64::read(&bytes[at + 8..at + 16]) as usize;
+            match start.checked_add(len) {
+                Some(end) if end <= bytes.len() => {}
+                _ => return Err(invalid("section out of bounds")),
+            }
+            sections.push(start..start + len);
+        }
+        Ok(Reader { buffer, sections, next: 0, verify })
+    }
+
+    pub(crate) fn verifies(&self) -> bool {
+        self.verify
+    }
+
+    fn section(&mut self) -> PyResult<Range<usize>> {
+        let section = self.sections.get(self.next).cloned().ok_or_else(|| invalid("missing section"))?;
+        self.next += 1;
+        Ok(section)
+    }
+
+    pub(crate) fn array<T: Element>(&mut self) -> PyResult<Array<T>> {
+        let section = self.section()?;
+        if section.len() % T::SIZE != 0 {
+            return Err(invalid("misaligned array"));
+        }
+        Ok(Array::Mapped {
+            buffer: self.buffer.clone(),
+            start: section.start,
+            len: section.len() / T::SIZE,
+            element: PhantomData,
+        })
+    }
+
+    pub(crate) fn strings(&mut self) -> PyResult<Strings> {
+        let ends: Array<u64> = self.array()?;
+        let data = self.section()?;
+        if self.verify {
+            let text = std::str::from_utf8(&self.buffer.bytes()[data.clone()]).map_err(|_| invalid("invalid UTF-8"))?;
+            let mut previous = 0;
+            for end in ends.iter() {
+                let end = end as usize;
+                if end < previous || end > text.len() || !text.is_char_boundary(end) {
+                    return Err(invalid("invalid string offsets"));
+                }
+                previous = end;
+            }
+        }
+        Ok(Strings::Mapped { ends, buffer: self.buffer.clone(), data, validated: self.verify })
+    }
+
+    fn offsets(&mut self, keys: usize) -> PyResult<Array<u64>> {
+        let offsets: Array<u64> = self.array()?;
+        if offsets.len() != keys + 1
+            || (self.verify && offsets.iter().zip(offsets.iter().skip(1)).any(|(a, b)| a > b))
+        {
+            return Err(invalid("invalid offsets"));
+        }
+        Ok(offsets)
+    }
+
+    /// Reads the keys and offsets written by `Writer::str_multimap` or
+    /// `Writer::str_keys`.
+    pub(crate) fn str_keys(&mut self) -> PyResult<StrKeys> {
+        let keys = self.strings()?;
+        let offsets = self.offsets(keys.len())?;
+        Ok(StrKeys::Mapped { keys, offsets })
+    }
+
+    /// Reads the keys and offsets written by `Writer::int_keys`.
+    pub(crate) fn int_keys(&mut self) -> PyResult<IntKeys> {
+        let keys: Array<u64> = self.array()?;
+        let offsets = self.offsets(keys.len())?;
+        Ok(IntKeys { keys, offsets })
+    }
+
+    /// Reads the `total` values owned by a set of keys.
+    pub(crate) fn values<V: Element>(&mut self, total: usize) -> PyResult<Array<V>> {
+        let values = self.array()?;
+        if total != values.len() {
+            return Err(invalid("offsets do not match values"));
+        }
+        Ok(values)
+    }
+
+    /// Checks that every id in `ids` indexes a list of `len` items, when
+    /// verifying.
+    pub(crate) fn check_ids(&self, ids: &Array<u32>, len: usize) -> PyResult<()> {
+        if self.verify && ids.iter().any(|id| id as usize >= len) {
+            return Err(invalid("id out of range"));
+        }
+        Ok(())
+    }
+}
+
+/// Reads a fixed-length array of parameters.
+pub(crate) fn params(reader: &mut Reader, count: usize) -> PyResult<Vec<u64>> {
+    let params: Array<u64> = reader.array()?;
+    if params.len() != count {
+        return Err(invalid("unexpected parameters"));
+    }
+    Ok(params.iter().collect())
+}
+
+pub(crate) fn invalid_param(name: &str) -> PyErr {
+    invalid(&format!("invalid {}", name))
+}
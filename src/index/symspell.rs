@@ -0,0 +1,700 @@
+//! Symmetric delete spelling correction.
+//!
+//! Every dictionary word is stored under all the strings obtained by deleting
+//! up to `max_distance` characters from its prefix. A term is looked up by
+//! generating its own deletes the same way, so two strings within the maximum
+//! distance always meet on a shared delete; candidates are then verified with
+//! the OSA or the Damerau-Levenshtein distance.
+
+use std::collections::{HashMap, HashSet};
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rayon::prelude::*;
+
+use super::storage::{self, Array, Buffer, Kind, Reader, StrKeys, Strings, Writer};
+use crate::distance::Unit;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Metric {
+    OsaDistance,
+    DamerauLevenshtein,
+}
+
+impl Metric {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "osa_distance" => Ok(Metric::OsaDistance),
+            "damerau_levenshtein" => Ok(Metric::DamerauLevenshtein),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unsupported metric for SymSpell: {}",
+                name
+            ))),
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Metric::OsaDistance => "osa_distance",
+            Metric::DamerauLevenshtein => "damerau_levenshtein",
+        }
+    }
+
+    fn distance(&self, a: &str, b: &str) -> usize {
+        match self {
+            Metric::OsaDistance => Unit::Char.osa_distance(&a.into(), &b.into()),
+            Metric::DamerauLevenshtein => Unit::Char.damerau_levenshtein(&a.into(), &b.into()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Verbosity {
+    /// The closest suggestion with the highest frequency.
+    Top,
+    /// Every suggestion at the smallest distance found.
+    Closest,
+    /// Every suggestion within the maximum distance.
+    All,
+}
+
+impl Verbosity {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "top" => Ok(Verbosity::Top),
+            "closest" => Ok(Verbosity::Closest),
+            "all" => Ok(Verbosity::All),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unknown verbosity: {}",
+                name
+            ))),
+        }
+    }
+}
+
+/// A suggestion: dictionary word, distance to the term and word frequency.
+pub(crate) type Suggestion = (String, usize, u64);
+
+/// Inserts every string obtained by deleting up to `max_distance - distance`
+/// characters from `word` into `deletes`.
+fn edits(word: &[char], distance: usize, max_distance: usize, deletes: &mut HashSet<String>) {
+    if distance >= max_distance {
+        return;
+    }
+    for i in 0..word.len() {
+        let mut shorter = word.to_vec();
+        shorter.remove(i);
+        // Every string of a given length sits at the same depth, so one seen
+        // before has already been expanded.
+        if deletes.insert(shorter.iter().collect()) {
+            edits(&shorter, distance + 1, max_distance, deletes);
+        }
+    }
+}
+
+/// SymSpell(max_distance=2, prefix_length=7, metric="osa_distance")
+///
+/// Symmetric delete dictionary for fast spelling correction. Words are added
+/// with :py:meth:`SymSpell.add` and looked up with :py:meth:`SymSpell.lookup`,
+/// :py:meth:`SymSpell.lookup_compound` or :py:meth:`SymSpell.word_segmentation`.
+/// Only the first ``prefix_length`` characters of each word are used to
+/// generate deletes, which bounds memory for long words.
+///
+/// :param int max_distance: largest edit distance supported by lookups
+/// :param int prefix_length: number of leading characters used for deletes
+/// :param str metric: ``"osa_distance"`` or ``"damerau_levenshtein"``
+/// :raises ValueError: if the metric is unknown or prefix_length is not larger than max_distance
+#[pyclass(module = "xdistances")]
+pub(crate) struct SymSpell {
+    pub(crate) max_distance: usize,
+    pub(crate) prefix_length: usize,
+    pub(crate) metric: Metric,
+    /// Dictionary read from a file; its words take the first ids.
+    base: Option<Frozen>,
+    /// Words added since construction or load, numbered after `base`.
+    words: Vec<String>,
+    frequencies: Vec<u64>,
+    /// Word -> id, for words in `words`.
+    ids: HashMap<String, u32>,
+    /// Delete -> ids of the words in `words` it was generated from.
+    deletes: HashMap<String, Vec<u32>>,
+    /// Frequency added to words of `base` since it was loaded.
+    boosts: HashMap<u32, u64>,
+    /// Length in chars of the longest word.
+    max_length: usize,
+    /// Sum of all word frequencies, used as the corpus size.
+    total: u64,
+}
+
+/// Read-only dictionary laid out by `SymSpell::freeze`.
+struct Frozen {
+    buffer: Buffer,
+    words: Strings,
+    frequencies: Array<u64>,
+    /// Word ids in word order, for exact lookups.
+    sorted: Array<u32>,
+    deletes: StrKeys,
+    delete_ids: Array<u32>,
+}
+
+impl SymSpell {
+    pub(crate) fn new(max_distance: usize, prefix_length: usize, metric: Metric) -> PyResult<Self> {
+        if prefix_length <= max_distance {
+            return Err(exceptions::PyValueError::new_err(
+                "prefix_length must be larger than max_distance",
+            ));
+        }
+        Ok(SymSpell {
+            max_distance,
+            prefix_length,
+            metric,
+            base: None,
+            words: Vec::new(),
+            frequencies: Vec::new(),
+            ids: HashMap::new(),
+            deletes: HashMap::new(),
+            boosts: HashMap::new(),
+            max_length: 0,
+            total: 0,
+        })
+    }
+
+    fn from_buffer(buffer: Buffer, verify: bool) -> PyResult<Self> {
+        let mut reader = Reader::new(buffer.clone(), Kind::SymSpell, verify)?;
+        let params = storage::params(&mut reader, 5)?;
+        let metric = match params[2] {
+            0 => Metric::OsaDistance,
+            1 => Metric::DamerauLevenshtein,
+            _ => return Err(storage::invalid_param("metric")),
+        };
+        let mut symspell = SymSpell::new(params[0] as usize, params[1] as usize, metric)
+            .map_err(|_| storage::invalid_param("prefix_length"))?;
+        let words = reader.strings()?;
+        let frequencies: Array<u64> = reader.array()?;
+        let sorted = reader.array()?;
+        let deletes = reader.str_keys()?;
+        let delete_ids = reader.values(deletes.total())?;
+        if frequencies.len() != words.len() || sorted.len() != words.len() {
+            return Err(storage::invalid_param("frequencies"));
+        }
+        reader.check_ids(&sorted, words.len())?;
+        reader.check_ids(&delete_ids, words.len())?;
+        symspell.max_length = params[3] as usize;
+        symspell.total = params[4];
+        symspell.base = Some(Frozen { buffer, words, frequencies, sorted, deletes, delete_ids });
+        Ok(symspell)
+    }
+
+    /// Lays out every word, base and added alike, in the on-disk format.
+    fn freeze(&self) -> Buffer {
+        let count = self.base_len() + self.words.len();
+        let mut deletes: HashMap<&str, Vec<u32>> = HashMap::new();
+        if let Some(base) = &self.base {
+            for (key, range) in base.deletes.entries() {
+                deletes.entry(key).or_default().extend(base.delete_ids.range(range));
+            }
+        }
+        for (key, ids) in &self.deletes {
+            deletes.entry(key).or_default().extend(ids);
+        }
+        let mut sorted: Vec<u32> = (0..count as u32).collect();
+        sorted.sort_unstable_by(|&a, &b| self.word(a).cmp(self.word(b)));
+
+        let mut writer = Writer::default();
+        writer.array(vec![
+            self.max_distance as u64,
+            self.prefix_length as u64,
+            self.metric as u64,
+            self.max_length as u64,
+            self.total,
+        ]);
+        writer.strings((0..count as u32).map(|id| self.word(id)));
+        writer.array((0..count as u32).map(|id| self.frequency(id)));
+        writer.array(sorted);
+        writer.str_multimap(deletes.iter().map(|(&key, ids)| (key, ids.as_slice())));
+        writer.finish(Kind::SymSpell)
+    }
+
+    fn base_len(&self) -> usize {
+        self.base.as_ref().map_or(0, |base| base.words.len())
+    }
+
+    fn word(&self, id: u32) -> &str {
+        let id = id as usize;
+        match &self.base {
+            Some(base) if id < base.words.len() => base.words.get(id),
+            _ => &self.words[id - self.base_len()],
+        }
+    }
+
+    fn frequency(&self, id: u32) -> u64 {
+        match &self.base {
+            Some(base) if (id as usize) < base.words.len() => base
+                .frequencies
+                .get(id as usize)
+                .saturating_add(self.boosts.get(&id).copied().unwrap_or(0)),
+            _ => self.frequencies[id as usize - self.base_len()],
+        }
+    }
+
+    fn id_of(&self, word: &str) -> Option<u32> {
+        if let Some(&id) = self.ids.get(word) {
+            return Some(id);
+        }
+        let base = self.base.as_ref()?;
+        let (mut low, mut high) = (0, base.sorted.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let id = base.sorted.get(middle);
+            match base.words.get(id as usize).cmp(word) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(id),
+            }
+        }
+        None
+    }
+
+    /// Ids of the words stored under `delete`, in the base and added since.
+    fn candidates(&self, delete: &str) -> Vec<u32> {
+        let mut ids = Vec::new();
+        if let Some(base) = &self.base {
+            if let Some(range) = base.deletes.range(delete) {
+                ids.extend(base.delete_ids.range(range));
+            }
+        }
+        ids.extend(self.deletes.get(delete).into_iter().flatten());
+        ids
+    }
+
+    fn prefix_deletes(&self, term: &str, max_distance: usize) -> HashSet<String> {
+        let prefix: Vec<char> = term.chars().take(self.prefix_length).collect();
+        let mut deletes = HashSet::new();
+        deletes.insert(prefix.iter().collect());
+        edits(&prefix, 0, max_distance, &mut deletes);
+        deletes
+    }
+
+    pub(crate) fn add_word(&mut self, word: &str, frequency: u64) {
+        self.total = self.total.saturating_add(frequency);
+        if let Some(id) = self.id_of(word) {
+            let base_len = self.base_len();
+            let count = if (id as usize) < base_len {
+                self.boosts.entry(id).or_insert(0)
+            } else {
+                &mut self.frequencies[id as usize - base_len]
+            };
+            *count = count.saturating_add(frequency);
+            return;
+        }
+        let id = (self.base_len() + self.words.len()) as u32;
+        for delete in self.prefix_deletes(word, self.max_distance) {
+            self.deletes.entry(delete).or_default().push(id);
+        }
+        self.max_length = self.max_length.max(word.chars().count());
+        self.ids.insert(word.to_string(), id);
+        self.words.push(word.to_string());
+        self.frequencies.push(frequency);
+    }
+
+    fn lookup_term(&self, term: &str, verbosity: Verbosity, max_distance: usize) -> Vec<Suggestion> {
+        let length = term.chars().count();
+        let mut suggestions: Vec<Suggestion> = Vec::new();
+        if length > self.max_length + max_distance {
+            return suggestions;
+        }
+        if let Some(id) = self.id_of(term) {
+            suggestions.push((term.to_string(), 0, self.frequency(id)));
+            if verbosity != Verbosity::All {
+                return suggestions;
+            }
+        }
+        if max_distance == 0 {
+            return suggestions;
+        }
+
+        let mut seen: HashSet<u32> = HashSet::new();
+        let mut best = max_distance;
+        for delete in self.prefix_deletes(term, max_distance) {
+            for id in self.candidates(&delete) {
+                if !seen.insert(id) {
+                    continue;
+                }
+                let word = self.word(id);
+                if word == term {
+                    continue;
+                }
+                let difference = (word.chars().count() as isize - length as isize).unsigned_abs();
+                if difference > best {
+                    continue;
+                }
+                let distance = self.metric.distance(term, word);
+                if distance > best {
+                    continue;
+                }
+                if verbosity != Verbosity::All {
+                    best = distance;
+                }
+                suggestions.push((word.to_string(), distance, self.frequency(id)));
+            }
+        }
+
+        suggestions.retain(|s| s.1 <= best);
+        suggestions.sort_by(|a, b| a.1.cmp(&b.1).then(b.2.cmp(&a.2)).then(a.0.cmp(&b.0)));
+        if verbosity == Verbosity::Top {
+            suggestions.truncate(1);
+        }
+        suggestions
+    }
+
+    fn top(&self, term: &str, max_distance: usize) -> Option<Suggestion> {
+        self.lookup_term(term, Verbosity::Top, max_distance).pop()
+    }
+
+    /// Probability of a word under the dictionary's unigram model.
+    fn probability(&self, frequency: u64) -> f64 {
+        frequency as f64 / self.total.max(1) as f64
+    }
+
+    fn compound(&self, phrase: &str, max_distance: usize) -> Suggestion {
+        let terms: Vec<&str> = phrase.split_whitespace().collect();
+        let mut parts: Vec<Suggestion> = Vec::new();
+        let mut last_combined = false;
+
+        for (i, term) in terms.iter().enumerate() {
+            let suggestion = self.top(term, max_distance);
+
+            // Try merging with the previous term, e.g. "the re" -> "there".
+            if i > 0 && !last_combined {
+                let combined = format!("{}{}", terms[i - 1], term);
+                if let Some(merged) = self.top(&combined, max_distance) {
+                    let previous = parts.last().expect("one part per previous term").clone();
+                    let current = suggestion
+                        .clone()
+                        .unwrap_or_else(|| (term.to_string(), max_distance + 1, 0));
+                    let separate = previous.1 + current.1;
+                    let together = merged.1 + 1;
+                    let separate_probability =
+                        self.probability(previous.2) * self.probability(current.2);
+                    if together < separate
+                        || (together == separate && self.probability(merged.2) > separate_probability)
+                    {
+                        let last = parts.len() - 1;
+                        parts[last] = (merged.0, together, merged.2);
+                        last_combined = true;
+                        continue;
+                    }
+                }
+            }
+            last_combined = false;
+
+            if let Some(found) = &suggestion {
+                if found.1 == 0 || term.chars().count() == 1 {
+                    parts.push(found.clone());
+                    continue;
+                }
+            }
+
+            // Try splitting the term in two, e.g. "inthe" -> "in the".
+            let mut best = suggestion;
+            let chars: Vec<char> = term.chars().collect();
+            for j in 1..chars.len() {
+                let head: String = chars[..j].iter().collect();
+                let tail: String = chars[j..].iter().collect();
+                if let (Some(first), Some(second)) =
+                    (self.top(&head, max_distance), self.top(&tail, max_distance))
+                {
+                    let joined = format!("{} {}", first.0, second.0);
+                    let distance = self.metric.distance(term, &joined);
+                    let frequency = (self.probability(first.2)
+                        * self.probability(second.2)
+                        * self.total as f64) as u64;
+                    let better = match &best {
+                        None => true,
+                        Some(current) => {
+                            distance < current.1 || (distance == current.1 && frequency > current.2)
+                        }
+                    };
+                    if better {
+                        best = Some((joined, distance, frequency));
+                    }
+                }
+            }
+            parts.push(best.unwrap_or_else(|| (term.to_string(), max_distance + 1, 0)));
+        }
+
+        let corrected = parts
+            .iter()
+            .map(|p| p.0.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let probability: f64 = parts.iter().map(|p| self.probability(p.2)).product();
+        let frequency = (probability * self.total as f64) as u64;
+        let distance = self.metric.distance(phrase, &corrected);
+        (corrected, distance, frequency)
+    }
+
+    fn segment(&self, phrase: &str, max_distance: usize) -> (String, String, usize, f64) {
+        let chars: Vec<char> = phrase.chars().filter(|c| !c.is_whitespace()).collect();
+        let total = self.total.max(1) as f64;
+        // best[i] is the best segmentation of chars[..i]:
+        // (segmented, corrected, distance sum, log10 probability sum)
+        let mut best: Vec<Option<(String, String, usize, f64)>> = vec![None; chars.len() + 1];
+        best[0] = Some((String::new(), String::new(), 0, 0.0));
+
+        for start in 0..chars.len() {
+            let prefix = match best[start].clone() {
+                Some(prefix) => prefix,
+                None => continue,
+            };
+            let longest = self.max_length.max(1).min(chars.len() - start);
+            for end in start + 1..=start + longest {
+                let part: String = chars[start..end].iter().collect();
+                let (word, distance, log_probability) = match self.top(&part, max_distance) {
+                    Some((word, distance, frequency)) => {
+                        (word, distance, (frequency as f64 / total).log10())
+                    }
+                    // Unknown words get a probability that decays with length.
+                    None => {
+                        let length = end - start;
+                        (part.clone(), length, (10.0 / (total * 10f64.powi(length as i32))).log10())
+                    }
+                };
+                let separator = if start > 0 { 1 } else { 0 };
+                let candidate_distance = prefix.2 + separator + distance;
+                let candidate_probability = prefix.3 + log_probability;
+                let better = match &best[end] {
+                    None => true,
+                    Some(current) => {
+                        candidate_distance < current.2
+                            || (candidate_distance == current.2 && candidate_probability > current.3)
+                    }
+                };
+                if better {
+                    let join = |head: &str, tail: &str| {
+                        if head.is_empty() {
+                            tail.to_string()
+                        } else {
+                            format!("{} {}", head, tail)
+                        }
+                    };
+                    best[end] = Some((
+                        join(&prefix.0, &part),
+                        join(&prefix.1, &word),
+                        candidate_distance,
+                        candidate_probability,
+                    ));
+                }
+            }
+        }
+        best[chars.len()].clone().expect("every position is reachable")
+    }
+
+    fn check_distance(&self, max_distance: Option<usize>) -> PyResult<usize> {
+        match max_distance {
+            Some(d) if d > self.max_distance => Err(exceptions::PyValueError::new_err(
+                "max_distance exceeds the dictionary's max_distance",
+            )),
+            Some(d) => Ok(d),
+            None => Ok(self.max_distance),
+        }
+    }
+}
+
+#[pymethods]
+impl SymSpell {
+    #[new]
+    #[pyo3(signature = (max_distance=2, prefix_length=7, metric="osa_distance"))]
+    fn py_new(max_distance: usize, prefix_length: usize, metric: &str) -> PyResult<Self> {
+        SymSpell::new(max_distance, prefix_length, Metric::parse(metric)?)
+    }
+
+    /// add(word, frequency=1)
+    ///
+    /// Adds a word to the dictionary, or increases its frequency if it is
+    /// already present.
+    ///
+    /// :param str word: dictionary word
+    /// :param int frequency: number of occurrences of the word
+    #[pyo3(signature = (word, frequency=1))]
+    fn add(&mut self, word: &str, frequency: u64) {
+        self.add_word(word, frequency);
+    }
+
+    /// lookup(term, verbosity="closest", max_distance=None)
+    ///
+    /// Finds dictionary words within ``max_distance`` of ``term``, sorted by
+    /// distance then decreasing frequency. ``verbosity`` is ``"top"`` for the
+    /// single best suggestion, ``"closest"`` for every suggestion at the
+    /// smallest distance or ``"all"`` for every suggestion in range.
+    ///
+    /// :param str term: word to correct
+    /// :param str verbosity: ``"top"``, ``"closest"`` or ``"all"``
+    /// :param int max_distance: defaults to the dictionary's max_distance
+    /// :return: list of ``(word, distance, frequency)`` tuples
+    /// :rtype: list
+    /// :raises ValueError: if max_distance exceeds the dictionary's max_distance
+    #[pyo3(signature = (term, verbosity="closest", max_distance=None))]
+    fn lookup(
+        &self,
+        py: Python,
+        term: &str,
+        verbosity: &str,
+        max_distance: Option<usize>,
+    ) -> PyResult<Vec<Suggestion>> {
+        let verbosity = Verbosity::parse(verbosity)?;
+        let max_distance = self.check_distance(max_distance)?;
+        Ok(py.allow_threads(|| self.lookup_term(term, verbosity, max_distance)))
+    }
+
+    /// lookup_parallel(terms, verbosity="closest", max_distance=None)
+    ///
+    /// Like :py:meth:`SymSpell.lookup`, for many terms at once. Operates in
+    /// parallel over the list of terms.
+    ///
+    /// :param list terms: words to correct
+    /// :param str verbosity: ``"top"``, ``"closest"`` or ``"all"``
+    /// :param int max_distance: defaults to the dictionary's max_distance
+    /// :return: one list of ``(word, distance, frequency)`` tuples per term
+    /// :rtype: list
+    #[pyo3(signature = (terms, verbosity="closest", max_distance=None))]
+    fn lookup_parallel(
+        &self,
+        py: Python,
+        terms: Vec<&str>,
+        verbosity: &str,
+        max_distance: Option<usize>,
+    ) -> PyResult<Vec<Vec<Suggestion>>> {
+        let verbosity = Verbosity::parse(verbosity)?;
+        let max_distance = self.check_distance(max_distance)?;
+        Ok(py.allow_threads(|| {
+            terms
+                .par_iter()
+                .map(|term| self.lookup_term(term, verbosity, max_distance))
+                .collect()
+        }))
+    }
+
+    /// lookup_compound(phrase, max_distance=None)
+    ///
+    /// Corrects a multi-word phrase, merging terms split by a stray space and
+    /// splitting terms missing one. Frequencies of the resulting words are
+    /// combined under a naive Bayes unigram model.
+    ///
+    /// :param str phrase: text to correct
+    /// :param int max_distance: defaults to the dictionary's max_distance
+    /// :return: ``(corrected, distance, frequency)``
+    /// :rtype: tuple
+    #[pyo3(signature = (phrase, max_distance=None))]
+    fn lookup_compound(&self, py: Python, phrase: &str, max_distance: Option<usize>) -> PyResult<Suggestion> {
+        let max_distance = self.check_distance(max_distance)?;
+        Ok(py.allow_threads(|| self.compound(phrase, max_distance)))
+    }
+
+    /// word_segmentation(phrase, max_distance=0)
+    ///
+    /// Inserts spaces into text written without them, optionally correcting
+    /// each segment. Segmentations with the smallest total edit distance win,
+    /// ties going to the most probable one.
+    ///
+    /// :param str phrase: text to segment
+    /// :param int max_distance: maximum distance used to correct segments
+    /// :return: ``(segmented, corrected, distance, log10 probability)``
+    /// :rtype: tuple
+    #[pyo3(signature = (phrase, max_distance=0))]
+    fn word_segmentation(
+        &self,
+        py: Python,
+        phrase: &str,
+        max_distance: usize,
+    ) -> PyResult<(String, String, usize, f64)> {
+        let max_distance = self.check_distance(Some(max_distance))?;
+        Ok(py.allow_threads(|| self.segment(phrase, max_distance)))
+    }
+
+    /// save(path)
+    ///
+    /// Writes the dictionary to ``path`` in the versioned binary format read
+    /// by :py:meth:`SymSpell.load`.
+    ///
+    /// :param str path: destination file
+    /// :raises IOError: if the file cannot be written
+    fn save(&self, py: Python, path: &str) -> PyResult<()> {
+        py.allow_threads(|| self.freeze().save(path))
+    }
+
+    /// load(path, mmap=True, verify=False)
+    ///
+    /// Reads a dictionary written by :py:meth:`SymSpell.save`, mapping the
+    /// file read-only when ``mmap`` is set so that worker processes share it.
+    /// Words added afterwards are kept in memory on top of the mapped ones.
+    /// Set ``verify`` to check the file's checksum and contents before use.
+    ///
+    /// :param str path: dictionary file
+    /// :param bool mmap: memory-map the file instead of reading it
+    /// :param bool verify: check the whole file before using it
+    /// :return: dictionary
+    /// :rtype: SymSpell
+    /// :raises IOError: if the file cannot be read
+    /// :raises ValueError: if the file is not a SymSpell of a supported version,
+    ///     or fails verification
+    #[staticmethod]
+    #[pyo3(signature = (path, mmap=true, verify=false))]
+    fn load(py: Python, path: &str, mmap: bool, verify: bool) -> PyResult<Self> {
+        py.allow_threads(|| SymSpell::from_buffer(Buffer::open(path, mmap)?, verify))
+    }
+
+    /// __getstate__()
+    ///
+    /// Serialized dictionary, including words added since it was loaded. A
+    /// pickled dictionary is unpickled into memory, not mapped.
+    fn __getstate__<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, self.freeze().bytes())
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = SymSpell::from_buffer(Buffer::from_bytes(state.to_vec()), true)?;
+        Ok(())
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, (PyObject,))> {
+        let py = slf.py();
+        let state = slf.borrow().__getstate__(py).into();
+        Ok((slf.get_type().getattr("_from_state")?.into(), (state,)))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "_from_state")]
+    fn from_state(state: &[u8]) -> PyResult<Self> {
+        SymSpell::from_buffer(Buffer::from_bytes(state.to_vec()), true)
+    }
+
+    #[getter]
+    fn mapped(&self) -> bool {
+        self.base.as_ref().is_some_and(|base| base.buffer.is_mapped())
+    }
+
+    #[getter]
+    fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+
+    #[getter]
+    fn prefix_length(&self) -> usize {
+        self.prefix_length
+    }
+
+    #[getter]
+    fn metric(&self) -> &'static str {
+        self.metric.name()
+    }
+
+    fn __len__(&self) -> usize {
+        self.base_len() + self.words.len()
+    }
+
+    fn __contains__(&self, word: &str) -> bool {
+        self.id_of(word).is_some()
+    }
+}
@@ -0,0 +1,484 @@
+//! Vantage-point tree over `1 - similarity`.
+//!
+//! Each node splits its strings by their distance to a vantage point around
+//! the median `radius`; the triangle inequality then rules out whole subtrees
+//! during range and nearest-neighbour queries. Metrics that only approximately
+//! satisfy it may miss hits, which the `exhaustive` flag trades for a scan.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::ops::Range;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rayon::prelude::*;
+
+use super::storage::{self, Array, Buffer, Kind, Reader, Strings, Writer};
+use super::{sort_hits, Hit, Score};
+use crate::metric::Similarity;
+
+/// Subtrees at most this large are stored as a flat list and scanned.
+const LEAF_SIZE: usize = 16;
+
+/// Slack absorbing rounding in `1 - similarity` near the pruning bounds.
+const EPSILON: f64 = 1e-12;
+
+/// Node of a tree, read from the arrays of `VPTree`.
+#[derive(Clone, Debug)]
+enum Node {
+    /// Range of `leaf_ids` holding the strings of the leaf.
+    Leaf(Range<usize>),
+    Split {
+        vantage: u32,
+        radius: f64,
+        /// Node holding the strings at distance at most `radius`.
+        inside: usize,
+        /// Node holding strings at distance at least `radius`.
+        outside: usize,
+    },
+}
+
+/// Marks leaves in `VPTree::vantages`.
+const LEAF: u32 = u32::MAX;
+
+/// Candidate kept in the k-nearest heap, ordered by distance then id so that
+/// the heap's maximum is the worst hit.
+#[derive(PartialEq)]
+struct Neighbour(f64, u32);
+
+impl Eq for Neighbour {}
+
+impl PartialOrd for Neighbour {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Neighbour {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .partial_cmp(&other.0)
+            .unwrap_or(Ordering::Equal)
+            .then(self.1.cmp(&other.1))
+    }
+}
+
+/// VPTree
+///
+/// Vantage-point tree answering range and k-nearest-neighbour queries for a
+/// normalized similarity. Build it with :py:meth:`VPTree.build`.
+#[pyclass(module = "xdistances")]
+pub(crate) struct VPTree {
+    pub(crate) metric: Similarity,
+    pub(crate) exhaustive: bool,
+    /// File or pickle the tree was read from, `None` when built in memory.
+    buffer: Option<Buffer>,
+    strings: Strings,
+    /// Nodes in build order, the root first, as parallel arrays. Splits
+    /// store their vantage point, radius and children; leaves store `LEAF`
+    /// and the bounds of their range of `leaf_ids`.
+    vantages: Array<u32>,
+    radii: Array<f64>,
+    firsts: Array<u64>,
+    seconds: Array<u64>,
+    leaf_ids: Array<u32>,
+}
+
+/// Tree under construction.
+#[derive(Default)]
+struct Builder {
+    vantages: Vec<u32>,
+    radii: Vec<f64>,
+    firsts: Vec<u64>,
+    seconds: Vec<u64>,
+    leaf_ids: Vec<u32>,
+}
+
+impl Builder {
+    /// Builds the subtree over `ids` and returns the position of its root.
+    fn grow(&mut self, strings: &[String], metric: Similarity, mut ids: Vec<u32>) -> usize {
+        let position = self.vantages.len();
+        if ids.len() <= LEAF_SIZE {
+            self.vantages.push(LEAF);
+            self.radii.push(0.0);
+            self.firsts.push(self.leaf_ids.len() as u64);
+            self.leaf_ids.extend(ids);
+            self.seconds.push(self.leaf_ids.len() as u64);
+            return position;
+        }
+        // The middle element is as good a vantage point as a random one and
+        // keeps builds reproducible.
+        let vantage = ids.swap_remove(ids.len() / 2);
+        let origin = &strings[vantage as usize];
+        let mut distances: Vec<(f64, u32)> = ids
+            .par_iter()
+            .map(|&id| (metric.distance(origin, &strings[id as usize]), id))
+            .collect();
+        let middle = distances.len() / 2;
+        distances.select_nth_unstable_by(middle, |a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        let radius = distances[middle].0;
+        // Splitting at the median position rather than by value keeps the tree
+        // balanced when many strings tie with the radius.
+        let outside: Vec<u32> = distances.drain(middle + 1..).map(|(_, id)| id).collect();
+        let inside: Vec<u32> = distances.into_iter().map(|(_, id)| id).collect();
+
+        self.vantages.push(vantage);
+        self.radii.push(radius);
+        self.firsts.push(0);
+        self.seconds.push(0);
+        self.firsts[position] = self.grow(strings, metric, inside) as u64;
+        self.seconds[position] = self.grow(strings, metric, outside) as u64;
+        position
+    }
+}
+
+impl VPTree {
+    pub(crate) fn new(strings: Vec<String>, metric: Similarity, exhaustive: bool) -> PyResult<Self> {
+        if strings.len() > u32::MAX as usize - 1 {
+            return Err(exceptions::PyValueError::new_err("Too many strings to index"));
+        }
+        let mut builder = Builder::default();
+        builder.grow(&strings, metric, (0..strings.len() as u32).collect());
+        Ok(VPTree {
+            metric,
+            exhaustive,
+            buffer: None,
+            strings: strings.into(),
+            vantages: builder.vantages.into(),
+            radii: builder.radii.into(),
+            firsts: builder.firsts.into(),
+            seconds: builder.seconds.into(),
+            leaf_ids: builder.leaf_ids.into(),
+        })
+    }
+
+    fn from_buffer(buffer: Buffer, verify: bool) -> PyResult<Self> {
+        let mut reader = Reader::new(buffer.clone(), Kind::VPTree, verify)?;
+        let exhaustive = storage::params(&mut reader, 1)?[0] != 0;
+        let names = reader.strings()?;
+        if names.len() != 1 {
+            return Err(storage::invalid_param("metric"));
+        }
+        let metric = Similarity::parse(names.get(0)).map_err(|_| storage::invalid_param("metric"))?;
+        let strings = reader.strings()?;
+        let vantages: Array<u32> = reader.array()?;
+        let radii: Array<f64> = reader.array()?;
+        let firsts: Array<u64> = reader.array()?;
+        let seconds: Array<u64> = reader.array()?;
+        let leaf_ids = reader.array()?;
+        let nodes = vantages.len();
+        if [radii.len(), firsts.len(), seconds.len()].iter().any(|&len| len != nodes) {
+            return Err(storage::invalid_param("nodes"));
+        }
+        reader.check_ids(&leaf_ids, strings.len())?;
+        // Children must come after their parent so that a corrupt file cannot
+        // send queries round in circles; `node` checks the same when reading.
+        let valid = |position: usize| {
+            let (first, second) = (firsts.get(position) as usize, seconds.get(position) as usize);
+            match vantages.get(position) {
+                LEAF => first <= second && second <= leaf_ids.len(),
+                vantage => {
+                    (vantage as usize) < strings.len()
+                        && position < first.min(second)
+                        && first.max(second) < nodes
+                }
+            }
+        };
+        if reader.verifies() && !(0..nodes).all(valid) {
+            return Err(storage::invalid_param("nodes"));
+        }
+        Ok(VPTree { metric, exhaustive, buffer: Some(buffer), strings, vantages, radii, firsts, seconds, leaf_ids })
+    }
+
+    /// The tree in the format read by `from_buffer`.
+    fn buffer(&self) -> Buffer {
+        if let Some(buffer) = &self.buffer {
+            return buffer.clone();
+        }
+        let mut writer = Writer::default();
+        writer.array(vec![self.exhaustive as u64]);
+        writer.strings(std::iter::once(self.metric.name()));
+        writer.strings(self.strings.iter());
+        writer.array(self.vantages.iter());
+        writer.array(self.radii.iter());
+        writer.array(self.firsts.iter());
+        writer.array(self.seconds.iter());
+        writer.array(self.leaf_ids.iter());
+        writer.finish(Kind::VPTree)
+    }
+
+    fn node(&self, position: usize) -> Node {
+        let (first, second) = (self.firsts.get(position) as usize, self.seconds.get(position) as usize);
+        match self.vantages.get(position) {
+            LEAF => Node::Leaf(first..second),
+            vantage => {
+                assert!(position < first.min(second), "corrupt index, load it with verify=True to check it");
+                Node::Split { vantage, radius: self.radii.get(position), inside: first, outside: second }
+            }
+        }
+    }
+
+    fn distance(&self, query: &str, id: u32) -> f64 {
+        self.metric.distance(query, self.strings.get(id as usize))
+    }
+
+    fn hit(&self, id: u32, distance: f64) -> Hit {
+        (id as usize, self.strings.get(id as usize).to_string(), Score::Similarity(1.0 - distance))
+    }
+
+    pub(crate) fn within(&self, query: &str, min_similarity: f64) -> Vec<Hit> {
+        let limit = 1.0 - min_similarity;
+        let mut found: Vec<(u32, f64)> = Vec::new();
+        if self.exhaustive {
+            found = (0..self.strings.len() as u32)
+                .map(|id| (id, self.distance(query, id)))
+                .filter(|&(_, d)| d <= limit + EPSILON)
+                .collect();
+        } else if self.vantages.len() > 0 {
+            let mut pending = vec![0];
+            while let Some(position) = pending.pop() {
+                match self.node(position) {
+                    Node::Leaf(ids) => found.extend(
+                        self.leaf_ids
+                            .range(ids)
+                            .map(|id| (id, self.distance(query, id)))
+                            .filter(|&(_, d)| d <= limit + EPSILON),
+                    ),
+                    Node::Split { vantage, radius, inside, outside } => {
+                        let d = self.distance(query, vantage);
+                        if d <= limit + EPSILON {
+                            found.push((vantage, d));
+                        }
+                        if d - limit <= radius + EPSILON {
+                            pending.push(inside);
+                        }
+                        if d + limit >= radius - EPSILON {
+                            pending.push(outside);
+                        }
+                    }
+                }
+            }
+        }
+        // Similarities are compared exactly so results agree with the scalar
+        // functions regardless of the slack used while pruning.
+        let mut hits: Vec<Hit> = found
+            .into_iter()
+            .map(|(id, d)| self.hit(id, d))
+            .filter(|hit| matches!(hit.2, Score::Similarity(s) if s >= min_similarity))
+            .collect();
+        sort_hits(&mut hits);
+        hits
+    }
+
+    fn nearest(&self, query: &str, k: usize) -> Vec<Hit> {
+        let mut heap: BinaryHeap<Neighbour> = BinaryHeap::with_capacity(k + 1);
+        let offer = |heap: &mut BinaryHeap<Neighbour>, id: u32, d: f64| {
+            heap.push(Neighbour(d, id));
+            if heap.len() > k {
+                heap.pop();
+            }
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+        if self.exhaustive {
+            for id in 0..self.strings.len() as u32 {
+                offer(&mut heap, id, self.distance(query, id));
+            }
+        } else if self.vantages.len() > 0 {
+            // Subtrees are stacked with a lower bound on their distances and
+            // skipped once `k` closer strings have been found.
+            let mut pending = vec![(0, 0.0)];
+            while let Some((position, bound)) = pending.pop() {
+                let worst = if heap.len() < k {
+                    f64::INFINITY
+                } else {
+                    heap.peek().map_or(f64::INFINITY, |n| n.0)
+                };
+                if bound > worst + EPSILON {
+                    continue;
+                }
+                match self.node(position) {
+                    Node::Leaf(ids) => {
+                        for id in self.leaf_ids.range(ids) {
+                            offer(&mut heap, id, self.distance(query, id));
+                        }
+                    }
+                    Node::Split { vantage, radius, inside, outside } => {
+                        let d = self.distance(query, vantage);
+                        offer(&mut heap, vantage, d);
+                        // The far side goes first so the near side is explored
+                        // first and tightens the bound.
+                        if d <= radius {
+                            pending.push((outside, radius - d));
+                            pending.push((inside, 0.0));
+                        } else {
+                            pending.push((inside, d - radius));
+                            pending.push((outside, 0.0));
+                        }
+                    }
+                }
+            }
+        }
+        let mut hits: Vec<Hit> = heap.into_iter().map(|n| self.hit(n.1, n.0)).collect();
+        sort_hits(&mut hits);
+        hits
+    }
+}
+
+#[pymethods]
+impl VPTree {
+    /// build(strings, metric="normalized_levenshtein", exhaustive=False)
+    ///
+    /// Builds a tree over ``1 - metric(a, b)``. ``metric`` is one of
+    /// ``"normalized_levenshtein"``, ``"normalized_damerau_levenshtein"``,
+    /// ``"jaro"``, ``"jaro_winkler"``, ``"sorensen_dice"``,
+    /// ``"normalized_editex"``, ``"jaccard"``, ``"overlap_coefficient"`` or
+    /// ``"cosine"``, the q-gram ones with their default bigrams. Pruning assumes
+    /// the triangle inequality, which the normalized edit distances violate
+    /// only rarely and the other metrics routinely; set ``exhaustive`` to
+    /// verify every string when results must be exact.
+    ///
+    /// :param list strings: strings to index
+    /// :param str metric: similarity to search with
+    /// :param bool exhaustive: scan every string rather than prune
+    /// :return: tree
+    /// :rtype: VPTree
+    /// :raises ValueError: if the metric is unknown
+    #[staticmethod]
+    #[pyo3(signature = (strings, metric="normalized_levenshtein", exhaustive=false))]
+    fn build(py: Python, strings: Vec<String>, metric: &str, exhaustive: bool) -> PyResult<Self> {
+        let metric = Similarity::parse(metric)?;
+        py.allow_threads(|| VPTree::new(strings, metric, exhaustive))
+    }
+
+    /// range(query, min_similarity)
+    ///
+    /// Finds the indexed strings with a similarity of at least
+    /// ``min_similarity`` to ``query``, best first.
+    ///
+    /// :param str query: string to look up
+    /// :param float min_similarity: minimum similarity
+    /// :return: list of ``(index, string, similarity)`` tuples
+    /// :rtype: list
+    fn range(&self, py: Python, query: &str, min_similarity: f64) -> Vec<Hit> {
+        py.allow_threads(|| self.within(query, min_similarity))
+    }
+
+    /// range_parallel(queries, min_similarity)
+    ///
+    /// Like :py:meth:`VPTree.range`, for many queries at once. Operates in
+    /// parallel over the list of queries.
+    ///
+    /// :param list queries: strings to look up
+    /// :param float min_similarity: minimum similarity
+    /// :return: one list of ``(index, string, similarity)`` tuples per query
+    /// :rtype: list
+    fn range_parallel(&self, py: Python, queries: Vec<&str>, min_similarity: f64) -> Vec<Vec<Hit>> {
+        py.allow_threads(|| {
+            queries
+                .par_iter()
+                .map(|query| self.within(query, min_similarity))
+                .collect()
+        })
+    }
+
+    /// knn(query, k)
+    ///
+    /// Finds the ``k`` indexed strings most similar to ``query``, best first.
+    ///
+    /// :param str query: string to look up
+    /// :param int k: number of neighbours
+    /// :return: list of ``(index, string, similarity)`` tuples
+    /// :rtype: list
+    fn knn(&self, py: Python, query: &str, k: usize) -> Vec<Hit> {
+        py.allow_threads(|| self.nearest(query, k))
+    }
+
+    /// knn_parallel(queries, k)
+    ///
+    /// Like :py:meth:`VPTree.knn`, for many queries at once. Operates in
+    /// parallel over the list of queries.
+    ///
+    /// :param list queries: strings to look up
+    /// :param int k: number of neighbours
+    /// :return: one list of ``(index, string, similarity)`` tuples per query
+    /// :rtype: list
+    fn knn_parallel(&self, py: Python, queries: Vec<&str>, k: usize) -> Vec<Vec<Hit>> {
+        py.allow_threads(|| queries.par_iter().map(|query| self.nearest(query, k)).collect())
+    }
+
+    /// save(path)
+    ///
+    /// Writes the tree, its strings and its metric to ``path`` so that it can
+    /// be reopened without recomputing any distance.
+    ///
+    /// :param str path: destination file
+    /// :raises IOError: if the file cannot be written
+    fn save(&self, py: Python, path: &str) -> PyResult<()> {
+        py.allow_threads(|| self.buffer().save(path))
+    }
+
+    /// load(path, mmap=True, verify=False)
+    ///
+    /// Opens a tree written by :py:meth:`VPTree.save`, memory-mapped unless
+    /// ``mmap`` is false. With ``verify`` every node is checked up front.
+    ///
+    /// :param str path: tree file
+    /// :param bool mmap: memory-map the file instead of reading it
+    /// :param bool verify: check the whole file before using it
+    /// :return: tree
+    /// :rtype: VPTree
+    /// :raises IOError: if the file cannot be read
+    /// :raises ValueError: if the file is not a VPTree of a supported version,
+    ///     or fails verification
+    #[staticmethod]
+    #[pyo3(signature = (path, mmap=true, verify=false))]
+    fn load(py: Python, path: &str, mmap: bool, verify: bool) -> PyResult<Self> {
+        py.allow_threads(|| VPTree::from_buffer(Buffer::open(path, mmap)?, verify))
+    }
+
+    /// __getstate__()
+    ///
+    /// Serialized tree, as pickled; the same bytes :py:meth:`VPTree.save` writes.
+    fn __getstate__<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, self.buffer().bytes())
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = VPTree::from_buffer(Buffer::from_bytes(state.to_vec()), true)?;
+        Ok(())
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, (PyObject,))> {
+        let py = slf.py();
+        let state = slf.borrow().__getstate__(py).into();
+        Ok((slf.get_type().getattr("_from_state")?.into(), (state,)))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "_from_state")]
+    fn from_state(state: &[u8]) -> PyResult<Self> {
+        VPTree::from_buffer(Buffer::from_bytes(state.to_vec()), true)
+    }
+
+    #[getter]
+    fn mapped(&self) -> bool {
+        self.buffer.as_ref().is_some_and(Buffer::is_mapped)
+    }
+
+    #[getter]
+    fn metric(&self) -> &'static str {
+        self.metric.name()
+    }
+
+    #[getter]
+    fn exhaustive(&self) -> bool {
+        self.exhaustive
+    }
+
+    fn __len__(&self) -> usize {
+        self.strings.len()
+    }
+}
@@ -1,4 +1,6 @@
 #![deny(clippy::all)]
+// pyo3 0.19 expands `#[new]` into impls nested in functions.
+#![allow(non_local_definitions)]
 
 extern crate pyo3;
 use pyo3::exceptions;
@@ -6,41 +8,83 @@ use pyo3::prelude::*;
 use rayon::prelude::*;
 use paste::paste;
 use eddie::*;
+use distance::Text;
 use std::cmp::Ordering;
 
 extern crate strsim;
 extern crate eddie;
 
+mod cluster;
+mod distance;
+mod extract;
+mod index;
+mod metric;
+mod phonetic;
+mod process;
+mod sequence;
+mod token;
+
 macro_rules! wrapper {
-    ($(#[$doc:meta])* hamming -> $type:ty) => {
+    ($(#[$doc:meta])* unit hamming -> $type:ty) => {
         $(#[$doc])*
         #[pyfunction]
-        fn hamming(a: &str, b: &str) -> PyResult<$type> {
-            match strsim::hamming(a, b) {
+        #[pyo3(signature = (a, b, processor=None, unit="char"))]
+        fn hamming(a: Text, b: Text, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<$type> {
+            let unit = distance::Unit::parse(unit)?;
+            match unit.hamming(&process::apply(&processor, &a)?, &process::apply(&processor, &b)?) {
                 Ok(distance) => Ok(distance),
                 Err(_) => Err(exceptions::PyValueError::new_err("Length mismatch")),
             }
         }
     };
+    ($(#[$doc:meta])* unit $name:ident -> $type:ty) => {
+        $(#[$doc])*
+        #[pyfunction]
+        #[pyo3(signature = (a, b, processor=None, unit="char"))]
+        fn $name(a: Text, b: Text, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<$type> {
+            let unit = distance::Unit::parse(unit)?;
+            Ok(unit.$name(&process::apply(&processor, &a)?, &process::apply(&processor, &b)?))
+        }
+    };
     ($(#[$doc:meta])* $name:ident -> $type:ty) => {
         $(#[$doc])*
         #[pyfunction]
-        fn $name(a: &str, b: &str) -> PyResult<$type> {
-            Ok(strsim::$name(a, b))
+        #[pyo3(signature = (a, b, processor=None))]
+        fn $name(a: Text, b: Text, processor: Option<process::Preprocessor>) -> PyResult<$type> {
+            Ok(distance::$name(&process::apply(&processor, &a)?, &process::apply(&processor, &b)?))
         }
     };
 }
 
 macro_rules! parallel_wrapper {        
+    ($(#[$doc:meta])* unit $name:ident -> $type:ty) => {
+        paste! {
+            $(#[$doc])*
+            #[pyfunction]
+            #[pyo3(signature = (left, right, processor=None, unit="char"))]
+            fn [<$name _parallel>] (left: Vec<Text>, right: Vec<Text>, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<$type> {
+                let unit = distance::Unit::parse(unit)?;
+                let (left, right) = process::apply_pair(&processor, &left, &right)?;
+                Ok(
+                    (left, right)
+                    .into_par_iter()
+                    .map(|(x, y)| unit.$name(&x, &y))
+                    .collect()
+                )
+            }
+        }
+    };
     ($(#[$doc:meta])* $name:ident -> $type:ty) => {
         paste! {
             $(#[$doc])*
             #[pyfunction]
-            fn [<$name _parallel>] (left: Vec<&str>, right: Vec<&str>) -> PyResult<$type> {
+            #[pyo3(signature = (left, right, processor=None))]
+            fn [<$name _parallel>] (left: Vec<Text>, right: Vec<Text>, processor: Option<process::Preprocessor>) -> PyResult<$type> {
+                let (left, right) = process::apply_pair(&processor, &left, &right)?;
                 Ok(
                     (left, right)
                     .into_par_iter()
-                    .map(|(x, y)| strsim::$name(x, y))
+                    .map(|(x, y)| distance::$name(&x, &y))
                     .collect()
                 )
             }
@@ -49,16 +93,40 @@ macro_rules! parallel_wrapper {
 }
 
 macro_rules! max_similarity_wrapper {
+    ($(#[$doc:meta])* unit $name:ident -> $type:ty) => {
+        paste::item! {
+            $(#[$doc])*
+            #[pyfunction]
+            #[pyo3(signature = (targets, source_strings, processor=None, unit="char"))]
+            fn [<$name _max_similarity>] (targets: Vec<Text>, source_strings: Vec<Text>, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<Vec<$type>> {
+                let unit = distance::Unit::parse(unit)?;
+                let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+                let zero = <$type>::default(); // Get zero value for type
+                let max_distances: Vec<$type> = (targets.par_iter()
+                    .map(|target| {
+                        source_strings.par_iter()
+                            .map(|source_str| unit.$name(target, source_str))
+                            .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                            .unwrap_or(zero)
+                    })
+                    .collect()
+                );
+                Ok(max_distances)
+            }
+        }
+    };
     ($(#[$doc:meta])* $name:ident -> $type:ty) => {
         paste::item! {
             $(#[$doc])*
             #[pyfunction]
-            fn [<$name _max_similarity>] (targets: Vec<&str>, source_strings: Vec<&str>) -> PyResult<Vec<$type>> {
+            #[pyo3(signature = (targets, source_strings, processor=None))]
+            fn [<$name _max_similarity>] (targets: Vec<Text>, source_strings: Vec<Text>, processor: Option<process::Preprocessor>) -> PyResult<Vec<$type>> {
+                let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
                 let zero = <$type>::default(); // Get zero value for type
                 let max_distances: Vec<$type> = (targets.par_iter()
                     .map(|target| {
                         source_strings.par_iter()
-                            .map(|source_str| strsim::$name(target, source_str))
+                            .map(|source_str| distance::$name(target, source_str))
                             .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
                             .unwrap_or(zero)
                     })
@@ -71,16 +139,40 @@ macro_rules! max_similarity_wrapper {
 }
 
 macro_rules! min_similarity_wrapper {
+    ($(#[$doc:meta])* unit $name:ident -> $type:ty) => {
+        paste::item! {
+            $(#[$doc])*
+            #[pyfunction]
+            #[pyo3(signature = (targets, source_strings, processor=None, unit="char"))]
+            fn [<$name _min_similarity>] (targets: Vec<Text>, source_strings: Vec<Text>, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<Vec<$type>>{
+                let unit = distance::Unit::parse(unit)?;
+                let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+                let zero = <$type>::default(); // Get zero value for type
+                let min_distances: Vec<$type> = (targets.par_iter()
+                    .map(|target| {
+                        source_strings.par_iter()
+                            .map(|source_str| unit.$name(target, source_str))
+                            .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                            .unwrap_or(zero)
+                    })
+                    .collect()
+                );
+                Ok(min_distances)
+            }
+        }
+    };
     ($(#[$doc:meta])* $name:ident -> $type:ty) => {
         paste::item! {
             $(#[$doc])*
             #[pyfunction]
-            fn [<$name _min_similarity>] (targets: Vec<&str>, source_strings: Vec<&str>) -> PyResult<Vec<$type>>{
+            #[pyo3(signature = (targets, source_strings, processor=None))]
+            fn [<$name _min_similarity>] (targets: Vec<Text>, source_strings: Vec<Text>, processor: Option<process::Preprocessor>) -> PyResult<Vec<$type>>{
+                let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
                 let zero = <$type>::default(); // Get zero value for type
                 let min_distances: Vec<$type> = (targets.par_iter()
                     .map(|target| {
                         source_strings.par_iter()
-                            .map(|source_str| strsim::$name(target, source_str))
+                            .map(|source_str| distance::$name(target, source_str))
                             .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
                             .unwrap_or(zero)
                     })
@@ -93,6 +185,140 @@ macro_rules! min_similarity_wrapper {
 }
 
 
+macro_rules! sift4_wrapper {
+    (
+        $(#[$doc:meta])* $name:ident => $variant:ident;
+        $(#[$parallel_doc:meta])*;
+        $(#[$max_doc:meta])*;
+        $(#[$min_doc:meta])*
+    ) => {
+        paste! {
+            $(#[$doc])*
+            #[pyfunction]
+            #[pyo3(signature = (a, b, max_offset=5, max_distance=None, processor=None, unit="char"))]
+            fn $name(a: Text, b: Text, max_offset: usize, max_distance: Option<usize>, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<usize> {
+                Ok(distance::Sift4::new(max_offset, max_distance, distance::Unit::parse(unit)?).$variant(&process::apply(&processor, &a)?, &process::apply(&processor, &b)?))
+            }
+
+            $(#[$parallel_doc])*
+            #[pyfunction]
+            #[pyo3(signature = (left, right, max_offset=5, max_distance=None, processor=None, unit="char"))]
+            fn [<$name _parallel>](left: Vec<Text>, right: Vec<Text>, max_offset: usize, max_distance: Option<usize>, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<Vec<usize>> {
+                let metric = distance::Sift4::new(max_offset, max_distance, distance::Unit::parse(unit)?);
+                let (left, right) = process::apply_pair(&processor, &left, &right)?;
+                Ok(
+                    (left, right)
+                    .into_par_iter()
+                    .map(|(x, y)| metric.$variant(&x, &y))
+                    .collect()
+                )
+            }
+
+            $(#[$max_doc])*
+            #[pyfunction]
+            #[pyo3(signature = (targets, source_strings, max_offset=5, max_distance=None, processor=None, unit="char"))]
+            fn [<$name _max_similarity>](targets: Vec<Text>, source_strings: Vec<Text>, max_offset: usize, max_distance: Option<usize>, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<Vec<usize>> {
+                let metric = distance::Sift4::new(max_offset, max_distance, distance::Unit::parse(unit)?);
+                let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+                Ok(targets.par_iter()
+                    .map(|target| {
+                        source_strings.par_iter()
+                            .map(|source_str| metric.$variant(target, source_str))
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect()
+                )
+            }
+
+            $(#[$min_doc])*
+            #[pyfunction]
+            #[pyo3(signature = (targets, source_strings, max_offset=5, max_distance=None, processor=None, unit="char"))]
+            fn [<$name _min_similarity>](targets: Vec<Text>, source_strings: Vec<Text>, max_offset: usize, max_distance: Option<usize>, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<Vec<usize>> {
+                let metric = distance::Sift4::new(max_offset, max_distance, distance::Unit::parse(unit)?);
+                let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+                Ok(targets.par_iter()
+                    .map(|target| {
+                        source_strings.par_iter()
+                            .map(|source_str| metric.$variant(target, source_str))
+                            .min()
+                            .unwrap_or(0)
+                    })
+                    .collect()
+                )
+            }
+        }
+    };
+}
+
+
+macro_rules! qgram_wrapper {
+    (
+        $(#[$doc:meta])* $name:ident => $measure:expr;
+        $(#[$parallel_doc:meta])*;
+        $(#[$max_doc:meta])*;
+        $(#[$min_doc:meta])*
+    ) => {
+        paste! {
+            $(#[$doc])*
+            #[pyfunction]
+            #[pyo3(signature = (a, b, q=2, pad=false, multiset=true, processor=None, unit="char"))]
+            fn $name(a: Text, b: Text, q: usize, pad: bool, multiset: bool, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<f64> {
+                Ok(distance::QGram::new($measure, q, pad, multiset, distance::Unit::parse(unit)?)?.similarity(&process::apply(&processor, &a)?, &process::apply(&processor, &b)?))
+            }
+
+            $(#[$parallel_doc])*
+            #[pyfunction]
+            #[pyo3(signature = (left, right, q=2, pad=false, multiset=true, processor=None, unit="char"))]
+            fn [<$name _parallel>](left: Vec<Text>, right: Vec<Text>, q: usize, pad: bool, multiset: bool, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<Vec<f64>> {
+                let metric = distance::QGram::new($measure, q, pad, multiset, distance::Unit::parse(unit)?)?;
+                let (left, right) = process::apply_pair(&processor, &left, &right)?;
+                Ok(
+                    (left, right)
+                    .into_par_iter()
+                    .map(|(x, y)| metric.similarity(&x, &y))
+                    .collect()
+                )
+            }
+
+            $(#[$max_doc])*
+            #[pyfunction]
+            #[pyo3(signature = (targets, source_strings, q=2, pad=false, multiset=true, processor=None, unit="char"))]
+            fn [<$name _max_similarity>](targets: Vec<Text>, source_strings: Vec<Text>, q: usize, pad: bool, multiset: bool, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<Vec<f64>> {
+                let metric = distance::QGram::new($measure, q, pad, multiset, distance::Unit::parse(unit)?)?;
+                let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+                Ok(targets.par_iter()
+                    .map(|target| {
+                        source_strings.par_iter()
+                            .map(|source_str| metric.similarity(target, source_str))
+                            .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                            .unwrap_or(0.0)
+                    })
+                    .collect()
+                )
+            }
+
+            $(#[$min_doc])*
+            #[pyfunction]
+            #[pyo3(signature = (targets, source_strings, q=2, pad=false, multiset=true, processor=None, unit="char"))]
+            fn [<$name _min_similarity>](targets: Vec<Text>, source_strings: Vec<Text>, q: usize, pad: bool, multiset: bool, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<Vec<f64>> {
+                let metric = distance::QGram::new($measure, q, pad, multiset, distance::Unit::parse(unit)?)?;
+                let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+                Ok(targets.par_iter()
+                    .map(|target| {
+                        source_strings.par_iter()
+                            .map(|source_str| metric.similarity(target, source_str))
+                            .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                            .unwrap_or(0.0)
+                    })
+                    .collect()
+                )
+            }
+        }
+    };
+}
+
+
 #[pyfunction]
 fn eddie_levenshtein_distance (left: &str, right: &str) -> PyResult<usize> {
     let lev: Levenshtein = Levenshtein::new();
@@ -113,452 +339,1475 @@ fn eddie_levenshtein_distance_parallel (left: Vec<&str>, right: Vec<&str>) -> Py
 
 
 wrapper! {
-    /// hamming(a, b)
+    /// hamming(a, b, processor=None, unit="char")
     ///
     /// Calculates the number of positions in the two strings where the characters
     /// differ. Returns an error if the strings have different lengths.
     ///
-    /// :param str a: base string
-    /// :param str b: string to compare
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: int
     /// :raises ValueError: if a and b have a different lengths
-    hamming -> usize
+    unit hamming -> usize
 }
 
 wrapper! {
-    /// levenshtein(a, b)
+    /// levenshtein(a, b, processor=None, unit="char")
     ///
     /// Calculates the minimum number of insertions, deletions, and substitutions
     /// required to change one string into the other.
     ///
-    /// :param str a: base string
-    /// :param str b: string to compare
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: int
-    levenshtein -> usize
+    unit levenshtein -> usize
 }
 
 wrapper! {
-    /// osa_distance(a, b)
+    /// osa_distance(a, b, processor=None, unit="char")
     ///
     /// Like Levenshtein but allows for adjacent transpositions. Each substring can
     /// only be edited once.
     ///
-    /// :param str a: base string
-    /// :param str b: string to compare
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: int
-    osa_distance -> usize
+    unit osa_distance -> usize
 }
 
 wrapper! {
-    /// damerau_levenshtein(a, b)
+    /// damerau_levenshtein(a, b, processor=None, unit="char")
     ///
     /// Like optimal string alignment, but substrings can be edited an unlimited
     /// number of times, and the triangle inequality holds.
     ///
-    /// :param str a: base string
-    /// :param str b: string to compare
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: int
-    damerau_levenshtein -> usize
+    unit damerau_levenshtein -> usize
 }
 
 wrapper! {
-    /// normalized_levenshtein(a, b)
+    /// normalized_levenshtein(a, b, processor=None, unit="char")
     ///
     /// Calculates a normalized score of the Levenshtein algorithm between 0.0 and
     /// 1.0 (inclusive), where 1.0 means the strings are the same.
     ///
-    /// :param str a: base string
-    /// :param str b: string to compare
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: float
-    normalized_levenshtein -> f64
+    unit normalized_levenshtein -> f64
 }
 
 wrapper! {
-    /// normalized_damerau_levenshtein(a, b)
+    /// normalized_damerau_levenshtein(a, b, processor=None, unit="char")
     ///
     /// Calculates a normalized score of the Damerau–Levenshtein algorithm between
     /// 0.0 and 1.0 (inclusive), where 1.0 means the strings are the same.
     ///
-    /// :param str a: base string
-    /// :param str b: string to compare
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: float
-    normalized_damerau_levenshtein -> f64
+    unit normalized_damerau_levenshtein -> f64
 }
 
 wrapper! {
-    /// jaro(a, b)
+    /// editex(a, b, processor=None)
     ///
-    /// Calculates the Jaro similarity between two strings. The returned value
-    /// is between 0.0 and 1.0 (higher value means more similar).
+    /// Like Levenshtein, but substituting letters that sound alike (such as "c"
+    /// and "k") costs 1 instead of 2, and so does dropping a silent "h" or "w".
+    /// Works better than Levenshtein on misspelled names. Case is ignored.
     ///
-    /// :param str a: base string
-    /// :param str b: string to compare
-    /// :return: similarity
-    /// :rtype: float
-    jaro -> f64
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :return: distance
+    /// :rtype: int
+    editex -> usize
 }
 
 wrapper! {
-    /// jaro_winkler(a, b)
+    /// normalized_editex(a, b, processor=None)
     ///
-    /// Like Jaro but gives a boost to strings that have a common prefix.
+    /// Calculates a normalized score of the Editex algorithm between 0.0 and 1.0
+    /// (inclusive), where 1.0 means the strings are the same.
     ///
-    /// :param str a: base string
-    /// :param str b: string to compare
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
     /// :return: similarity
     /// :rtype: float
-    jaro_winkler -> f64
+    normalized_editex -> f64
+}
+
+wrapper! {
+    /// bag_distance(a, b, processor=None, unit="char")
+    ///
+    /// Calculates the larger of the number of characters of one string missing
+    /// from the other, in linear time. It is a lower bound of the Levenshtein
+    /// distance, handy to discard pairs before computing it.
+    ///
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: distance
+    /// :rtype: int
+    unit bag_distance -> usize
 }
 
 wrapper! {
-    /// sorensen_dice(a, b)
+    /// jaro(a, b, processor=None, unit="char")
     ///
-    /// Calculates a Sørensen-Dice similarity distance using bigrams. See 
-    /// http://en.wikipedia.org/wiki/S%C3%B8rensen%E2%80%93Dice_coefficient.
+    /// Calculates the Jaro similarity between two strings. The returned value
+    /// is between 0.0 and 1.0 (higher value means more similar).
     ///
-    /// :param str a: base string
-    /// :param str b: string to compare
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: similarity
     /// :rtype: float
-    sorensen_dice -> f64
+    unit jaro -> f64
+}
+
+/// jaro_winkler(a, b, prefix_weight=0.1, max_prefix=4, boost_threshold=0.7, processor=None, unit="char")
+///
+/// Like Jaro but gives a boost to strings that have a common prefix. Each
+/// common prefix character, up to ``max_prefix``, closes ``prefix_weight`` of
+/// the remaining gap to 1.0, for pairs whose Jaro similarity is above
+/// ``boost_threshold``. The defaults are Winkler's; pass ``max_prefix=None,
+/// boost_threshold=0.0`` for strsim's version, used before these parameters
+/// existed.
+///
+/// :param str|bytes a: base string
+/// :param str|bytes b: string to compare
+/// :param float prefix_weight: boost per common prefix character
+/// :param int max_prefix: longest prefix boosted, None for no limit
+/// :param float boost_threshold: Jaro similarity above which pairs are boosted
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: similarity
+/// :rtype: float
+/// :raises ValueError: if prefix_weight or boost_threshold is not between 0 and 1
+#[pyfunction]
+#[pyo3(signature = (a, b, prefix_weight=0.1, max_prefix=4, boost_threshold=0.7, processor=None, unit="char"))]
+fn jaro_winkler(a: Text, b: Text, prefix_weight: f64, max_prefix: Option<usize>, boost_threshold: f64, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<f64> {
+    Ok(distance::JaroWinkler::new(prefix_weight, max_prefix, boost_threshold, distance::Unit::parse(unit)?)?.similarity(&process::apply(&processor, &a)?, &process::apply(&processor, &b)?))
 }
 
+
 parallel_wrapper! {
-    /// levenshtein(a, b)
+    /// levenshtein(a, b, processor=None, unit="char")
     ///
     /// Calculates the minimum number of insertions, deletions, and substitutions
     /// required to change one string into the other. Operates in parallel over two lists of strings.
     ///
-    /// :param Vec<&str> a: base strings
-    /// :param Vec<&str> b: strings to compare
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: int
-    levenshtein -> Vec<usize>
+    unit levenshtein -> Vec<usize>
 }
 
 parallel_wrapper! {
-    /// osa_distance(a, b)
+    /// osa_distance(a, b, processor=None, unit="char")
     ///
     /// Like Levenshtein but allows for adjacent transpositions. Each substring can
     /// only be edited once.
     ///
-    /// :param Vec<&str> a: base strings
-    /// :param Vec<&str> b: strings to compare
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: int
-    osa_distance -> Vec<usize>
+    unit osa_distance -> Vec<usize>
 }
 
 parallel_wrapper! {
-    /// damerau_levenshtein(a, b)
+    /// damerau_levenshtein(a, b, processor=None, unit="char")
     ///
     /// Like optimal string alignment, but substrings can be edited an unlimited
     /// number of times, and the triangle inequality holds.
     ///
-    /// :param Vec<&str> a: base strings
-    /// :param Vec<&str> b: strings to compare
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: int
-    damerau_levenshtein -> Vec<usize>
+    unit damerau_levenshtein -> Vec<usize>
 }
 
 parallel_wrapper! {
-    /// normalized_levenshtein(a, b)
+    /// normalized_levenshtein(a, b, processor=None, unit="char")
     ///
     /// Calculates a normalized score of the Levenshtein algorithm between 0.0 and
     /// 1.0 (inclusive), where 1.0 means the strings are the same.
     ///
-    /// :param Vec<&str> a: base strings
-    /// :param Vec<&str> b: strings to compare
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: float
-    normalized_levenshtein -> Vec<f64>
+    unit normalized_levenshtein -> Vec<f64>
 }
 
 parallel_wrapper! {
-    /// normalized_damerau_levenshtein(a, b)
+    /// normalized_damerau_levenshtein(a, b, processor=None, unit="char")
     ///
     /// Calculates a normalized score of the Damerau–Levenshtein algorithm between
     /// 0.0 and 1.0 (inclusive), where 1.0 means the strings are the same.
     ///
-    /// :param Vec<&str> a: base strings
-    /// :param Vec<&str> b: strings to compare
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: distance
     /// :rtype: float
-    normalized_damerau_levenshtein -> Vec<f64>
+    unit normalized_damerau_levenshtein -> Vec<f64>
 }
 
 parallel_wrapper! {
-    /// jaro(a, b)
+    /// editex(a, b, processor=None)
     ///
-    /// Calculates the Jaro similarity between two strings. The returned value
-    /// is between 0.0 and 1.0 (higher value means more similar).
+    /// Like Levenshtein, but substituting letters that sound alike costs 1
+    /// instead of 2. Operates in parallel over two lists of strings.
     ///
-    /// :param Vec<&str> a: base strings
-    /// :param Vec<&str> b: strings to compare
-    /// :return: similarity
-    /// :rtype: float
-    jaro -> Vec<f64>
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :return: distance
+    /// :rtype: int
+    editex -> Vec<usize>
 }
 
 parallel_wrapper! {
-    /// jaro_winkler(a, b)
+    /// normalized_editex(a, b, processor=None)
     ///
-    /// Like Jaro but gives a boost to strings that have a common prefix.
+    /// Calculates a normalized score of the Editex algorithm between 0.0 and 1.0
+    /// (inclusive), where 1.0 means the strings are the same.
     ///
-    /// :param Vec<&str> a: base strings
-    /// :param Vec<&str> b: strings to compare
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
     /// :return: similarity
     /// :rtype: float
-    jaro_winkler -> Vec<f64>
+    normalized_editex -> Vec<f64>
 }
 
 parallel_wrapper! {
-    /// sorensen_dice(a, b)
+    /// bag_distance(a, b, processor=None, unit="char")
+    ///
+    /// Calculates the bag distance, a linear-time lower bound of the Levenshtein
+    /// distance. Operates in parallel over two lists of strings.
     ///
-    /// Calculates a Sørensen-Dice similarity distance using bigrams. See 
-    /// http://en.wikipedia.org/wiki/S%C3%B8rensen%E2%80%93Dice_coefficient.
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: distance
+    /// :rtype: int
+    unit bag_distance -> Vec<usize>
+}
+
+parallel_wrapper! {
+    /// jaro(a, b, processor=None, unit="char")
     ///
-    /// :param Vec<&str> a: base strings
-    /// :param Vec<&str> b: strings to compare
+    /// Calculates the Jaro similarity between two strings. The returned value
+    /// is between 0.0 and 1.0 (higher value means more similar).
+    ///
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: similarity
     /// :rtype: float
-    sorensen_dice -> Vec<f64>
+    unit jaro -> Vec<f64>
 }
 
+/// jaro_winkler(a, b, prefix_weight=0.1, max_prefix=4, boost_threshold=0.7, processor=None, unit="char")
+///
+/// Like Jaro but gives a boost to strings that have a common prefix. Operates
+/// in parallel over two lists of strings.
+///
+/// :param Vec<str|bytes> a: base strings
+/// :param Vec<str|bytes> b: strings to compare
+/// :param float prefix_weight: boost per common prefix character
+/// :param int max_prefix: longest prefix boosted, None for no limit
+/// :param float boost_threshold: Jaro similarity above which pairs are boosted
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: similarity
+/// :rtype: float
+/// :raises ValueError: if prefix_weight or boost_threshold is not between 0 and 1
+#[pyfunction]
+#[pyo3(signature = (left, right, prefix_weight=0.1, max_prefix=4, boost_threshold=0.7, processor=None, unit="char"))]
+fn jaro_winkler_parallel(
+    left: Vec<Text>,
+    right: Vec<Text>,
+    prefix_weight: f64,
+    max_prefix: Option<usize>,
+    boost_threshold: f64,
+    processor: Option<process::Preprocessor>,
+    unit: &str,
+) -> PyResult<Vec<f64>> {
+    let metric = distance::JaroWinkler::new(prefix_weight, max_prefix, boost_threshold, distance::Unit::parse(unit)?)?;
+    let (left, right) = process::apply_pair(&processor, &left, &right)?;
+    Ok(
+        (left, right)
+        .into_par_iter()
+        .map(|(x, y)| metric.similarity(&x, &y))
+        .collect()
+    )
+}
+
+
 max_similarity_wrapper! {
-    /// levenshtein_max_similarity(targets, source_strings)
+    /// levenshtein_max_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the maximum Levenshtein distance between each target string
     /// and a list of "known bad strings". The Levenshtein distance measures the minimum
     /// number of insertions, deletions, and substitutions required to change one string
     /// into the other.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of maximum similarities
     /// :rtype: Vec<usize>
-    levenshtein -> usize
+    unit levenshtein -> usize
 }
 
 max_similarity_wrapper! {
-    /// osa_distance_max_similarity(targets, source_strings)
+    /// osa_distance_max_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the maximum optimal string alignment distance between each target
     /// string and a list of "known bad strings". This is similar to Levenshtein distance but
     /// allows for adjacent transpositions.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of maximum similarities
     /// :rtype: Vec<usize>
-    osa_distance -> usize
+    unit osa_distance -> usize
 }
 
 max_similarity_wrapper! {
-    /// damerau_levenshtein_max_similarity(targets, source_strings)
+    /// damerau_levenshtein_max_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the maximum Damerau-Levenshtein distance between each target
     /// string and a list of "known bad strings". This algorithm allows for transpositions and
     /// is more flexible than the simple Levenshtein distance.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of maximum similarities
     /// :rtype: Vec<usize>
-    damerau_levenshtein -> usize
+    unit damerau_levenshtein -> usize
 }
 
 max_similarity_wrapper! {
-    /// normalized_levenshtein_max_similarity(targets, source_strings)
+    /// normalized_levenshtein_max_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the maximum normalized Levenshtein similarity between each target
     /// string and a list of "known bad strings". The score is between 0.0 and 1.0, with
     /// 1.0 meaning the strings are identical.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of maximum similarities
     /// :rtype: Vec<f64>
-    normalized_levenshtein -> f64
+    unit normalized_levenshtein -> f64
 }
 
 max_similarity_wrapper! {
-    /// normalized_damerau_levenshtein_max_similarity(targets, source_strings)
+    /// normalized_damerau_levenshtein_max_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the maximum normalized Damerau-Levenshtein similarity between each target
     /// string and a list of "known bad strings". The score is between 0.0 and 1.0, with
     /// 1.0 meaning the strings are identical.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of maximum similarities
     /// :rtype: Vec<f64>
-    normalized_damerau_levenshtein -> f64
+    unit normalized_damerau_levenshtein -> f64
 }
 
 max_similarity_wrapper! {
-    /// jaro_max_similarity(targets, source_strings)
+    /// editex_max_similarity(targets, source_strings, processor=None)
     ///
-    /// Calculates the maximum Jaro similarity between each target string
-    /// and a list of "known bad strings". The Jaro similarity score is between 0.0 and 1.0,
-    /// where a higher score indicates more similarity.
+    /// Calculates the maximum Editex distance between each target string and a list
+    /// of "known bad strings". Editex is like Levenshtein but substituting letters
+    /// that sound alike costs less.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
     /// :return: vector of maximum similarities
-    /// :rtype: Vec<f64>
-    jaro -> f64
+    /// :rtype: Vec<usize>
+    editex -> usize
 }
 
 max_similarity_wrapper! {
-    /// jaro_winkler_max_similarity(targets, source_strings)
+    /// normalized_editex_max_similarity(targets, source_strings, processor=None)
     ///
-    /// Calculates the maximum Jaro-Winkler similarity between each target string
-    /// and a list of "known bad strings". This is similar to the Jaro similarity but gives
-    /// a boost to strings that have a common prefix.
+    /// Calculates the maximum normalized Editex similarity between each target
+    /// string and a list of "known bad strings". The score is between 0.0 and 1.0, with
+    /// 1.0 meaning the strings are identical.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
     /// :return: vector of maximum similarities
     /// :rtype: Vec<f64>
-    jaro_winkler -> f64
+    normalized_editex -> f64
+}
+
+max_similarity_wrapper! {
+    /// bag_distance_max_similarity(targets, source_strings, processor=None, unit="char")
+    ///
+    /// Calculates the maximum bag distance between each target string and a list
+    /// of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of maximum similarities
+    /// :rtype: Vec<usize>
+    unit bag_distance -> usize
 }
 
 max_similarity_wrapper! {
-    /// sorensen_dice_max_similarity(targets, source_strings)
+    /// jaro_max_similarity(targets, source_strings, processor=None, unit="char")
     ///
-    /// Calculates the maximum Sørensen-Dice similarity between each target string
-    /// and a list of "known bad strings". This uses bigrams to calculate the similarity
-    /// score, which is between 0.0 and 1.0, where a higher score indicates more similarity.
+    /// Calculates the maximum Jaro similarity between each target string
+    /// and a list of "known bad strings". The Jaro similarity score is between 0.0 and 1.0,
+    /// where a higher score indicates more similarity.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of maximum similarities
     /// :rtype: Vec<f64>
-    sorensen_dice -> f64
+    unit jaro -> f64
+}
+
+/// jaro_winkler_max_similarity(targets, source_strings, prefix_weight=0.1, max_prefix=4, boost_threshold=0.7, processor=None, unit="char")
+///
+/// Calculates the maximum Jaro-Winkler similarity between each target string
+/// and a list of "known bad strings". This is similar to the Jaro similarity but gives
+/// a boost to strings that have a common prefix.
+///
+/// :param Vec<str|bytes> targets: list of target strings
+/// :param Vec<str|bytes> source_strings: list of source strings
+/// :param float prefix_weight: boost per common prefix character
+/// :param int max_prefix: longest prefix boosted, None for no limit
+/// :param float boost_threshold: Jaro similarity above which pairs are boosted
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: vector of maximum similarities
+/// :rtype: Vec<f64>
+/// :raises ValueError: if prefix_weight or boost_threshold is not between 0 and 1
+#[pyfunction]
+#[pyo3(signature = (targets, source_strings, prefix_weight=0.1, max_prefix=4, boost_threshold=0.7, processor=None, unit="char"))]
+fn jaro_winkler_max_similarity(
+    targets: Vec<Text>,
+    source_strings: Vec<Text>,
+    prefix_weight: f64,
+    max_prefix: Option<usize>,
+    boost_threshold: f64,
+    processor: Option<process::Preprocessor>,
+    unit: &str,
+) -> PyResult<Vec<f64>> {
+    let metric = distance::JaroWinkler::new(prefix_weight, max_prefix, boost_threshold, distance::Unit::parse(unit)?)?;
+    let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+    Ok(targets.par_iter()
+        .map(|target| {
+            source_strings.par_iter()
+                .map(|source_str| metric.similarity(target, source_str))
+                .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .unwrap_or(0.0)
+        })
+        .collect()
+    )
 }
 
+
 // min similarity
 
 min_similarity_wrapper! {
-    /// levenshtein_min_similarity(targets, source_strings)
+    /// levenshtein_min_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the minimum Levenshtein distance between each target string
     /// and a list of "known bad strings". The Levenshtein distance measures the minimum
     /// number of insertions, deletions, and substitutions required to change one string
     /// into the other.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of minimum similarities
     /// :rtype: Vec<usize>
-    levenshtein -> usize
+    unit levenshtein -> usize
 }
 
 
 min_similarity_wrapper! {
-    /// osa_distance_min_similarity(targets, source_strings)
+    /// osa_distance_min_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the minimum optimal string alignment distance between each target
     /// string and a list of "known bad strings". This is similar to Levenshtein distance but
     /// allows for adjacent transpositions.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of minimum similarities
     /// :rtype: Vec<usize>
-    osa_distance -> usize
+    unit osa_distance -> usize
 }
 
 min_similarity_wrapper! {
-    /// damerau_levenshtein_min_similarity(targets, source_strings)
+    /// damerau_levenshtein_min_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the minimum Damerau-Levenshtein distance between each target
     /// string and a list of "known bad strings". This algorithm allows for transpositions and
     /// is more flexible than the simple Levenshtein distance.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of minimum similarities
     /// :rtype: Vec<usize>
-    damerau_levenshtein -> usize
+    unit damerau_levenshtein -> usize
 }
 
 min_similarity_wrapper! {
-    /// normalized_levenshtein_min_similarity(targets, source_strings)
+    /// normalized_levenshtein_min_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the minimum normalized Levenshtein similarity between each target
     /// string and a list of "known bad strings". The score is between 0.0 and 1.0, with
     /// 1.0 meaning the strings are identical.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of minimum similarities
     /// :rtype: Vec<f64>
-    normalized_levenshtein -> f64
+    unit normalized_levenshtein -> f64
 }
 
 min_similarity_wrapper! {
-    /// normalized_damerau_levenshtein_min_similarity(targets, source_strings)
+    /// normalized_damerau_levenshtein_min_similarity(targets, source_strings, processor=None, unit="char")
     ///
     /// Calculates the minimum normalized Damerau-Levenshtein similarity between each target
     /// string and a list of "known bad strings". The score is between 0.0 and 1.0, with
     /// 1.0 meaning the strings are identical.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of minimum similarities
     /// :rtype: Vec<f64>
-    normalized_damerau_levenshtein -> f64
+    unit normalized_damerau_levenshtein -> f64
 }
 
 min_similarity_wrapper! {
-    /// jaro_min_similarity(targets, source_strings)
+    /// editex_min_similarity(targets, source_strings, processor=None)
     ///
-    /// Calculates the minimum Jaro similarity between each target string
-    /// and a list of known source strings. The Jaro similarity score is between 0.0 and 1.0,
-    /// where a higher score indicates more similarity.
+    /// Calculates the minimum Editex distance between each target string and a list
+    /// of "known bad strings". Editex is like Levenshtein but substituting letters
+    /// that sound alike costs less.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
     /// :return: vector of minimum similarities
-    /// :rtype: Vec<f64>
-    jaro -> f64
+    /// :rtype: Vec<usize>
+    editex -> usize
 }
 
 min_similarity_wrapper! {
-    /// jaro_winkler_min_similarity(targets, source_strings)
+    /// normalized_editex_min_similarity(targets, source_strings, processor=None)
     ///
-    /// Calculates the minimum Jaro-Winkler similarity between each target string
-    /// and a list of "known bad strings". This is similar to the Jaro similarity but gives
-    /// a boost to strings that have a common prefix.
+    /// Calculates the minimum normalized Editex similarity between each target
+    /// string and a list of "known bad strings". The score is between 0.0 and 1.0, with
+    /// 1.0 meaning the strings are identical.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
     /// :return: vector of minimum similarities
     /// :rtype: Vec<f64>
-    jaro_winkler -> f64
+    normalized_editex -> f64
+}
+
+min_similarity_wrapper! {
+    /// bag_distance_min_similarity(targets, source_strings, processor=None, unit="char")
+    ///
+    /// Calculates the minimum bag distance between each target string and a list
+    /// of "known bad strings". Since it is a lower bound of the Levenshtein
+    /// distance, no source string is closer than this by Levenshtein.
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of minimum similarities
+    /// :rtype: Vec<usize>
+    unit bag_distance -> usize
 }
 
 min_similarity_wrapper! {
-    /// sorensen_dice_min_similarity(targets, source_strings)
+    /// jaro_min_similarity(targets, source_strings, processor=None, unit="char")
     ///
-    /// Calculates the minimum Sørensen-Dice similarity between each target string
-    /// and a list of "known bad strings". This uses bigrams to calculate the similarity
-    /// score, which is between 0.0 and 1.0, where a higher score indicates more similarity.
+    /// Calculates the minimum Jaro similarity between each target string
+    /// and a list of known source strings. The Jaro similarity score is between 0.0 and 1.0,
+    /// where a higher score indicates more similarity.
     ///
-    /// :param Vec<str> targets: list of target strings
-    /// :param Vec<str> source_strings: list of source strings
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
     /// :return: vector of minimum similarities
     /// :rtype: Vec<f64>
-    sorensen_dice -> f64
+    unit jaro -> f64
+}
+
+/// jaro_winkler_min_similarity(targets, source_strings, prefix_weight=0.1, max_prefix=4, boost_threshold=0.7, processor=None, unit="char")
+///
+/// Calculates the minimum Jaro-Winkler similarity between each target string
+/// and a list of "known bad strings". This is similar to the Jaro similarity but gives
+/// a boost to strings that have a common prefix.
+///
+/// :param Vec<str|bytes> targets: list of target strings
+/// :param Vec<str|bytes> source_strings: list of source strings
+/// :param float prefix_weight: boost per common prefix character
+/// :param int max_prefix: longest prefix boosted, None for no limit
+/// :param float boost_threshold: Jaro similarity above which pairs are boosted
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: vector of minimum similarities
+/// :rtype: Vec<f64>
+/// :raises ValueError: if prefix_weight or boost_threshold is not between 0 and 1
+#[pyfunction]
+#[pyo3(signature = (targets, source_strings, prefix_weight=0.1, max_prefix=4, boost_threshold=0.7, processor=None, unit="char"))]
+fn jaro_winkler_min_similarity(
+    targets: Vec<Text>,
+    source_strings: Vec<Text>,
+    prefix_weight: f64,
+    max_prefix: Option<usize>,
+    boost_threshold: f64,
+    processor: Option<process::Preprocessor>,
+    unit: &str,
+) -> PyResult<Vec<f64>> {
+    let metric = distance::JaroWinkler::new(prefix_weight, max_prefix, boost_threshold, distance::Unit::parse(unit)?)?;
+    let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+    Ok(targets.par_iter()
+        .map(|target| {
+            source_strings.par_iter()
+                .map(|source_str| metric.similarity(target, source_str))
+                .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .unwrap_or(0.0)
+        })
+        .collect()
+    )
+}
+
+
+
+// q-gram similarities
+
+/// sorensen_dice(a, b, q=2, pad=False, multiset=True, count_grams=False, processor=None, unit="char")
+///
+/// Calculates the Sørensen-Dice similarity of the q-gram profiles of two
+/// strings, twice the shared q-grams over the total. Whitespace is ignored.
+/// The defaults give the bigram similarity of strsim, which sizes each
+/// multiset profile by the UTF-8 length of its string; ``count_grams`` sizes
+/// it by its q-gram count instead, so non-ASCII strings score as they would
+/// ASCII ones. See
+/// http://en.wikipedia.org/wiki/S%C3%B8rensen%E2%80%93Dice_coefficient.
+///
+/// :param str|bytes a: base string
+/// :param str|bytes b: string to compare
+/// :param int q: q-gram length
+/// :param bool pad: add ``q - 1`` start and end markers to the strings
+/// :param bool multiset: count repeated q-grams as often as they occur
+/// :param bool count_grams: divide by the q-gram counts rather than the UTF-8 lengths
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: similarity
+/// :rtype: float
+/// :raises ValueError: if q is 0
+#[pyfunction]
+#[pyo3(signature = (a, b, q=2, pad=false, multiset=true, count_grams=false, processor=None, unit="char"))]
+#[allow(clippy::too_many_arguments)]
+fn sorensen_dice(a: Text, b: Text, q: usize, pad: bool, multiset: bool, count_grams: bool, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<f64> {
+    let measure = distance::Measure::SorensenDice { count_grams };
+    Ok(distance::QGram::new(measure, q, pad, multiset, distance::Unit::parse(unit)?)?.similarity(&process::apply(&processor, &a)?, &process::apply(&processor, &b)?))
+}
+
+/// sorensen_dice_parallel(a, b, q=2, pad=False, multiset=True, count_grams=False, processor=None, unit="char")
+///
+/// Calculates the Sørensen-Dice similarity of each pair of strings. Operates in
+/// parallel over two lists of strings.
+///
+/// :param Vec<str|bytes> a: base strings
+/// :param Vec<str|bytes> b: strings to compare
+/// :param int q: q-gram length
+/// :param bool pad: add ``q - 1`` start and end markers to the strings
+/// :param bool multiset: count repeated q-grams as often as they occur
+/// :param bool count_grams: divide by the q-gram counts rather than the UTF-8 lengths
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: similarity
+/// :rtype: float
+/// :raises ValueError: if q is 0
+#[pyfunction]
+#[pyo3(signature = (left, right, q=2, pad=false, multiset=true, count_grams=false, processor=None, unit="char"))]
+#[allow(clippy::too_many_arguments)]
+fn sorensen_dice_parallel(
+    left: Vec<Text>,
+    right: Vec<Text>,
+    q: usize,
+    pad: bool,
+    multiset: bool,
+    count_grams: bool,
+    processor: Option<process::Preprocessor>,
+    unit: &str,
+) -> PyResult<Vec<f64>> {
+    let metric = distance::QGram::new(distance::Measure::SorensenDice { count_grams }, q, pad, multiset, distance::Unit::parse(unit)?)?;
+    let (left, right) = process::apply_pair(&processor, &left, &right)?;
+    Ok(
+        (left, right)
+        .into_par_iter()
+        .map(|(x, y)| metric.similarity(&x, &y))
+        .collect()
+    )
+}
+
+/// sorensen_dice_max_similarity(targets, source_strings, q=2, pad=False, multiset=True, count_grams=False, processor=None, unit="char")
+///
+/// Calculates the maximum Sørensen-Dice similarity between each target string
+/// and a list of "known bad strings".
+///
+/// :param Vec<str|bytes> targets: list of target strings
+/// :param Vec<str|bytes> source_strings: list of source strings
+/// :param int q: q-gram length
+/// :param bool pad: add ``q - 1`` start and end markers to the strings
+/// :param bool multiset: count repeated q-grams as often as they occur
+/// :param bool count_grams: divide by the q-gram counts rather than the UTF-8 lengths
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: vector of maximum similarities
+/// :rtype: Vec<f64>
+/// :raises ValueError: if q is 0
+#[pyfunction]
+#[pyo3(signature = (targets, source_strings, q=2, pad=false, multiset=true, count_grams=false, processor=None, unit="char"))]
+#[allow(clippy::too_many_arguments)]
+fn sorensen_dice_max_similarity(
+    targets: Vec<Text>,
+    source_strings: Vec<Text>,
+    q: usize,
+    pad: bool,
+    multiset: bool,
+    count_grams: bool,
+    processor: Option<process::Preprocessor>,
+    unit: &str,
+) -> PyResult<Vec<f64>> {
+    let metric = distance::QGram::new(distance::Measure::SorensenDice { count_grams }, q, pad, multiset, distance::Unit::parse(unit)?)?;
+    let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+    Ok(targets.par_iter()
+        .map(|target| {
+            source_strings.par_iter()
+                .map(|source_str| metric.similarity(target, source_str))
+                .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .unwrap_or(0.0)
+        })
+        .collect()
+    )
+}
+
+/// sorensen_dice_min_similarity(targets, source_strings, q=2, pad=False, multiset=True, count_grams=False, processor=None, unit="char")
+///
+/// Calculates the minimum Sørensen-Dice similarity between each target string
+/// and a list of "known bad strings".
+///
+/// :param Vec<str|bytes> targets: list of target strings
+/// :param Vec<str|bytes> source_strings: list of source strings
+/// :param int q: q-gram length
+/// :param bool pad: add ``q - 1`` start and end markers to the strings
+/// :param bool multiset: count repeated q-grams as often as they occur
+/// :param bool count_grams: divide by the q-gram counts rather than the UTF-8 lengths
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: vector of minimum similarities
+/// :rtype: Vec<f64>
+/// :raises ValueError: if q is 0
+#[pyfunction]
+#[pyo3(signature = (targets, source_strings, q=2, pad=false, multiset=true, count_grams=false, processor=None, unit="char"))]
+#[allow(clippy::too_many_arguments)]
+fn sorensen_dice_min_similarity(
+    targets: Vec<Text>,
+    source_strings: Vec<Text>,
+    q: usize,
+    pad: bool,
+    multiset: bool,
+    count_grams: bool,
+    processor: Option<process::Preprocessor>,
+    unit: &str,
+) -> PyResult<Vec<f64>> {
+    let metric = distance::QGram::new(distance::Measure::SorensenDice { count_grams }, q, pad, multiset, distance::Unit::parse(unit)?)?;
+    let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+    Ok(targets.par_iter()
+        .map(|target| {
+            source_strings.par_iter()
+                .map(|source_str| metric.similarity(target, source_str))
+                .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .unwrap_or(0.0)
+        })
+        .collect()
+    )
+}
+
+qgram_wrapper! {
+    /// jaccard(a, b, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the Jaccard similarity of the q-gram profiles of two strings,
+    /// the shared q-grams over all of them. Whitespace is ignored.
+    ///
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: similarity
+    /// :rtype: float
+    /// :raises ValueError: if q is 0
+    jaccard => distance::Measure::Jaccard;
+    /// jaccard_parallel(a, b, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the Jaccard similarity of each pair of strings. Operates in
+    /// parallel over two lists of strings.
+    ///
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: similarity
+    /// :rtype: float
+    /// :raises ValueError: if q is 0
+    ;
+    /// jaccard_max_similarity(targets, source_strings, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the maximum Jaccard similarity between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of maximum similarities
+    /// :rtype: Vec<f64>
+    /// :raises ValueError: if q is 0
+    ;
+    /// jaccard_min_similarity(targets, source_strings, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the minimum Jaccard similarity between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of minimum similarities
+    /// :rtype: Vec<f64>
+    /// :raises ValueError: if q is 0
+}
+
+qgram_wrapper! {
+    /// overlap_coefficient(a, b, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the overlap coefficient of the q-gram profiles of two
+    /// strings, the shared q-grams over those of the shorter string, so a
+    /// string scores 1.0 against any string containing it. Whitespace is
+    /// ignored.
+    ///
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: similarity
+    /// :rtype: float
+    /// :raises ValueError: if q is 0
+    overlap_coefficient => distance::Measure::Overlap;
+    /// overlap_coefficient_parallel(a, b, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the overlap similarity of each pair of strings. Operates in
+    /// parallel over two lists of strings.
+    ///
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: similarity
+    /// :rtype: float
+    /// :raises ValueError: if q is 0
+    ;
+    /// overlap_coefficient_max_similarity(targets, source_strings, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the maximum overlap similarity between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of maximum similarities
+    /// :rtype: Vec<f64>
+    /// :raises ValueError: if q is 0
+    ;
+    /// overlap_coefficient_min_similarity(targets, source_strings, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the minimum overlap similarity between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of minimum similarities
+    /// :rtype: Vec<f64>
+    /// :raises ValueError: if q is 0
+}
+
+qgram_wrapper! {
+    /// cosine(a, b, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the cosine similarity of the q-gram count vectors of two
+    /// strings. Whitespace is ignored.
+    ///
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: similarity
+    /// :rtype: float
+    /// :raises ValueError: if q is 0
+    cosine => distance::Measure::Cosine;
+    /// cosine_parallel(a, b, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the cosine similarity of each pair of strings. Operates in
+    /// parallel over two lists of strings.
+    ///
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: similarity
+    /// :rtype: float
+    /// :raises ValueError: if q is 0
+    ;
+    /// cosine_max_similarity(targets, source_strings, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the maximum cosine similarity between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of maximum similarities
+    /// :rtype: Vec<f64>
+    /// :raises ValueError: if q is 0
+    ;
+    /// cosine_min_similarity(targets, source_strings, q=2, pad=False, multiset=True, processor=None, unit="char")
+    ///
+    /// Calculates the minimum cosine similarity between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int q: q-gram length
+    /// :param bool pad: add ``q - 1`` start and end markers to the strings
+    /// :param bool multiset: count repeated q-grams as often as they occur
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of minimum similarities
+    /// :rtype: Vec<f64>
+    /// :raises ValueError: if q is 0
+}
+
+/// tversky(a, b, alpha=1.0, beta=1.0, q=2, pad=False, multiset=True, processor=None, unit="char")
+///
+/// Calculates the Tversky index of the q-gram profiles of two strings, the
+/// shared q-grams over themselves plus ``alpha`` times those only in ``a``
+/// and ``beta`` times those only in ``b``. ``alpha = beta = 1`` is Jaccard
+/// and ``alpha = beta = 0.5`` Sørensen-Dice with ``count_grams``; unequal
+/// weights make it asymmetric. Whitespace is ignored.
+///
+/// :param str|bytes a: base string
+/// :param str|bytes b: string to compare
+/// :param float alpha: weight of the q-grams only in a
+/// :param float beta: weight of the q-grams only in b
+/// :param int q: q-gram length
+/// :param bool pad: add ``q - 1`` start and end markers to the strings
+/// :param bool multiset: count repeated q-grams as often as they occur
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: similarity
+/// :rtype: float
+/// :raises ValueError: if q is 0 or alpha or beta is negative
+#[pyfunction]
+#[pyo3(signature = (a, b, alpha=1.0, beta=1.0, q=2, pad=false, multiset=true, processor=None, unit="char"))]
+#[allow(clippy::too_many_arguments)]
+fn tversky(a: Text, b: Text, alpha: f64, beta: f64, q: usize, pad: bool, multiset: bool, processor: Option<process::Preprocessor>, unit: &str) -> PyResult<f64> {
+    let measure = distance::Measure::Tversky { alpha, beta };
+    Ok(distance::QGram::new(measure, q, pad, multiset, distance::Unit::parse(unit)?)?.similarity(&process::apply(&processor, &a)?, &process::apply(&processor, &b)?))
+}
+
+/// tversky_parallel(a, b, alpha=1.0, beta=1.0, q=2, pad=False, multiset=True, processor=None, unit="char")
+///
+/// Calculates the Tversky index of each pair of strings. Operates in parallel
+/// over two lists of strings.
+///
+/// :param Vec<str|bytes> a: base strings
+/// :param Vec<str|bytes> b: strings to compare
+/// :param float alpha: weight of the q-grams only in a
+/// :param float beta: weight of the q-grams only in b
+/// :param int q: q-gram length
+/// :param bool pad: add ``q - 1`` start and end markers to the strings
+/// :param bool multiset: count repeated q-grams as often as they occur
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: similarity
+/// :rtype: float
+/// :raises ValueError: if q is 0 or alpha or beta is negative
+#[pyfunction]
+#[pyo3(signature = (left, right, alpha=1.0, beta=1.0, q=2, pad=false, multiset=true, processor=None, unit="char"))]
+#[allow(clippy::too_many_arguments)]
+fn tversky_parallel(
+    left: Vec<Text>,
+    right: Vec<Text>,
+    alpha: f64,
+    beta: f64,
+    q: usize,
+    pad: bool,
+    multiset: bool,
+    processor: Option<process::Preprocessor>,
+    unit: &str,
+) -> PyResult<Vec<f64>> {
+    let metric = distance::QGram::new(distance::Measure::Tversky { alpha, beta }, q, pad, multiset, distance::Unit::parse(unit)?)?;
+    let (left, right) = process::apply_pair(&processor, &left, &right)?;
+    Ok(
+        (left, right)
+        .into_par_iter()
+        .map(|(x, y)| metric.similarity(&x, &y))
+        .collect()
+    )
+}
+
+/// tversky_max_similarity(targets, source_strings, alpha=1.0, beta=1.0, q=2, pad=False, multiset=True, processor=None, unit="char")
+///
+/// Calculates the maximum Tversky index between each target string and a list
+/// of "known bad strings", with the targets as ``a``.
+///
+/// :param Vec<str|bytes> targets: list of target strings
+/// :param Vec<str|bytes> source_strings: list of source strings
+/// :param float alpha: weight of the q-grams only in a
+/// :param float beta: weight of the q-grams only in b
+/// :param int q: q-gram length
+/// :param bool pad: add ``q - 1`` start and end markers to the strings
+/// :param bool multiset: count repeated q-grams as often as they occur
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: vector of maximum similarities
+/// :rtype: Vec<f64>
+/// :raises ValueError: if q is 0 or alpha or beta is negative
+#[pyfunction]
+#[pyo3(signature = (targets, source_strings, alpha=1.0, beta=1.0, q=2, pad=false, multiset=true, processor=None, unit="char"))]
+#[allow(clippy::too_many_arguments)]
+fn tversky_max_similarity(
+    targets: Vec<Text>,
+    source_strings: Vec<Text>,
+    alpha: f64,
+    beta: f64,
+    q: usize,
+    pad: bool,
+    multiset: bool,
+    processor: Option<process::Preprocessor>,
+    unit: &str,
+) -> PyResult<Vec<f64>> {
+    let metric = distance::QGram::new(distance::Measure::Tversky { alpha, beta }, q, pad, multiset, distance::Unit::parse(unit)?)?;
+    let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+    Ok(targets.par_iter()
+        .map(|target| {
+            source_strings.par_iter()
+                .map(|source_str| metric.similarity(target, source_str))
+                .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .unwrap_or(0.0)
+        })
+        .collect()
+    )
+}
+
+/// tversky_min_similarity(targets, source_strings, alpha=1.0, beta=1.0, q=2, pad=False, multiset=True, processor=None, unit="char")
+///
+/// Calculates the minimum Tversky index between each target string and a list
+/// of "known bad strings", with the targets as ``a``.
+///
+/// :param Vec<str|bytes> targets: list of target strings
+/// :param Vec<str|bytes> source_strings: list of source strings
+/// :param float alpha: weight of the q-grams only in a
+/// :param float beta: weight of the q-grams only in b
+/// :param int q: q-gram length
+/// :param bool pad: add ``q - 1`` start and end markers to the strings
+/// :param bool multiset: count repeated q-grams as often as they occur
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+/// :return: vector of minimum similarities
+/// :rtype: Vec<f64>
+/// :raises ValueError: if q is 0 or alpha or beta is negative
+#[pyfunction]
+#[pyo3(signature = (targets, source_strings, alpha=1.0, beta=1.0, q=2, pad=false, multiset=true, processor=None, unit="char"))]
+#[allow(clippy::too_many_arguments)]
+fn tversky_min_similarity(
+    targets: Vec<Text>,
+    source_strings: Vec<Text>,
+    alpha: f64,
+    beta: f64,
+    q: usize,
+    pad: bool,
+    multiset: bool,
+    processor: Option<process::Preprocessor>,
+    unit: &str,
+) -> PyResult<Vec<f64>> {
+    let metric = distance::QGram::new(distance::Measure::Tversky { alpha, beta }, q, pad, multiset, distance::Unit::parse(unit)?)?;
+    let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+    Ok(targets.par_iter()
+        .map(|target| {
+            source_strings.par_iter()
+                .map(|source_str| metric.similarity(target, source_str))
+                .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .unwrap_or(0.0)
+        })
+        .collect()
+    )
+}
+
+
+// linear-time approximate edit distances
+
+sift4_wrapper! {
+    /// sift4_simple(a, b, max_offset=5, max_distance=None, processor=None, unit="char")
+    ///
+    /// Approximates the Levenshtein distance in linear time: walks both strings,
+    /// looking ahead at most ``max_offset`` characters after a mismatch, and
+    /// returns the length of the longest string minus the characters matched.
+    /// Suited to long strings where ``levenshtein`` is too slow.
+    ///
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param int max_offset: characters looked ahead for a match after a mismatch
+    /// :param int max_distance: distance from which to stop early, None for no limit
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: distance, above max_distance when stopped early
+    /// :rtype: int
+    sift4_simple => simple;
+    /// sift4_simple_parallel(a, b, max_offset=5, max_distance=None, processor=None, unit="char")
+    ///
+    /// Calculates the simple Sift4 distance of each pair of strings. Operates in
+    /// parallel over two lists of strings.
+    ///
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param int max_offset: characters looked ahead for a match after a mismatch
+    /// :param int max_distance: distance from which to stop early, None for no limit
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: distance
+    /// :rtype: int
+    ;
+    /// sift4_simple_max_similarity(targets, source_strings, max_offset=5, max_distance=None, processor=None, unit="char")
+    ///
+    /// Calculates the maximum simple Sift4 distance between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int max_offset: characters looked ahead for a match after a mismatch
+    /// :param int max_distance: distance from which to stop early, None for no limit
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of maximum similarities
+    /// :rtype: Vec<usize>
+    ;
+    /// sift4_simple_min_similarity(targets, source_strings, max_offset=5, max_distance=None, processor=None, unit="char")
+    ///
+    /// Calculates the minimum simple Sift4 distance between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int max_offset: characters looked ahead for a match after a mismatch
+    /// :param int max_distance: distance from which to stop early, None for no limit
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of minimum similarities
+    /// :rtype: Vec<usize>
+}
+
+sift4_wrapper! {
+    /// sift4_general(a, b, max_offset=5, max_distance=None, processor=None, unit="char")
+    ///
+    /// Like :py:func:`sift4_simple`, but also counts transpositions, so it
+    /// approximates the optimal string alignment distance more closely.
+    ///
+    /// :param str|bytes a: base string
+    /// :param str|bytes b: string to compare
+    /// :param int max_offset: characters looked ahead for a match after a mismatch
+    /// :param int max_distance: distance from which to stop early, None for no limit
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: distance, above max_distance when stopped early
+    /// :rtype: int
+    sift4_general => general;
+    /// sift4_general_parallel(a, b, max_offset=5, max_distance=None, processor=None, unit="char")
+    ///
+    /// Calculates the Sift4 distance of each pair of strings. Operates in
+    /// parallel over two lists of strings.
+    ///
+    /// :param Vec<str|bytes> a: base strings
+    /// :param Vec<str|bytes> b: strings to compare
+    /// :param int max_offset: characters looked ahead for a match after a mismatch
+    /// :param int max_distance: distance from which to stop early, None for no limit
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: distance
+    /// :rtype: int
+    ;
+    /// sift4_general_max_similarity(targets, source_strings, max_offset=5, max_distance=None, processor=None, unit="char")
+    ///
+    /// Calculates the maximum Sift4 distance between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int max_offset: characters looked ahead for a match after a mismatch
+    /// :param int max_distance: distance from which to stop early, None for no limit
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of maximum similarities
+    /// :rtype: Vec<usize>
+    ;
+    /// sift4_general_min_similarity(targets, source_strings, max_offset=5, max_distance=None, processor=None, unit="char")
+    ///
+    /// Calculates the minimum Sift4 distance between each target string
+    /// and a list of "known bad strings".
+    ///
+    /// :param Vec<str|bytes> targets: list of target strings
+    /// :param Vec<str|bytes> source_strings: list of source strings
+    /// :param int max_offset: characters looked ahead for a match after a mismatch
+    /// :param int max_distance: distance from which to stop early, None for no limit
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :param str unit: unit compared, "char", "grapheme" (extended grapheme clusters) or "byte"
+    /// :return: vector of minimum similarities
+    /// :rtype: Vec<usize>
+}
+
+// compression distance
+
+/// ncd(a, b, compressor="zlib", processor=None)
+///
+/// Calculates the Normalized Compression Distance, how much smaller the two
+/// strings compress together than apart. Suited to long strings such as log
+/// lines or HTML, where edit distances are too slow. ``compressor`` is
+/// ``"zlib"``, ``"lzma"`` or ``"entropy"``, an order-2 PPM model that is
+/// more accurate than zlib on short strings.
+///
+/// :param str|bytes a: base string
+/// :param str|bytes b: string to compare
+/// :param str compressor: compressor name
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :return: distance, 0.0 for identical strings and about 1.0 for unrelated
+///     ones, slightly above with compressor overhead
+/// :rtype: float
+/// :raises ValueError: if the compressor is unknown
+#[pyfunction]
+#[pyo3(signature = (a, b, compressor="zlib", processor=None))]
+fn ncd(py: Python, a: Text, b: Text, compressor: &str, processor: Option<process::Preprocessor>) -> PyResult<f64> {
+    let compressor = distance::Compressor::parse(compressor)?;
+    let (a, b) = (process::apply(&processor, &a)?, process::apply(&processor, &b)?);
+    Ok(py.allow_threads(|| compressor.distance(&a, &b)))
+}
+
+/// ncd_parallel(a, b, compressor="zlib", processor=None)
+///
+/// Calculates the Normalized Compression Distance. Operates in parallel over
+/// two lists of strings.
+///
+/// :param Vec<str|bytes> a: base strings
+/// :param Vec<str|bytes> b: strings to compare
+/// :param str compressor: compressor name
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :return: distance
+/// :rtype: float
+/// :raises ValueError: if the compressor is unknown
+#[pyfunction]
+#[pyo3(signature = (left, right, compressor="zlib", processor=None))]
+fn ncd_parallel(py: Python, left: Vec<Text>, right: Vec<Text>, compressor: &str, processor: Option<process::Preprocessor>) -> PyResult<Vec<f64>> {
+    let compressor = distance::Compressor::parse(compressor)?;
+    let (left, right) = process::apply_pair(&processor, &left, &right)?;
+    Ok(py.allow_threads(|| {
+        (left, right)
+            .into_par_iter()
+            .map(|(x, y)| compressor.distance(&x, &y))
+            .collect()
+    }))
+}
+
+/// ncd_cdist(targets, source_strings, compressor="zlib", processor=None)
+///
+/// Calculates the Normalized Compression Distance between each target string
+/// and each source string, compressing every string alone only once.
+/// Operates in parallel.
+///
+/// :param Vec<str|bytes> targets: list of target strings
+/// :param Vec<str|bytes> source_strings: list of source strings
+/// :param str compressor: compressor name
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :return: one row of distances to the source strings per target
+/// :rtype: Vec<Vec<f64>>
+/// :raises ValueError: if the compressor is unknown
+#[pyfunction]
+#[pyo3(signature = (targets, source_strings, compressor="zlib", processor=None))]
+fn ncd_cdist(
+    py: Python,
+    targets: Vec<Text>,
+    source_strings: Vec<Text>,
+    compressor: &str,
+    processor: Option<process::Preprocessor>,
+) -> PyResult<Vec<Vec<f64>>> {
+    let compressor = distance::Compressor::parse(compressor)?;
+    let (targets, source_strings) = process::apply_pair(&processor, &targets, &source_strings)?;
+    Ok(py.allow_threads(|| {
+        let size = |s: &Text| compressor.size(s.as_bytes());
+        let target_sizes: Vec<f64> = targets.par_iter().map(size).collect();
+        let source_sizes: Vec<f64> = source_strings.par_iter().map(size).collect();
+        targets.par_iter()
+            .zip(target_sizes)
+            .map(|(target, target_size)| {
+                source_strings.par_iter()
+                    .zip(&source_sizes)
+                    .map(|(source_str, &source_size)| {
+                        compressor.distance_with_sizes(target.as_bytes(), target_size, source_str.as_bytes(), source_size)
+                    })
+                    .collect()
+            })
+            .collect()
+    }))
+}
+
+// score matrices
+
+/// cdist(queries, choices, scorer="normalized_levenshtein", processor=None)
+///
+/// Calculates the similarity of each query to each choice. Named scorers run
+/// in Rust in parallel; a Python scorer is called serially, after the strings
+/// are processed.
+///
+/// :param Vec<str|bytes> queries: list of query strings
+/// :param Vec<str|bytes> choices: list of choice strings
+/// :param scorer: similarity name accepted by :py:meth:`VPTree.build`, or a
+///     Python callable taking two strings and returning a float
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :return: one row of similarities to the choices per query
+/// :rtype: Vec<Vec<f64>>
+/// :raises ValueError: if the scorer name is unknown
+#[pyfunction]
+#[pyo3(signature = (queries, choices, scorer=metric::Scorer::Rust(metric::Similarity::NormalizedLevenshtein), processor=None))]
+fn cdist(
+    py: Python,
+    queries: Vec<Text>,
+    choices: Vec<Text>,
+    scorer: metric::Scorer,
+    processor: Option<process::Preprocessor>,
+) -> PyResult<Vec<Vec<f64>>> {
+    let (queries, choices) = process::apply_pair(&processor, &queries, &choices)?;
+    scorer.score_all(py, &queries, &choices)
 }
 
 
@@ -570,38 +1819,147 @@ fn xdistances(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(damerau_levenshtein))?;
     m.add_wrapped(wrap_pyfunction!(normalized_levenshtein))?;
     m.add_wrapped(wrap_pyfunction!(normalized_damerau_levenshtein))?;
+    m.add_wrapped(wrap_pyfunction!(editex))?;
+    m.add_wrapped(wrap_pyfunction!(normalized_editex))?;
     m.add_wrapped(wrap_pyfunction!(jaro))?;
     m.add_wrapped(wrap_pyfunction!(jaro_winkler))?;
-    m.add_wrapped(wrap_pyfunction!(sorensen_dice))?;
     // parallel
     m.add_wrapped(wrap_pyfunction!(levenshtein_parallel))?;
     m.add_wrapped(wrap_pyfunction!(osa_distance_parallel))?;
     m.add_wrapped(wrap_pyfunction!(damerau_levenshtein_parallel))?;
     m.add_wrapped(wrap_pyfunction!(normalized_levenshtein_parallel))?;
     m.add_wrapped(wrap_pyfunction!(normalized_damerau_levenshtein_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(editex_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(normalized_editex_parallel))?;
     m.add_wrapped(wrap_pyfunction!(jaro_parallel))?;
     m.add_wrapped(wrap_pyfunction!(jaro_winkler_parallel))?;
-    m.add_wrapped(wrap_pyfunction!(sorensen_dice_parallel))?;
     // max sim
     m.add_wrapped(wrap_pyfunction!(levenshtein_max_similarity))?;
     m.add_wrapped(wrap_pyfunction!(osa_distance_max_similarity))?;
     m.add_wrapped(wrap_pyfunction!(damerau_levenshtein_max_similarity))?;
     m.add_wrapped(wrap_pyfunction!(normalized_levenshtein_max_similarity))?;
     m.add_wrapped(wrap_pyfunction!(normalized_damerau_levenshtein_max_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(editex_max_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(normalized_editex_max_similarity))?;
     m.add_wrapped(wrap_pyfunction!(jaro_max_similarity))?;
     m.add_wrapped(wrap_pyfunction!(jaro_winkler_max_similarity))?;
-    m.add_wrapped(wrap_pyfunction!(sorensen_dice_max_similarity))?;
     // min sim
     m.add_wrapped(wrap_pyfunction!(levenshtein_min_similarity))?;
     m.add_wrapped(wrap_pyfunction!(osa_distance_min_similarity))?;
     m.add_wrapped(wrap_pyfunction!(damerau_levenshtein_min_similarity))?;
     m.add_wrapped(wrap_pyfunction!(normalized_levenshtein_min_similarity))?;
     m.add_wrapped(wrap_pyfunction!(normalized_damerau_levenshtein_min_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(editex_min_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(normalized_editex_min_similarity))?;
     m.add_wrapped(wrap_pyfunction!(jaro_min_similarity))?;
     m.add_wrapped(wrap_pyfunction!(jaro_winkler_min_similarity))?;
+    // q-gram similarities
+    m.add_wrapped(wrap_pyfunction!(sorensen_dice))?;
+    m.add_wrapped(wrap_pyfunction!(sorensen_dice_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(sorensen_dice_max_similarity))?;
     m.add_wrapped(wrap_pyfunction!(sorensen_dice_min_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(jaccard))?;
+    m.add_wrapped(wrap_pyfunction!(jaccard_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(jaccard_max_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(jaccard_min_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(overlap_coefficient))?;
+    m.add_wrapped(wrap_pyfunction!(overlap_coefficient_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(overlap_coefficient_max_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(overlap_coefficient_min_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(cosine))?;
+    m.add_wrapped(wrap_pyfunction!(cosine_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(cosine_max_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(cosine_min_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(tversky))?;
+    m.add_wrapped(wrap_pyfunction!(tversky_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(tversky_max_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(tversky_min_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(bag_distance))?;
+    m.add_wrapped(wrap_pyfunction!(bag_distance_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(bag_distance_max_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(bag_distance_min_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(sift4_simple))?;
+    m.add_wrapped(wrap_pyfunction!(sift4_simple_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(sift4_simple_max_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(sift4_simple_min_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(sift4_general))?;
+    m.add_wrapped(wrap_pyfunction!(sift4_general_parallel))?;
+    m.add_wrapped(wrap_pyfunction!(sift4_general_max_similarity))?;
+    m.add_wrapped(wrap_pyfunction!(sift4_general_min_similarity))?;
+    m.add_function(wrap_pyfunction!(ncd, m)?)?;
+    m.add_function(wrap_pyfunction!(ncd_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(ncd_cdist, m)?)?;
+    m.add_function(wrap_pyfunction!(cdist, m)?)?;
+    m.add_function(wrap_pyfunction!(extract::extract_one, m)?)?;
+    m.add_function(wrap_pyfunction!(extract::extract, m)?)?;
+    m.add_function(wrap_pyfunction!(cluster::dedupe, m)?)?;
+    m.add_function(wrap_pyfunction!(cluster::cluster, m)?)?;
+    m.add_class::<process::Processor>()?;
+    // sequences of hashables
+    m.add_function(wrap_pyfunction!(sequence::generic_levenshtein, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_levenshtein_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_levenshtein_max_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_levenshtein_min_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_normalized_levenshtein, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_normalized_levenshtein_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_normalized_levenshtein_max_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_normalized_levenshtein_min_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_osa_distance, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_osa_distance_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_osa_distance_max_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_osa_distance_min_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_damerau_levenshtein, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_damerau_levenshtein_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_damerau_levenshtein_max_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_damerau_levenshtein_min_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_normalized_damerau_levenshtein, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_normalized_damerau_levenshtein_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_normalized_damerau_levenshtein_max_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_normalized_damerau_levenshtein_min_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_jaro, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_jaro_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_jaro_max_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(sequence::generic_jaro_min_similarity, m)?)?;
     // eddie special-cased
     m.add_function(wrap_pyfunction!(eddie_levenshtein_distance, m)?)?;
     m.add_function(wrap_pyfunction!(eddie_levenshtein_distance_parallel, m)?)?;
+    // indexes
+    m.add_class::<index::qgram::QGramIndex>()?;
+    m.add_class::<index::symspell::SymSpell>()?;
+    m.add_class::<index::automaton::LevenshteinAutomaton>()?;
+    m.add_function(wrap_pyfunction!(index::automaton::build_fst_set, m)?)?;
+    m.add_class::<index::minhash::MinHashLSH>()?;
+    m.add_function(wrap_pyfunction!(index::minhash::minhash, m)?)?;
+    m.add_function(wrap_pyfunction!(index::minhash::minhash_parallel, m)?)?;
+    m.add_class::<index::vptree::VPTree>()?;
+    // phonetic
+    m.add_function(wrap_pyfunction!(phonetic::soundex::soundex, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::soundex::soundex_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::refined_soundex::refined_soundex, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::refined_soundex::refined_soundex_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::metaphone::metaphone, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::metaphone::metaphone_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::double_metaphone::double_metaphone, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::double_metaphone::double_metaphone_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::nysiis::nysiis, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::nysiis::nysiis_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::mra::mra, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::mra::mra_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::mra::mra_compare, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::mra::mra_compare_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::caverphone::caverphone, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::caverphone::caverphone_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::daitch_mokotoff::daitch_mokotoff, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::daitch_mokotoff::daitch_mokotoff_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::approx_beider_morse::approx_beider_morse, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::approx_beider_morse::approx_beider_morse_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::approx_beider_morse::approx_beider_morse_languages, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::phonetic_match, m)?)?;
+    m.add_function(wrap_pyfunction!(phonetic::phonetic_match_parallel, m)?)?;
+    // token
+    m.add_function(wrap_pyfunction!(token::monge_elkan::monge_elkan, m)?)?;
+    m.add_function(wrap_pyfunction!(token::monge_elkan::monge_elkan_parallel, m)?)?;
+    m.add_class::<token::soft_tfidf::SoftTfidf>()?;
+    m.add_class::<token::tfidf::TfidfMatcher>()?;
     Ok(())
 }
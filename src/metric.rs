@@ -0,0 +1,133 @@
+//! Metrics selectable by name from the classes built on top of the wrappers.
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::distance::{self, Measure, QGram, Text, Unit};
+
+/// Normalized similarities in `[0, 1]`, where 1 means the strings are equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Similarity {
+    NormalizedLevenshtein,
+    NormalizedDamerauLevenshtein,
+    Jaro,
+    JaroWinkler,
+    SorensenDice,
+    NormalizedEditex,
+    Jaccard,
+    OverlapCoefficient,
+    Cosine,
+}
+
+impl Similarity {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "normalized_levenshtein" => Ok(Similarity::NormalizedLevenshtein),
+            "normalized_damerau_levenshtein" => Ok(Similarity::NormalizedDamerauLevenshtein),
+            "jaro" => Ok(Similarity::Jaro),
+            "jaro_winkler" => Ok(Similarity::JaroWinkler),
+            "sorensen_dice" => Ok(Similarity::SorensenDice),
+            "normalized_editex" => Ok(Similarity::NormalizedEditex),
+            "jaccard" => Ok(Similarity::Jaccard),
+            "overlap_coefficient" => Ok(Similarity::OverlapCoefficient),
+            "cosine" => Ok(Similarity::Cosine),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unknown similarity metric: {}",
+                name
+            ))),
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Similarity::NormalizedLevenshtein => "normalized_levenshtein",
+            Similarity::NormalizedDamerauLevenshtein => "normalized_damerau_levenshtein",
+            Similarity::Jaro => "jaro",
+            Similarity::JaroWinkler => "jaro_winkler",
+            Similarity::SorensenDice => "sorensen_dice",
+            Similarity::NormalizedEditex => "normalized_editex",
+            Similarity::Jaccard => "jaccard",
+            Similarity::OverlapCoefficient => "overlap_coefficient",
+            Similarity::Cosine => "cosine",
+        }
+    }
+
+    pub(crate) fn similarity(&self, a: &str, b: &str) -> f64 {
+        self.text_similarity(&a.into(), &b.into())
+    }
+
+    pub(crate) fn text_similarity(&self, a: &Text, b: &Text) -> f64 {
+        match self {
+            Similarity::NormalizedLevenshtein => Unit::Char.normalized_levenshtein(a, b),
+            Similarity::NormalizedDamerauLevenshtein => Unit::Char.normalized_damerau_levenshtein(a, b),
+            Similarity::Jaro => Unit::Char.jaro(a, b),
+            Similarity::JaroWinkler => distance::jaro_winkler(a, b),
+            Similarity::SorensenDice => QGram::bigrams(Measure::SorensenDice { count_grams: false }).similarity(a, b),
+            Similarity::NormalizedEditex => distance::normalized_editex(a, b),
+            Similarity::Jaccard => QGram::bigrams(Measure::Jaccard).similarity(a, b),
+            Similarity::OverlapCoefficient => QGram::bigrams(Measure::Overlap).similarity(a, b),
+            Similarity::Cosine => QGram::bigrams(Measure::Cosine).similarity(a, b),
+        }
+    }
+
+    /// `1 - similarity`, the distance the metric trees work with.
+    pub(crate) fn distance(&self, a: &str, b: &str) -> f64 {
+        1.0 - self.similarity(a, b)
+    }
+}
+
+/// A ``scorer`` argument: the name of a `Similarity`, computed in Rust and
+/// in parallel, or a Python callable taking two strings and returning a
+/// float, called serially with the GIL held.
+#[derive(Clone, Copy)]
+pub(crate) enum Scorer<'py> {
+    Rust(Similarity),
+    Python(&'py PyAny),
+}
+
+impl<'py> FromPyObject<'py> for Scorer<'py> {
+    fn extract(ob: &'py PyAny) -> PyResult<Self> {
+        if let Ok(name) = ob.extract::<&str>() {
+            return Ok(Scorer::Rust(Similarity::parse(name)?));
+        }
+        if ob.is_callable() {
+            return Ok(Scorer::Python(ob));
+        }
+        Err(exceptions::PyTypeError::new_err(
+            "scorer must be a metric name or a callable",
+        ))
+    }
+}
+
+impl<'py> Scorer<'py> {
+    /// Score of every query against every choice, one row per query.
+    pub(crate) fn score_all(&self, py: Python, queries: &[Text], choices: &[Text]) -> PyResult<Vec<Vec<f64>>> {
+        match self {
+            Scorer::Rust(similarity) => Ok(py.allow_threads(|| {
+                queries
+                    .par_iter()
+                    .map(|query| {
+                        choices
+                            .par_iter()
+                            .map(|choice| similarity.text_similarity(query, choice))
+                            .collect()
+                    })
+                    .collect()
+            })),
+            Scorer::Python(callable) => {
+                let choices: Vec<PyObject> = choices.iter().map(|choice| choice.to_object(py)).collect();
+                queries
+                    .iter()
+                    .map(|query| {
+                        let query = query.to_object(py);
+                        choices
+                            .iter()
+                            .map(|choice| callable.call1((&query, choice))?.extract())
+                            .collect()
+                    })
+                    .collect()
+            }
+        }
+    }
+}
@@ -0,0 +1,619 @@
+//! Multi-language phonetic matching modelled on the approach of Beider and
+//! Morse.
+//!
+//! The languages a name may come from are guessed from its spelling, the name
+//! is transcribed with each language's rules into every plausible
+//! pronunciation, and the transcriptions are approximated so that close
+//! pronunciations get the same code. The rules are our own and cover the
+//! common orthography of English, German, Polish, Hungarian, Russian
+//! (Cyrillic and romanized) and Hebrew script; they are not the Beider-Morse
+//! Phonetic Matching tables, whose codes this encoder does not reproduce.
+
+use std::collections::BTreeSet;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Transcriptions kept per language; names with more ambiguous letters than
+/// this allows keep the first ones in sorted order.
+const MAX_TRANSCRIPTIONS: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Language {
+    /// No language could be singled out; only the generic rules apply.
+    Any,
+    English,
+    German,
+    Hebrew,
+    Hungarian,
+    Polish,
+    Russian,
+}
+
+/// Candidates for a name in Latin script.
+const LATIN: &[Language] = &[
+    Language::English,
+    Language::German,
+    Language::Hungarian,
+    Language::Polish,
+    Language::Russian,
+];
+
+/// Spelling cues as `(pattern, languages, accept)`: a name containing the
+/// pattern can only be one of the languages, or none of them if `accept` is
+/// false. Patterns are anchored with `^` and `$` as in the rules below.
+const CUES: &[(&str, &[Language], bool)] = &[
+    ("sch", &[Language::German], true),
+    ("tz", &[Language::German], true),
+    ("ß", &[Language::German], true),
+    ("ä", &[Language::German], true),
+    ("ö", &[Language::German, Language::Hungarian], true),
+    ("ü", &[Language::German, Language::Hungarian], true),
+    ("ck", &[Language::English, Language::German], true),
+    ("th", &[Language::English, Language::German], true),
+    ("ee", &[Language::English, Language::German], true),
+    ("gh", &[Language::English], true),
+    ("wh", &[Language::English], true),
+    ("sh", &[Language::English, Language::Russian], true),
+    ("sz", &[Language::Hungarian, Language::Polish], true),
+    ("cz", &[Language::Hungarian, Language::Polish], true),
+    ("cs", &[Language::Hungarian], true),
+    ("zs", &[Language::Hungarian], true),
+    ("gy", &[Language::Hungarian], true),
+    ("ő", &[Language::Hungarian], true),
+    ("ű", &[Language::Hungarian], true),
+    ("ł", &[Language::Polish], true),
+    ("ą", &[Language::Polish], true),
+    ("ę", &[Language::Polish], true),
+    ("ś", &[Language::Polish], true),
+    ("ź", &[Language::Polish], true),
+    ("ż", &[Language::Polish], true),
+    ("ń", &[Language::Polish], true),
+    ("ć", &[Language::Polish], true),
+    ("wicz", &[Language::Polish], true),
+    ("ska$", &[Language::Polish], true),
+    ("ski$", &[Language::Polish, Language::Russian], true),
+    ("sky$", &[Language::Russian], true),
+    ("kh", &[Language::Russian], true),
+    ("zh", &[Language::Russian], true),
+    ("ov$", &[Language::Russian], true),
+    ("ova$", &[Language::Russian], true),
+    ("ev$", &[Language::Russian], true),
+    ("iy$", &[Language::Russian], true),
+    ("vich$", &[Language::Russian], true),
+    ("w", &[Language::Russian], false),
+];
+
+/// Transcription rules as `(pattern, pronunciations)`, alternatives separated
+/// by `|`. `S` and `Z` stand for the "sh" and "zh" sounds and `x` for "kh".
+/// The longest matching pattern of a language wins, and letters it does not
+/// cover fall back to [`GENERIC`].
+const ENGLISH: &[(&str, &str)] = &[
+    ("sh", "S"),
+    ("ch", "tS|k"),
+    ("tch", "tS"),
+    ("th", "t"),
+    ("ph", "f"),
+    ("gh", "g"),
+    ("igh", "aj"),
+    ("ough", "o|of|u"),
+    ("^kn", "n"),
+    ("^wr", "r"),
+    ("mb$", "m"),
+    ("wh", "v"),
+    ("w", "v"),
+    ("ck", "k"),
+    ("ce", "se"),
+    ("ci", "si"),
+    ("cy", "si"),
+    ("c", "k"),
+    ("j", "dZ"),
+    ("ee", "i"),
+    ("ea", "i|e"),
+    ("oo", "u"),
+    ("ou", "au|u"),
+    ("ay", "ej"),
+    ("ey$", "i"),
+    ("y$", "i"),
+    ("y", "i|j"),
+];
+
+const GERMAN: &[(&str, &str)] = &[
+    ("sch", "S"),
+    ("tsch", "tS"),
+    ("^sp", "Sp"),
+    ("^st", "St"),
+    ("chs", "ks"),
+    ("ch", "x"),
+    ("ck", "k"),
+    ("tz", "ts"),
+    ("z", "ts"),
+    ("w", "v"),
+    ("v", "f|v"),
+    ("ei", "aj"),
+    ("ey", "aj"),
+    ("ai", "aj"),
+    ("ie", "i"),
+    ("eu", "oj"),
+    ("äu", "oj"),
+    ("ä", "e"),
+    ("ö", "e"),
+    ("ü", "i"),
+    ("ß", "s"),
+    ("dt", "t"),
+    ("th", "t"),
+    ("ph", "f"),
+    ("j", "j"),
+];
+
+const HEBREW: &[(&str, &str)] = &[
+    ("א", "a"),
+    ("ב", "b|v"),
+    ("ג", "g"),
+    ("ד", "d"),
+    ("ה", "h"),
+    ("וו", "v"),
+    ("ו", "v|u|o"),
+    ("ז", "z"),
+    ("ח", "x"),
+    ("ט", "t"),
+    ("יי", "aj|ej"),
+    ("י", "j|i"),
+    ("כ", "k|x"),
+    ("ך", "x"),
+    ("ל", "l"),
+    ("מ", "m"),
+    ("ם", "m"),
+    ("נ", "n"),
+    ("ן", "n"),
+    ("ס", "s"),
+    ("ע", "a"),
+    ("פ", "p|f"),
+    ("ף", "f"),
+    ("צ", "ts"),
+    ("ץ", "ts"),
+    ("ק", "k"),
+    ("ר", "r"),
+    ("ש", "S|s"),
+    ("ת", "t|s"),
+];
+
+const HUNGARIAN: &[(&str, &str)] = &[
+    ("ccs", "tS"),
+    ("cs", "tS"),
+    ("cz", "ts"),
+    ("ch", "x|tS"),
+    ("c", "ts"),
+    ("sz", "s"),
+    ("zs", "Z"),
+    ("s", "S"),
+    ("gy", "dj|g"),
+    ("ny", "nj|n"),
+    ("ty", "tj|t"),
+    ("ly", "j|l"),
+    ("ö", "e|o"),
+    ("ő", "e|o"),
+    ("ü", "i|u"),
+    ("ű", "i|u"),
+    ("j", "j"),
+    ("w", "v"),
+];
+
+const POLISH: &[(&str, &str)] = &[
+    ("szcz", "StS"),
+    ("sz", "S"),
+    ("cz", "tS"),
+    ("ch", "x"),
+    ("rz", "Z|S"),
+    ("dż", "dZ"),
+    ("dź", "dZ"),
+    ("dz", "dz"),
+    ("ść", "StS"),
+    ("wicz", "vitS"),
+    ("ci", "tSi"),
+    ("si", "Si"),
+    ("zi", "Zi"),
+    ("c", "ts"),
+    ("w", "v"),
+    ("ł", "v|l"),
+    ("ó", "u"),
+    ("ą", "on|o"),
+    ("ę", "en|e"),
+    ("ś", "S"),
+    ("ź", "Z"),
+    ("ż", "Z"),
+    ("ć", "tS"),
+    ("ń", "n"),
+    ("ia", "ja"),
+    ("ie", "je"),
+    ("io", "jo"),
+    ("iu", "ju"),
+    ("j", "j"),
+    ("y", "i"),
+];
+
+const RUSSIAN: &[(&str, &str)] = &[
+    ("shch", "StS"),
+    ("sch", "StS|S"),
+    ("sh", "S"),
+    ("zh", "Z"),
+    ("kh", "x"),
+    ("ch", "tS"),
+    ("ck", "k"),
+    ("tz", "ts"),
+    ("c", "ts|k"),
+    ("ya", "ja"),
+    ("yu", "ju"),
+    ("ye", "je"),
+    ("yo", "jo"),
+    ("iy$", "i"),
+    ("yy$", "i"),
+    ("y", "i|j"),
+    ("j", "j|dZ"),
+    ("w", "v"),
+    ("x", "ks"),
+    ("а", "a"),
+    ("б", "b"),
+    ("в", "v"),
+    ("г", "g"),
+    ("д", "d"),
+    ("е", "e"),
+    ("ё", "jo"),
+    ("ж", "Z"),
+    ("з", "z"),
+    ("ий$", "i"),
+    ("ый$", "i"),
+    ("и", "i"),
+    ("й", "j"),
+    ("к", "k"),
+    ("л", "l"),
+    ("м", "m"),
+    ("н", "n"),
+    ("о", "o"),
+    ("п", "p"),
+    ("р", "r"),
+    ("с", "s"),
+    ("т", "t"),
+    ("у", "u"),
+    ("ф", "f"),
+    ("х", "x"),
+    ("ц", "ts"),
+    ("ч", "tS"),
+    ("ш", "S"),
+    ("щ", "StS"),
+    ("ъ", ""),
+    ("ы", "i"),
+    ("ь", ""),
+    ("э", "e"),
+    ("ю", "ju"),
+    ("я", "ja"),
+];
+
+/// Rules for Latin letters whatever the language.
+const GENERIC: &[(&str, &str)] = &[
+    ("sch", "S"),
+    ("sh", "S"),
+    ("zh", "Z"),
+    ("kh", "x"),
+    ("tsch", "tS"),
+    ("tch", "tS"),
+    ("ch", "x|tS"),
+    ("cz", "tS"),
+    ("cs", "tS|ks"),
+    ("sz", "S|s"),
+    ("ck", "k"),
+    ("ph", "f"),
+    ("th", "t"),
+    ("gh", "g"),
+    ("qu", "kv"),
+    ("tz", "ts"),
+    ("ce", "tse|se"),
+    ("ci", "tsi|si"),
+    ("c", "k|ts"),
+    ("x", "ks"),
+    ("q", "k"),
+    ("w", "v"),
+    ("y", "i"),
+    ("j", "j|dZ"),
+    ("ä", "e"),
+    ("ö", "e|o"),
+    ("ü", "i|u"),
+    ("ß", "s"),
+    ("ł", "l|v"),
+    ("ą", "on|o"),
+    ("ę", "en|e"),
+    ("ś", "S|s"),
+    ("ź", "Z|z"),
+    ("ż", "Z"),
+    ("ń", "n"),
+    ("ć", "tS"),
+    ("ó", "u|o"),
+    ("ő", "e|o"),
+    ("ű", "i|u"),
+    ("á", "a"),
+    ("à", "a"),
+    ("é", "e"),
+    ("è", "e"),
+    ("í", "i"),
+    ("ú", "u"),
+    ("ñ", "n"),
+    ("ç", "s"),
+    ("š", "S"),
+    ("č", "tS"),
+    ("ž", "Z"),
+    ("ř", "Z"),
+    ("a", "a"),
+    ("b", "b"),
+    ("d", "d"),
+    ("e", "e"),
+    ("f", "f"),
+    ("g", "g"),
+    ("h", "h"),
+    ("i", "i"),
+    ("k", "k"),
+    ("l", "l"),
+    ("m", "m"),
+    ("n", "n"),
+    ("o", "o"),
+    ("p", "p"),
+    ("r", "r"),
+    ("s", "s"),
+    ("t", "t"),
+    ("u", "u"),
+    ("v", "v"),
+    ("z", "z"),
+];
+
+impl Language {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "any" => Ok(Language::Any),
+            "english" => Ok(Language::English),
+            "german" => Ok(Language::German),
+            "hebrew" => Ok(Language::Hebrew),
+            "hungarian" => Ok(Language::Hungarian),
+            "polish" => Ok(Language::Polish),
+            "russian" => Ok(Language::Russian),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unknown language: {}",
+                name
+            ))),
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Language::Any => "any",
+            Language::English => "english",
+            Language::German => "german",
+            Language::Hebrew => "hebrew",
+            Language::Hungarian => "hungarian",
+            Language::Polish => "polish",
+            Language::Russian => "russian",
+        }
+    }
+
+    fn rules(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::Any => &[],
+            Language::English => ENGLISH,
+            Language::German => GERMAN,
+            Language::Hebrew => HEBREW,
+            Language::Hungarian => HUNGARIAN,
+            Language::Polish => POLISH,
+            Language::Russian => RUSSIAN,
+        }
+    }
+}
+
+/// Length in letters of `pattern` if it matches `word` at `i`.
+fn match_at(word: &[char], i: usize, pattern: &str) -> Option<usize> {
+    let (start, pattern) = match pattern.strip_prefix('^') {
+        Some(pattern) => (true, pattern),
+        None => (false, pattern),
+    };
+    let (end, pattern) = match pattern.strip_suffix('$') {
+        Some(pattern) => (true, pattern),
+        None => (false, pattern),
+    };
+    if start && i != 0 {
+        return None;
+    }
+    let mut len = 0;
+    for c in pattern.chars() {
+        if word.get(i + len) != Some(&c) {
+            return None;
+        }
+        len += 1;
+    }
+    if end && i + len != word.len() {
+        return None;
+    }
+    Some(len)
+}
+
+/// Longest rule matching at `i`, as its length and pronunciations.
+fn longest(word: &[char], i: usize, rules: &[(&str, &'static str)]) -> Option<(usize, &'static str)> {
+    let mut best: Option<(usize, &'static str)> = None;
+    for &(pattern, pronunciations) in rules {
+        match (match_at(word, i, pattern), best) {
+            (Some(len), Some((best_len, _))) if len <= best_len => {}
+            (Some(len), _) => best = Some((len, pronunciations)),
+            (None, _) => {}
+        }
+    }
+    best
+}
+
+/// Lower-cased letters of `s`.
+fn letters(s: &str) -> Vec<char> {
+    s.chars().flat_map(char::to_lowercase).filter(|c| c.is_alphabetic()).collect()
+}
+
+fn detect(word: &[char]) -> Vec<Language> {
+    if word.iter().any(|c| ('\u{05D0}'..='\u{05EA}').contains(c)) {
+        return vec![Language::Hebrew];
+    }
+    if word.iter().any(|c| ('\u{0400}'..='\u{04FF}').contains(c)) {
+        return vec![Language::Russian];
+    }
+    let mut languages = LATIN.to_vec();
+    for &(pattern, cue, accept) in CUES {
+        if (0..word.len()).any(|i| match_at(word, i, pattern).is_some()) {
+            languages.retain(|language| cue.contains(language) == accept);
+        }
+    }
+    if languages.is_empty() || languages.len() == LATIN.len() {
+        vec![Language::Any]
+    } else {
+        languages
+    }
+}
+
+fn transcribe(word: &[char], language: Language) -> BTreeSet<String> {
+    let mut transcriptions: BTreeSet<String> = std::iter::once(String::new()).collect();
+    let mut i = 0;
+    while i < word.len() {
+        match longest(word, i, language.rules()).or_else(|| longest(word, i, GENERIC)) {
+            Some((len, pronunciations)) => {
+                transcriptions = transcriptions
+                    .iter()
+                    .flat_map(|prefix| pronunciations.split('|').map(move |p| format!("{}{}", prefix, p)))
+                    .take(MAX_TRANSCRIPTIONS)
+                    .collect();
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+    transcriptions
+}
+
+/// Folds a transcription into a code: an initial vowel becomes `a`, later
+/// vowels and `h` are dropped, repeats merged and the final consonant
+/// devoiced.
+fn approximate(transcription: &str) -> String {
+    let mut code = String::new();
+    for (i, c) in transcription.chars().enumerate() {
+        let c = match c {
+            'a' | 'e' | 'i' | 'o' | 'u' if i == 0 => 'a',
+            'a' | 'e' | 'i' | 'o' | 'u' | 'h' => continue,
+            c => c,
+        };
+        if !code.ends_with(c) {
+            code.push(c);
+        }
+    }
+    let devoiced = match code.chars().last() {
+        Some('b') => 'p',
+        Some('d') => 't',
+        Some('g') => 'k',
+        Some('v') => 'f',
+        Some('z') => 's',
+        Some('Z') => 'S',
+        _ => return code,
+    };
+    code.pop();
+    if !code.ends_with(devoiced) {
+        code.push(devoiced);
+    }
+    code
+}
+
+/// Languages `s` may be written in, guessed from its script and spelling.
+pub(crate) fn languages(s: &str) -> Vec<Language> {
+    detect(&letters(s))
+}
+
+/// Codes of `s` under the given languages, or the detected ones.
+pub(crate) fn encode(s: &str, languages: Option<&[Language]>) -> BTreeSet<String> {
+    let word = letters(s);
+    if word.is_empty() {
+        return BTreeSet::new();
+    }
+    let detected;
+    let languages = match languages {
+        Some(languages) => languages,
+        None => {
+            detected = detect(&word);
+            &detected
+        }
+    };
+    languages
+        .iter()
+        .flat_map(|&language| transcribe(&word, language))
+        .map(|transcription| approximate(&transcription))
+        .filter(|code| !code.is_empty())
+        .collect()
+}
+
+fn parse_languages(names: Option<Vec<&str>>) -> PyResult<Option<Vec<Language>>> {
+    names
+        .map(|names| names.into_iter().map(Language::parse).collect())
+        .transpose()
+}
+
+/// approx_beider_morse(s, languages=None)
+///
+/// Encodes a name in the manner of Beider-Morse phonetic matching, with a
+/// small rule set of its own. The name is transcribed under each language it
+/// may be written in, into every plausible pronunciation, so it has a set of
+/// codes. The rules cover English, German, Polish, Hungarian, Russian
+/// (Cyrillic and romanized) and Hebrew script only.
+///
+/// :param str s: string to encode
+/// :param list languages: languages to transcribe under, among ``"any"``,
+///     ``"english"``, ``"german"``, ``"hebrew"``, ``"hungarian"``,
+///     ``"polish"`` and ``"russian"``; None detects them with
+///     :py:func:`approx_beider_morse_languages`
+/// :return: codes, empty if s has no letters
+/// :rtype: set
+/// :raises ValueError: if a language is unknown
+#[pyfunction]
+#[pyo3(signature = (s, languages=None))]
+pub(crate) fn approx_beider_morse(s: &str, languages: Option<Vec<&str>>) -> PyResult<BTreeSet<String>> {
+    let languages = parse_languages(languages)?;
+    Ok(encode(s, languages.as_deref()))
+}
+
+/// approx_beider_morse_parallel(strings, languages=None)
+///
+/// Like :py:func:`approx_beider_morse`, for a list of strings. Operates in parallel
+/// over the list.
+///
+/// :param list strings: strings to encode
+/// :param list languages: languages to transcribe under; None detects them
+///     for each string
+/// :return: one set of codes per string, in input order
+/// :rtype: list
+/// :raises ValueError: if a language is unknown
+#[pyfunction]
+#[pyo3(signature = (strings, languages=None))]
+pub(crate) fn approx_beider_morse_parallel(
+    py: Python,
+    strings: Vec<&str>,
+    languages: Option<Vec<&str>>,
+) -> PyResult<Vec<BTreeSet<String>>> {
+    let languages = parse_languages(languages)?;
+    Ok(py.allow_threads(|| {
+        strings
+            .par_iter()
+            .map(|s| encode(s, languages.as_deref()))
+            .collect()
+    }))
+}
+
+/// approx_beider_morse_languages(s)
+///
+/// Guesses the languages a name may be written in from its script and
+/// spelling, e.g. "sch" for German or "wicz" for Polish. Returns ``["any"]``
+/// when the spelling gives no clue or contradicting ones.
+///
+/// :param str s: name
+/// :return: language names
+/// :rtype: list
+#[pyfunction]
+pub(crate) fn approx_beider_morse_languages(s: &str) -> Vec<&'static str> {
+    languages(s).into_iter().map(Language::name).collect()
+}
@@ -0,0 +1,147 @@
+//! Caverphone 2.0, designed by David Hood for New Zealand electoral rolls.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Length of every code; shorter ones are padded with `1`.
+const LENGTH: usize = 10;
+
+/// Rewrite rules applied in order, as `(pattern, replacement)`. A leading `^`
+/// anchors the pattern at the start and a trailing `$` at the end of the word.
+const RULES: &[(&str, &str)] = &[
+    ("e$", ""),
+    ("^cough", "cou2f"),
+    ("^rough", "rou2f"),
+    ("^tough", "tou2f"),
+    ("^enough", "enou2f"),
+    ("^trough", "trou2f"),
+    ("^gn", "2n"),
+    ("mb$", "m2"),
+    ("cq", "2q"),
+    ("ci", "si"),
+    ("ce", "se"),
+    ("cy", "sy"),
+    ("tch", "2ch"),
+    ("c", "k"),
+    ("q", "k"),
+    ("x", "k"),
+    ("v", "f"),
+    ("dg", "2g"),
+    ("tio", "sio"),
+    ("tia", "sia"),
+    ("d", "t"),
+    ("ph", "fh"),
+    ("b", "p"),
+    ("sh", "s2"),
+    ("z", "s"),
+    ("^a", "A"),
+    ("^e", "A"),
+    ("^i", "A"),
+    ("^o", "A"),
+    ("^u", "A"),
+    ("a", "3"),
+    ("e", "3"),
+    ("i", "3"),
+    ("o", "3"),
+    ("u", "3"),
+    ("j", "y"),
+    ("^y3", "Y3"),
+    ("^y", "A"),
+    ("y", "3"),
+    ("3gh3", "3kh3"),
+    ("gh", "22"),
+    ("g", "k"),
+    ("s+", "S"),
+    ("t+", "T"),
+    ("p+", "P"),
+    ("k+", "K"),
+    ("f+", "F"),
+    ("m+", "M"),
+    ("n+", "N"),
+    ("w3", "W3"),
+    ("wh3", "Wh3"),
+    ("w$", "3"),
+    ("w", "2"),
+    ("^h", "H"),
+    ("h", "2"),
+    ("r3", "R3"),
+    ("r$", "3"),
+    ("r", "2"),
+    ("l3", "L3"),
+    ("l$", "3"),
+    ("l", "2"),
+    ("2", ""),
+    ("3$", "A"),
+    ("3", ""),
+];
+
+/// Applies one rule. Patterns are literal apart from the anchors and a
+/// trailing `+` on a single letter, which matches a run of it.
+fn rewrite(word: &str, pattern: &str, replacement: &str) -> String {
+    if let Some(prefix) = pattern.strip_prefix('^') {
+        return match word.strip_prefix(prefix) {
+            Some(rest) => format!("{}{}", replacement, rest),
+            None => word.to_string(),
+        };
+    }
+    if let Some(suffix) = pattern.strip_suffix('$') {
+        return match word.strip_suffix(suffix) {
+            Some(rest) => format!("{}{}", rest, replacement),
+            None => word.to_string(),
+        };
+    }
+    if let Some(letter) = pattern.strip_suffix('+') {
+        let letter = letter.chars().next().expect("single letter");
+        let mut out = String::with_capacity(word.len());
+        let mut previous = None;
+        for c in word.chars() {
+            if c != letter {
+                out.push(c);
+            } else if previous != Some(letter) {
+                out.push_str(replacement);
+            }
+            previous = Some(c);
+        }
+        return out;
+    }
+    word.replace(pattern, replacement)
+}
+
+/// Ten-character Caverphone 2.0 code of `s`. Only ASCII letters are
+/// considered.
+pub(crate) fn encode(s: &str) -> String {
+    let word: String = s
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if word.is_empty() {
+        return String::new();
+    }
+    let mut code = RULES
+        .iter()
+        .fold(word, |word, (pattern, replacement)| rewrite(&word, pattern, replacement));
+    code.push_str(&"1".repeat(LENGTH));
+    code.truncate(LENGTH);
+    code
+}
+
+encoder! {
+    /// caverphone(s)
+    ///
+    /// Encodes a name with Caverphone 2.0, tuned for the accents found in New
+    /// Zealand names. Codes are ten characters long, padded with ``1``.
+    ///
+    /// :param str s: string to encode
+    /// :return: code, or an empty string if s has no letters
+    /// :rtype: str
+    caverphone;
+    /// caverphone_parallel(strings)
+    ///
+    /// Like :py:func:`caverphone`, for a list of strings. Operates in parallel
+    /// over the list.
+    ///
+    /// :param list strings: strings to encode
+    /// :return: codes, in input order
+    /// :rtype: list
+}
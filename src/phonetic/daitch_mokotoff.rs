@@ -0,0 +1,231 @@
+//! Daitch-Mokotoff Soundex, designed for Slavic and Yiddish surnames.
+//!
+//! Letter groups are coded differently at the start of a name, before a
+//! vowel and elsewhere, and ambiguous groups such as "ch" or "rz" branch into
+//! several codes, so a name has a set of six-digit codes rather than one.
+
+use std::collections::BTreeSet;
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use super::soundex::letters;
+
+const LENGTH: usize = 6;
+
+/// `(pattern, at start, before a vowel, elsewhere)`. Alternatives are
+/// separated by `|`, and an empty code leaves the letters uncoded. Patterns
+/// starting with the same letter are listed longest first.
+const RULES: &[(&str, &str, &str, &str)] = &[
+    ("AI", "0", "1", ""),
+    ("AJ", "0", "1", ""),
+    ("AY", "0", "1", ""),
+    ("AU", "0", "7", ""),
+    ("A", "0", "", ""),
+    ("B", "7", "7", "7"),
+    ("CHS", "5", "54", "54"),
+    ("CSZ", "4", "4", "4"),
+    ("CZS", "4", "4", "4"),
+    ("CH", "5|4", "5|4", "5|4"),
+    ("CK", "5|45", "5|45", "5|45"),
+    ("CZ", "4", "4", "4"),
+    ("CS", "4", "4", "4"),
+    ("C", "5|4", "5|4", "5|4"),
+    ("DRZ", "4", "4", "4"),
+    ("DRS", "4", "4", "4"),
+    ("DSH", "4", "4", "4"),
+    ("DSZ", "4", "4", "4"),
+    ("DZH", "4", "4", "4"),
+    ("DZS", "4", "4", "4"),
+    ("DS", "4", "4", "4"),
+    ("DZ", "4", "4", "4"),
+    ("DT", "3", "3", "3"),
+    ("D", "3", "3", "3"),
+    ("EI", "0", "1", ""),
+    ("EJ", "0", "1", ""),
+    ("EY", "0", "1", ""),
+    ("EU", "1", "1", ""),
+    ("E", "0", "", ""),
+    ("FB", "7", "7", "7"),
+    ("F", "7", "7", "7"),
+    ("G", "5", "5", "5"),
+    ("H", "5", "5", ""),
+    ("IA", "1", "", ""),
+    ("IE", "1", "", ""),
+    ("IO", "1", "", ""),
+    ("IU", "1", "", ""),
+    ("I", "0", "", ""),
+    ("J", "1|4", "|4", "|4"),
+    ("KS", "5", "54", "54"),
+    ("KH", "5", "5", "5"),
+    ("K", "5", "5", "5"),
+    ("L", "8", "8", "8"),
+    ("MN", "", "66", "66"),
+    ("M", "6", "6", "6"),
+    ("NM", "", "66", "66"),
+    ("N", "6", "6", "6"),
+    ("OI", "0", "1", ""),
+    ("OJ", "0", "1", ""),
+    ("OY", "0", "1", ""),
+    ("O", "0", "", ""),
+    ("PF", "7", "7", "7"),
+    ("PH", "7", "7", "7"),
+    ("P", "7", "7", "7"),
+    ("Q", "5", "5", "5"),
+    ("RZ", "94|4", "94|4", "94|4"),
+    ("RS", "94|4", "94|4", "94|4"),
+    ("R", "9", "9", "9"),
+    ("SCHTSCH", "2", "4", "4"),
+    ("SCHTSH", "2", "4", "4"),
+    ("SCHTCH", "2", "4", "4"),
+    ("SHTSCH", "2", "4", "4"),
+    ("SHTCH", "2", "4", "4"),
+    ("SHTSH", "2", "4", "4"),
+    ("STSCH", "2", "4", "4"),
+    ("SCHT", "2", "43", "43"),
+    ("SCHD", "2", "43", "43"),
+    ("SHCH", "2", "4", "4"),
+    ("STCH", "2", "4", "4"),
+    ("STRZ", "2", "4", "4"),
+    ("STRS", "2", "4", "4"),
+    ("STSH", "2", "4", "4"),
+    ("SZCZ", "2", "4", "4"),
+    ("SZCS", "2", "4", "4"),
+    ("SCH", "4", "4", "4"),
+    ("SHT", "2", "43", "43"),
+    ("SZT", "2", "43", "43"),
+    ("SHD", "2", "43", "43"),
+    ("SZD", "2", "43", "43"),
+    ("SC", "2", "4", "4"),
+    ("SD", "2", "43", "43"),
+    ("SH", "4", "4", "4"),
+    ("ST", "2", "43", "43"),
+    ("SZ", "4", "4", "4"),
+    ("S", "4", "4", "4"),
+    ("TTSCH", "4", "4", "4"),
+    ("TSCH", "4", "4", "4"),
+    ("TTCH", "4", "4", "4"),
+    ("TTSZ", "4", "4", "4"),
+    ("TCH", "4", "4", "4"),
+    ("TRZ", "4", "4", "4"),
+    ("TRS", "4", "4", "4"),
+    ("TSH", "4", "4", "4"),
+    ("TTS", "4", "4", "4"),
+    ("TTZ", "4", "4", "4"),
+    ("TZS", "4", "4", "4"),
+    ("TSZ", "4", "4", "4"),
+    ("TH", "3", "3", "3"),
+    ("TS", "4", "4", "4"),
+    ("TC", "4", "4", "4"),
+    ("TZ", "4", "4", "4"),
+    ("T", "3", "3", "3"),
+    ("UI", "0", "1", ""),
+    ("UJ", "0", "1", ""),
+    ("UY", "0", "1", ""),
+    ("UE", "0", "", ""),
+    ("U", "0", "", ""),
+    ("V", "7", "7", "7"),
+    ("W", "7", "7", "7"),
+    ("X", "5", "54", "54"),
+    ("Y", "1", "", ""),
+    ("ZHDZH", "2", "4", "4"),
+    ("ZDZH", "2", "4", "4"),
+    ("ZSCH", "4", "4", "4"),
+    ("ZDZ", "2", "4", "4"),
+    ("ZHD", "2", "43", "43"),
+    ("ZSH", "4", "4", "4"),
+    ("ZD", "2", "43", "43"),
+    ("ZH", "4", "4", "4"),
+    ("ZS", "4", "4", "4"),
+    ("Z", "4", "4", "4"),
+];
+
+fn is_vowel(letter: u8) -> bool {
+    matches!(letter, b'A' | b'E' | b'I' | b'O' | b'U')
+}
+
+/// Code being built along one branch of the alternatives.
+#[derive(Clone)]
+struct Branch {
+    code: String,
+    /// Code of the previous letter group, which a repeat is merged into.
+    last: Option<&'static str>,
+}
+
+impl Branch {
+    fn push(&mut self, code: &'static str, force: bool) {
+        let repeat = self.last.is_some_and(|last| last.ends_with(code));
+        if (force || !repeat) && self.code.len() < LENGTH {
+            self.code.push_str(code);
+            self.code.truncate(LENGTH);
+        }
+        self.last = Some(code);
+    }
+}
+
+/// Codes of `s`, one per reading. Only ASCII letters are considered.
+pub(crate) fn encode(s: &str) -> BTreeSet<String> {
+    let word = letters(s);
+    if word.is_empty() {
+        return BTreeSet::new();
+    }
+    let mut branches = vec![Branch { code: String::new(), last: None }];
+    let mut i = 0;
+    while i < word.len() {
+        let rest = &word[i..];
+        let &(pattern, start, vowel, other) = RULES
+            .iter()
+            .find(|rule| rest.starts_with(rule.0.as_bytes()))
+            .expect("every letter has a rule");
+        let codes = if i == 0 {
+            start
+        } else if rest.get(pattern.len()).is_some_and(|&next| is_vowel(next)) {
+            vowel
+        } else {
+            other
+        };
+        // "mn" and "nm" code both letters even though they share a digit.
+        let force = i > 0 && matches!((word[i - 1], word[i]), (b'M', b'N') | (b'N', b'M'));
+        branches = branches
+            .into_iter()
+            .flat_map(|branch| {
+                codes.split('|').map(move |code| {
+                    let mut branch = branch.clone();
+                    branch.push(code, force);
+                    branch
+                })
+            })
+            .collect();
+        i += pattern.len();
+    }
+    branches
+        .into_iter()
+        .map(|branch| format!("{:0<width$}", branch.code, width = LENGTH))
+        .collect()
+}
+
+/// daitch_mokotoff(s)
+///
+/// Encodes a name with Daitch-Mokotoff Soundex. Ambiguous letters such as
+/// "ch" or "rz" yield one six-digit code per reading.
+///
+/// :param str s: string to encode
+/// :return: codes, empty if s has no letters
+/// :rtype: set
+#[pyfunction]
+pub(crate) fn daitch_mokotoff(s: &str) -> BTreeSet<String> {
+    encode(s)
+}
+
+/// daitch_mokotoff_parallel(strings)
+///
+/// Like :py:func:`daitch_mokotoff`, for a list of strings. Operates in
+/// parallel over the list.
+///
+/// :param list strings: strings to encode
+/// :return: one set of codes per string, in input order
+/// :rtype: list
+#[pyfunction]
+pub(crate) fn daitch_mokotoff_parallel(py: Python, strings: Vec<&str>) -> Vec<BTreeSet<String>> {
+    py.allow_threads(|| strings.par_iter().map(|s| encode(s)).collect())
+}
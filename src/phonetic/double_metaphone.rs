@@ -0,0 +1,585 @@
+//! Lawrence Philips' Double Metaphone.
+//!
+//! Every rule of the original is kept, including the special cases for
+//! Germanic, Slavic, Spanish, Italian and French spellings, so codes agree
+//! with the reference implementations. Where a spelling has two plausible
+//! pronunciations the alternate code takes the second one.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Length of the codes unless asked otherwise, as in the original.
+pub(crate) const MAX_LENGTH: usize = 4;
+
+/// Primary and alternate codes being built, each capped at `max_length`.
+struct Codes {
+    primary: String,
+    alternate: String,
+    max_length: usize,
+}
+
+impl Codes {
+    fn push(&mut self, primary: &str, alternate: &str) {
+        for (code, add) in [(&mut self.primary, primary), (&mut self.alternate, alternate)] {
+            let room = self.max_length.saturating_sub(code.len());
+            code.push_str(&add[..add.len().min(room)]);
+        }
+    }
+
+    fn both(&mut self, code: &str) {
+        self.push(code, code);
+    }
+
+    fn is_complete(&self) -> bool {
+        self.primary.len() >= self.max_length && self.alternate.len() >= self.max_length
+    }
+}
+
+struct Word {
+    chars: Vec<char>,
+    slavo_germanic: bool,
+}
+
+impl Word {
+    fn new(s: &str) -> Self {
+        let chars: Vec<char> = s.trim().chars().flat_map(char::to_uppercase).collect();
+        let text: String = chars.iter().collect();
+        let slavo_germanic = text.contains('W') || text.contains('K') || text.contains("CZ");
+        Word { chars, slavo_germanic }
+    }
+
+    fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Character at a signed position, NUL outside the word.
+    fn at(&self, i: isize) -> char {
+        if i < 0 {
+            return '\0';
+        }
+        self.chars.get(i as usize).copied().unwrap_or('\0')
+    }
+
+    /// Whether the `len` characters starting at `start` are one of `options`.
+    fn is(&self, start: isize, len: usize, options: &[&str]) -> bool {
+        if start < 0 || start as usize + len > self.chars.len() {
+            return false;
+        }
+        let start = start as usize;
+        options
+            .iter()
+            .any(|option| option.chars().eq(self.chars[start..start + len].iter().copied()))
+    }
+
+    fn is_vowel(&self, i: isize) -> bool {
+        matches!(self.at(i), 'A' | 'E' | 'I' | 'O' | 'U' | 'Y')
+    }
+
+    fn last(&self) -> isize {
+        self.len() as isize - 1
+    }
+
+    /// Skips a doubled `letter`.
+    fn step(&self, i: isize, letter: char) -> isize {
+        if self.at(i + 1) == letter {
+            i + 2
+        } else {
+            i + 1
+        }
+    }
+}
+
+/// Primary and alternate codes of `s`, each at most `max_length` long. The
+/// alternate equals the primary when the spelling is unambiguous.
+pub(crate) fn encode(s: &str, max_length: usize) -> (String, String) {
+    let w = Word::new(s);
+    let mut codes = Codes { primary: String::new(), alternate: String::new(), max_length };
+    let mut i: isize = if w.is(0, 2, &["GN", "KN", "PN", "WR", "PS"]) { 1 } else { 0 };
+    while !codes.is_complete() && i <= w.last() {
+        i = match w.at(i) {
+            'A' | 'E' | 'I' | 'O' | 'U' | 'Y' => {
+                if i == 0 {
+                    codes.both("A");
+                }
+                i + 1
+            }
+            'B' => {
+                codes.both("P");
+                w.step(i, 'B')
+            }
+            'Ç' => {
+                codes.both("S");
+                i + 1
+            }
+            'C' => handle_c(&w, &mut codes, i),
+            'D' => handle_d(&w, &mut codes, i),
+            'F' => {
+                codes.both("F");
+                w.step(i, 'F')
+            }
+            'G' => handle_g(&w, &mut codes, i),
+            'H' => {
+                if (i == 0 || w.is_vowel(i - 1)) && w.is_vowel(i + 1) {
+                    codes.both("H");
+                    i + 2
+                } else {
+                    i + 1
+                }
+            }
+            'J' => handle_j(&w, &mut codes, i),
+            'K' => {
+                codes.both("K");
+                w.step(i, 'K')
+            }
+            'L' => {
+                if w.at(i + 1) == 'L' {
+                    if spanish_ll(&w, i) {
+                        codes.push("L", "");
+                    } else {
+                        codes.both("L");
+                    }
+                    i + 2
+                } else {
+                    codes.both("L");
+                    i + 1
+                }
+            }
+            'M' => {
+                codes.both("M");
+                let silent_b = w.is(i - 1, 3, &["UMB"]) && (i + 1 == w.last() || w.is(i + 2, 2, &["ER"]));
+                if w.at(i + 1) == 'M' || silent_b {
+                    i + 2
+                } else {
+                    i + 1
+                }
+            }
+            'N' => {
+                codes.both("N");
+                w.step(i, 'N')
+            }
+            'Ñ' => {
+                codes.both("N");
+                i + 1
+            }
+            'P' => {
+                if w.at(i + 1) == 'H' {
+                    codes.both("F");
+                    i + 2
+                } else {
+                    codes.both("P");
+                    if w.is(i + 1, 1, &["P", "B"]) {
+                        i + 2
+                    } else {
+                        i + 1
+                    }
+                }
+            }
+            'Q' => {
+                codes.both("K");
+                w.step(i, 'Q')
+            }
+            'R' => {
+                // French endings such as "Rogier" drop the final r.
+                if i == w.last() && !w.slavo_germanic && w.is(i - 2, 2, &["IE"]) && !w.is(i - 4, 2, &["ME", "MA"]) {
+                    codes.push("", "R");
+                } else {
+                    codes.both("R");
+                }
+                w.step(i, 'R')
+            }
+            'S' => handle_s(&w, &mut codes, i),
+            'T' => handle_t(&w, &mut codes, i),
+            'V' => {
+                codes.both("F");
+                w.step(i, 'V')
+            }
+            'W' => handle_w(&w, &mut codes, i),
+            'X' => {
+                if i == 0 {
+                    codes.both("S");
+                    i + 1
+                } else {
+                    // French endings such as "Breaux" are silent.
+                    let silent = i == w.last() && (w.is(i - 3, 3, &["IAU", "EAU"]) || w.is(i - 2, 2, &["AU", "OU"]));
+                    if !silent {
+                        codes.both("KS");
+                    }
+                    if w.is(i + 1, 1, &["C", "X"]) {
+                        i + 2
+                    } else {
+                        i + 1
+                    }
+                }
+            }
+            'Z' => {
+                if w.at(i + 1) == 'H' {
+                    // Chinese pinyin, e.g. "Zhao".
+                    codes.both("J");
+                    i + 2
+                } else {
+                    if w.is(i + 1, 2, &["ZO", "ZI", "ZA"]) || (w.slavo_germanic && i > 0 && w.at(i - 1) != 'T') {
+                        codes.push("S", "TS");
+                    } else {
+                        codes.both("S");
+                    }
+                    w.step(i, 'Z')
+                }
+            }
+            _ => i + 1,
+        };
+    }
+    (codes.primary, codes.alternate)
+}
+
+/// double_metaphone(s, max_length=4)
+///
+/// Encodes a name with Double Metaphone, which also knows common Germanic,
+/// Slavic, Romance and Chinese spellings. Ambiguous spellings get a second,
+/// alternate code; otherwise both codes are the same.
+///
+/// :param str s: string to encode
+/// :param int max_length: maximum length of each code
+/// :return: ``(primary, alternate)`` codes
+/// :rtype: tuple
+#[pyfunction]
+#[pyo3(signature = (s, max_length=MAX_LENGTH))]
+pub(crate) fn double_metaphone(s: &str, max_length: usize) -> (String, String) {
+    encode(s, max_length)
+}
+
+/// double_metaphone_parallel(strings, max_length=4)
+///
+/// Like :py:func:`double_metaphone`, for a list of strings. Operates in
+/// parallel over the list.
+///
+/// :param list strings: strings to encode
+/// :param int max_length: maximum length of each code
+/// :return: ``(primary, alternate)`` codes, in input order
+/// :rtype: list
+#[pyfunction]
+#[pyo3(signature = (strings, max_length=MAX_LENGTH))]
+pub(crate) fn double_metaphone_parallel(py: Python, strings: Vec<&str>, max_length: usize) -> Vec<(String, String)> {
+    py.allow_threads(|| {
+        strings
+            .par_iter()
+            .map(|s| encode(s, max_length))
+            .collect()
+    })
+}
+
+/// Spanish "-llo", "-lla" and "-alle" endings, where the l is not sounded.
+fn spanish_ll(w: &Word, i: isize) -> bool {
+    let n = w.len() as isize;
+    if i == n - 3 && w.is(i - 1, 4, &["ILLO", "ILLA", "ALLE"]) {
+        return true;
+    }
+    (w.is(n - 2, 2, &["AS", "OS"]) || w.is(n - 1, 1, &["A", "O"])) && w.is(i - 1, 4, &["ALLE"])
+}
+
+fn handle_c(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    // Germanic "ach" as in "Bacher", but not "Macher".
+    let germanic = w.is(i, 4, &["CHIA"])
+        || (i > 1
+            && !w.is_vowel(i - 2)
+            && w.is(i - 1, 3, &["ACH"])
+            && ((w.at(i + 2) != 'I' && w.at(i + 2) != 'E') || w.is(i - 2, 6, &["BACHER", "MACHER"])));
+    if germanic {
+        codes.both("K");
+        i + 2
+    } else if i == 0 && w.is(i, 6, &["CAESAR"]) {
+        codes.both("S");
+        i + 2
+    } else if w.is(i, 2, &["CH"]) {
+        handle_ch(w, codes, i)
+    } else if w.is(i, 2, &["CZ"]) && !w.is(i - 2, 4, &["WICZ"]) {
+        codes.push("S", "X");
+        i + 2
+    } else if w.is(i + 1, 3, &["CIA"]) {
+        codes.both("X");
+        i + 3
+    } else if w.is(i, 2, &["CC"]) && !(i == 1 && w.at(0) == 'M') {
+        // "Bacci", "Bertucci" but "McClellan".
+        if w.is(i + 2, 1, &["I", "E", "H"]) && !w.is(i + 2, 2, &["HU"]) {
+            if (i == 1 && w.at(i - 1) == 'A') || w.is(i - 1, 5, &["UCCEE", "UCCES"]) {
+                codes.both("KS");
+            } else {
+                codes.both("X");
+            }
+            i + 3
+        } else {
+            codes.both("K");
+            i + 2
+        }
+    } else if w.is(i, 2, &["CK", "CG", "CQ"]) {
+        codes.both("K");
+        i + 2
+    } else if w.is(i, 2, &["CI", "CE", "CY"]) {
+        if w.is(i, 3, &["CIO", "CIE", "CIA"]) {
+            codes.push("S", "X");
+        } else {
+            codes.both("S");
+        }
+        i + 2
+    } else {
+        codes.both("K");
+        if w.is(i + 1, 2, &[" C", " Q", " G"]) {
+            i + 3
+        } else if w.is(i + 1, 1, &["C", "K", "Q"]) && !w.is(i + 1, 2, &["CE", "CI"]) {
+            i + 2
+        } else {
+            i + 1
+        }
+    }
+}
+
+fn handle_ch(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    // Greek roots such as "chorus" and "character".
+    let greek = i == 0
+        && (w.is(i + 1, 5, &["HARAC", "HARIS"]) || w.is(i + 1, 3, &["HOR", "HYM", "HIA", "HEM"]))
+        && !w.is(0, 5, &["CHORE"]);
+    // Germanic spellings and "orchestra", "architect" or "orchid".
+    let hard = w.is(0, 4, &["VAN ", "VON "])
+        || w.is(0, 3, &["SCH"])
+        || w.is(i - 2, 6, &["ORCHES", "ARCHIT", "ORCHID"])
+        || w.is(i + 2, 1, &["T", "S"])
+        || ((w.is(i - 1, 1, &["A", "O", "U", "E"]) || i == 0)
+            && (w.is(i + 2, 1, &["L", "R", "N", "M", "B", "H", "F", "V", "W", " "]) || i + 1 == w.last()));
+    if i > 0 && w.is(i, 4, &["CHAE"]) {
+        codes.push("K", "X");
+    } else if greek || hard {
+        codes.both("K");
+    } else if i > 0 {
+        if w.is(0, 2, &["MC"]) {
+            codes.both("K");
+        } else {
+            codes.push("X", "K");
+        }
+    } else {
+        codes.both("X");
+    }
+    i + 2
+}
+
+fn handle_d(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    if w.is(i, 2, &["DG"]) {
+        if w.is(i + 2, 1, &["I", "E", "Y"]) {
+            // "edge"
+            codes.both("J");
+            i + 3
+        } else {
+            // "Edgar"
+            codes.both("TK");
+            i + 2
+        }
+    } else if w.is(i, 2, &["DT", "DD"]) {
+        codes.both("T");
+        i + 2
+    } else {
+        codes.both("T");
+        i + 1
+    }
+}
+
+fn handle_g(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    if w.at(i + 1) == 'H' {
+        return handle_gh(w, codes, i);
+    }
+    if w.at(i + 1) == 'N' {
+        if i == 1 && w.is_vowel(0) && !w.slavo_germanic {
+            codes.push("KN", "N");
+        } else if !w.is(i + 2, 2, &["EY"]) && w.at(i + 1) != 'Y' && !w.slavo_germanic {
+            codes.push("N", "KN");
+        } else {
+            codes.both("KN");
+        }
+        i + 2
+    } else if w.is(i + 1, 2, &["LI"]) && !w.slavo_germanic {
+        // Italian "gli", as in "tagliaro".
+        codes.push("KL", "L");
+        i + 2
+    } else if (i == 0
+        && (w.at(i + 1) == 'Y' || w.is(i + 1, 2, &["ES", "EP", "EB", "EL", "EY", "IB", "IL", "IN", "IE", "EI", "ER"])))
+        || ((w.is(i + 1, 2, &["ER"]) || w.at(i + 1) == 'Y')
+            && !w.is(0, 6, &["DANGER", "RANGER", "MANGER"])
+            && !w.is(i - 1, 1, &["E", "I"])
+            && !w.is(i - 1, 3, &["RGY", "OGY"]))
+    {
+        // "Gerald" or "Geiger" at the start, "tiger" or "Bergy" inside.
+        codes.push("K", "J");
+        i + 2
+    } else if w.is(i + 1, 1, &["E", "I", "Y"]) || w.is(i - 1, 4, &["AGGI", "OGGI"]) {
+        if w.is(0, 4, &["VAN ", "VON "]) || w.is(0, 3, &["SCH"]) || w.is(i + 1, 2, &["ET"]) {
+            codes.both("K");
+        } else if w.is(i + 1, 3, &["IER"]) {
+            codes.both("J");
+        } else {
+            codes.push("J", "K");
+        }
+        i + 2
+    } else {
+        codes.both("K");
+        w.step(i, 'G')
+    }
+}
+
+fn handle_gh(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    if i > 0 && !w.is_vowel(i - 1) {
+        codes.both("K");
+    } else if i == 0 {
+        // "Ghislane" and "ghiradelli".
+        if w.at(i + 2) == 'I' {
+            codes.both("J");
+        } else {
+            codes.both("K");
+        }
+    } else if (i > 1 && w.is(i - 2, 1, &["B", "H", "D"]))
+        || (i > 2 && w.is(i - 3, 1, &["B", "H", "D"]))
+        || (i > 3 && w.is(i - 4, 1, &["B", "H"]))
+    {
+        // Silent, as in "Hugh", "bough" and "broughton".
+    } else if i > 2 && w.at(i - 1) == 'U' && w.is(i - 3, 1, &["C", "G", "L", "R", "T"]) {
+        // "laugh", "McLaughlin", "cough", "rough".
+        codes.both("F");
+    } else if i > 0 && w.at(i - 1) != 'I' {
+        codes.both("K");
+    }
+    i + 2
+}
+
+fn handle_j(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    if w.is(i, 4, &["JOSE"]) || w.is(0, 4, &["SAN "]) {
+        // Spanish "Jose" and "San Jacinto".
+        if (i == 0 && w.at(i + 4) == ' ') || w.len() == 4 || w.is(0, 4, &["SAN "]) {
+            codes.both("H");
+        } else {
+            codes.push("J", "H");
+        }
+        return i + 1;
+    }
+    if i == 0 {
+        codes.push("J", "A");
+    } else if w.is_vowel(i - 1) && !w.slavo_germanic && matches!(w.at(i + 1), 'A' | 'O') {
+        codes.push("J", "H");
+    } else if i == w.last() {
+        codes.push("J", "");
+    } else if !w.is(i + 1, 1, &["L", "T", "K", "S", "N", "M", "B", "Z"]) && !w.is(i - 1, 1, &["S", "K", "L"]) {
+        codes.both("J");
+    }
+    w.step(i, 'J')
+}
+
+fn handle_s(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    if w.is(i - 1, 3, &["ISL", "YSL"]) {
+        // Silent, as in "island" and "carlisle".
+        i + 1
+    } else if i == 0 && w.is(i, 5, &["SUGAR"]) {
+        codes.push("X", "S");
+        i + 1
+    } else if w.is(i, 2, &["SH"]) {
+        // Germanic "Holm" and "Oldenheim".
+        if w.is(i + 1, 4, &["HEIM", "HOEK", "HOLM", "HOLZ"]) {
+            codes.both("S");
+        } else {
+            codes.both("X");
+        }
+        i + 2
+    } else if w.is(i, 3, &["SIO", "SIA"]) || w.is(i, 4, &["SIAN"]) {
+        if w.slavo_germanic {
+            codes.both("S");
+        } else {
+            codes.push("S", "X");
+        }
+        i + 3
+    } else if (i == 0 && w.is(i + 1, 1, &["M", "N", "L", "W"])) || w.is(i + 1, 1, &["Z"]) {
+        // "Schmidt" is pronounced "Smith" and "Snider" "Schneider".
+        codes.push("S", "X");
+        if w.is(i + 1, 1, &["Z"]) {
+            i + 2
+        } else {
+            i + 1
+        }
+    } else if w.is(i, 2, &["SC"]) {
+        handle_sc(w, codes, i)
+    } else {
+        // French "Resnais" and "Artois".
+        if i == w.last() && w.is(i - 2, 2, &["AI", "OI"]) {
+            codes.push("", "S");
+        } else {
+            codes.both("S");
+        }
+        if w.is(i + 1, 1, &["S", "Z"]) {
+            i + 2
+        } else {
+            i + 1
+        }
+    }
+}
+
+fn handle_sc(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    if w.at(i + 2) == 'H' {
+        if w.is(i + 3, 2, &["OO", "ER", "EN", "UY", "ED", "EM"]) {
+            // Dutch "school" and "schooner"; "Schermerhorn" and "Schenker".
+            if w.is(i + 3, 2, &["ER", "EN"]) {
+                codes.push("X", "SK");
+            } else {
+                codes.both("SK");
+            }
+        } else if i == 0 && !w.is_vowel(3) && w.at(3) != 'W' {
+            codes.push("X", "S");
+        } else {
+            codes.both("X");
+        }
+    } else if w.is(i + 2, 1, &["I", "E", "Y"]) {
+        codes.both("S");
+    } else {
+        codes.both("SK");
+    }
+    i + 3
+}
+
+fn handle_t(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    if w.is(i, 4, &["TION"]) || w.is(i, 3, &["TIA", "TCH"]) {
+        codes.both("X");
+        i + 3
+    } else if w.is(i, 2, &["TH"]) || w.is(i, 3, &["TTH"]) {
+        // "Thomas" and "Thames", or Germanic spellings.
+        if w.is(i + 2, 2, &["OM", "AM"]) || w.is(0, 4, &["VAN ", "VON "]) || w.is(0, 3, &["SCH"]) {
+            codes.both("T");
+        } else {
+            codes.push("0", "T");
+        }
+        i + 2
+    } else {
+        codes.both("T");
+        if w.is(i + 1, 1, &["T", "D"]) {
+            i + 2
+        } else {
+            i + 1
+        }
+    }
+}
+
+fn handle_w(w: &Word, codes: &mut Codes, i: isize) -> isize {
+    if w.is(i, 2, &["WR"]) {
+        codes.both("R");
+        return i + 2;
+    }
+    if i == 0 && (w.is_vowel(i + 1) || w.is(i, 2, &["WH"])) {
+        // "Wasserman" should match "Vasserman".
+        if w.is_vowel(i + 1) {
+            codes.push("A", "F");
+        } else {
+            codes.both("A");
+        }
+    } else if (i == w.last() && w.is_vowel(i - 1))
+        || w.is(i - 1, 5, &["EWSKI", "EWSKY", "OWSKI", "OWSKY"])
+        || w.is(0, 3, &["SCH"])
+    {
+        // Polish "Filipowicz" and Germanic endings.
+        codes.push("", "F");
+    } else if w.is(i, 4, &["WICZ", "WITZ"]) {
+        codes.push("TS", "FX");
+        return i + 4;
+    }
+    i + 1
+}
@@ -0,0 +1,157 @@
+//! Lawrence Philips' original Metaphone.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use super::soundex::letters;
+
+fn is_vowel(word: &[u8], i: usize) -> bool {
+    matches!(word.get(i), Some(b'A' | b'E' | b'I' | b'O' | b'U'))
+}
+
+fn is_front_vowel(word: &[u8], i: usize) -> bool {
+    matches!(word.get(i), Some(b'E' | b'I' | b'Y'))
+}
+
+fn previous_is(word: &[u8], i: usize, letter: u8) -> bool {
+    i > 0 && word[i - 1] == letter
+}
+
+fn next_is(word: &[u8], i: usize, letter: u8) -> bool {
+    word.get(i + 1) == Some(&letter)
+}
+
+fn at(word: &[u8], i: usize, pattern: &[u8]) -> bool {
+    word[i..].starts_with(pattern)
+}
+
+/// Consonant skeleton of `s`, of any length. Only ASCII letters are
+/// considered; `0` stands for "th".
+pub(crate) fn encode(s: &str) -> String {
+    let mut word = letters(s);
+    match word.as_slice() {
+        [] => return String::new(),
+        [b'A', b'E', ..] | [b'G' | b'K' | b'P', b'N', ..] | [b'W', b'R', ..] => {
+            word.remove(0);
+        }
+        [b'W', b'H', ..] => {
+            word.remove(1);
+        }
+        [b'X', ..] => word[0] = b'S',
+        _ => {}
+    }
+    let word = word.as_slice();
+    let last = word.len() - 1;
+    let mut code = Vec::new();
+    let mut i = 0;
+    while i <= last {
+        let letter = word[i];
+        // Doubled letters sound once, except for "cc" as in "accident".
+        if letter != b'C' && previous_is(word, i, letter) {
+            i += 1;
+            continue;
+        }
+        match letter {
+            b'A' | b'E' | b'I' | b'O' | b'U' => {
+                if i == 0 {
+                    code.push(letter);
+                }
+            }
+            b'B' => {
+                if !(previous_is(word, i, b'M') && i == last) {
+                    code.push(b'B');
+                }
+            }
+            b'C' => {
+                if previous_is(word, i, b'S') && i < last && is_front_vowel(word, i + 1) {
+                    // "sci", "sce" and "scy" sound like "s".
+                } else if at(word, i, b"CIA") {
+                    code.push(b'X');
+                } else if is_front_vowel(word, i + 1) {
+                    code.push(b'S');
+                } else if previous_is(word, i, b'S') && next_is(word, i, b'H') {
+                    code.push(b'K');
+                } else if next_is(word, i, b'H') {
+                    code.push(if i == 0 && is_vowel(word, 2) { b'K' } else { b'X' });
+                } else {
+                    code.push(b'K');
+                }
+            }
+            b'D' => {
+                if next_is(word, i, b'G') && is_front_vowel(word, i + 2) {
+                    code.push(b'J');
+                    i += 2;
+                } else {
+                    code.push(b'T');
+                }
+            }
+            b'G' => {
+                let silent = (next_is(word, i, b'H') && (i + 1 == last || !is_vowel(word, i + 2)))
+                    || (i > 0 && (at(word, i, b"GN") || at(word, i, b"GNED")));
+                if !silent {
+                    let soft = is_front_vowel(word, i + 1) && !previous_is(word, i, b'G');
+                    code.push(if soft { b'J' } else { b'K' });
+                }
+            }
+            b'H' => {
+                let after_modifier = i > 0 && matches!(word[i - 1], b'C' | b'S' | b'P' | b'T' | b'G');
+                if i < last && !after_modifier && is_vowel(word, i + 1) {
+                    code.push(b'H');
+                }
+            }
+            b'K' => {
+                if !previous_is(word, i, b'C') {
+                    code.push(b'K');
+                }
+            }
+            b'P' => code.push(if next_is(word, i, b'H') { b'F' } else { b'P' }),
+            b'Q' => code.push(b'K'),
+            b'S' => {
+                let sh = at(word, i, b"SH") || at(word, i, b"SIO") || at(word, i, b"SIA");
+                code.push(if sh { b'X' } else { b'S' });
+            }
+            b'T' => {
+                if at(word, i, b"TIA") || at(word, i, b"TIO") {
+                    code.push(b'X');
+                } else if at(word, i, b"TCH") {
+                    // The "ch" carries the sound.
+                } else if at(word, i, b"TH") {
+                    code.push(b'0');
+                } else {
+                    code.push(b'T');
+                }
+            }
+            b'V' => code.push(b'F'),
+            b'W' | b'Y' => {
+                if is_vowel(word, i + 1) {
+                    code.push(letter);
+                }
+            }
+            b'X' => code.extend_from_slice(b"KS"),
+            b'Z' => code.push(b'S'),
+            _ => code.push(letter),
+        }
+        i += 1;
+    }
+    String::from_utf8(code).expect("ASCII code")
+}
+
+encoder! {
+    /// metaphone(s)
+    ///
+    /// Encodes a word with the original Metaphone rules for English
+    /// pronunciation. ``"0"`` stands for "th" and the code is not truncated.
+    ///
+    /// :param str s: string to encode
+    /// :return: code, or an empty string if s has no letters
+    /// :rtype: str
+    metaphone;
+    /// metaphone_parallel(strings)
+    ///
+    /// Like :py:func:`metaphone`, for a list of strings. Operates in parallel
+    /// over the list.
+    ///
+    /// :param list strings: strings to encode
+    /// :return: codes, in input order
+    /// :rtype: list
+}
@@ -0,0 +1,146 @@
+//! Phonetic encoders, which map names that sound alike to the same code.
+//!
+//! Unlike the edit distances they ignore spelling differences that do not
+//! change pronunciation, so they are used as a blocking key or a match
+//! predicate next to a similarity such as `jaro_winkler`.
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Defines the Python encoder `$name`, wrapping the module's `encode`, and
+/// its `_parallel` variant.
+macro_rules! encoder {
+    ($(#[$doc:meta])* $name:ident; $(#[$parallel_doc:meta])*) => {
+        paste::item! {
+            $(#[$doc])*
+            #[pyfunction]
+            pub(crate) fn $name(s: &str) -> String {
+                encode(s)
+            }
+
+            $(#[$parallel_doc])*
+            #[pyfunction]
+            pub(crate) fn [<$name _parallel>](py: Python, strings: Vec<&str>) -> Vec<String> {
+                py.allow_threads(|| strings.par_iter().map(|s| encode(s)).collect())
+            }
+        }
+    };
+}
+
+pub(crate) mod approx_beider_morse;
+pub(crate) mod caverphone;
+pub(crate) mod daitch_mokotoff;
+pub(crate) mod double_metaphone;
+pub(crate) mod metaphone;
+pub(crate) mod mra;
+pub(crate) mod nysiis;
+pub(crate) mod refined_soundex;
+pub(crate) mod soundex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Algorithm {
+    Soundex,
+    RefinedSoundex,
+    Metaphone,
+    DoubleMetaphone,
+    Nysiis,
+    Mra,
+    Caverphone,
+    DaitchMokotoff,
+    ApproxBeiderMorse,
+}
+
+impl Algorithm {
+    pub(crate) fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "soundex" => Ok(Algorithm::Soundex),
+            "refined_soundex" => Ok(Algorithm::RefinedSoundex),
+            "metaphone" => Ok(Algorithm::Metaphone),
+            "double_metaphone" => Ok(Algorithm::DoubleMetaphone),
+            "nysiis" => Ok(Algorithm::Nysiis),
+            "mra" => Ok(Algorithm::Mra),
+            "caverphone" => Ok(Algorithm::Caverphone),
+            "daitch_mokotoff" => Ok(Algorithm::DaitchMokotoff),
+            "approx_beider_morse" => Ok(Algorithm::ApproxBeiderMorse),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unknown phonetic algorithm: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Codes of `s`; strings without any letter have none.
+    pub(crate) fn codes(&self, s: &str) -> Vec<String> {
+        let codes = match self {
+            Algorithm::Soundex => vec![soundex::encode(s)],
+            Algorithm::RefinedSoundex => vec![refined_soundex::encode(s)],
+            Algorithm::Metaphone => vec![metaphone::encode(s)],
+            Algorithm::DoubleMetaphone => {
+                let (primary, alternate) = double_metaphone::encode(s, double_metaphone::MAX_LENGTH);
+                vec![primary, alternate]
+            }
+            Algorithm::Nysiis => vec![nysiis::encode(s, Some(nysiis::MAX_LENGTH))],
+            Algorithm::Mra => vec![mra::encode(s)],
+            Algorithm::Caverphone => vec![caverphone::encode(s)],
+            Algorithm::DaitchMokotoff => daitch_mokotoff::encode(s).into_iter().collect(),
+            Algorithm::ApproxBeiderMorse => approx_beider_morse::encode(s, None).into_iter().collect(),
+        };
+        codes.into_iter().filter(|code| !code.is_empty()).collect()
+    }
+
+    /// Whether `a` and `b` share a code, or for MRA pass its comparison rule.
+    pub(crate) fn matches(&self, a: &str, b: &str) -> bool {
+        if *self == Algorithm::Mra {
+            return mra::compare(a, b);
+        }
+        let b = self.codes(b);
+        self.codes(a).iter().any(|code| b.contains(code))
+    }
+}
+
+/// phonetic_match(a, b, algorithm="double_metaphone")
+///
+/// Tells whether two strings sound alike under ``algorithm``, one of
+/// ``"soundex"``, ``"refined_soundex"``, ``"metaphone"``,
+/// ``"double_metaphone"``, ``"nysiis"``, ``"mra"``, ``"caverphone"``,
+/// ``"daitch_mokotoff"`` or ``"approx_beider_morse"``. Encoders yielding
+/// several codes match when the code sets of ``a`` and ``b`` overlap, and MRA
+/// uses :py:func:`mra_compare`. Strings without letters never match. Combine
+/// it with a similarity to rank the matches, e.g.
+/// ``phonetic_match(a, b) and jaro_winkler(a, b) > 0.8``.
+///
+/// :param str a: base string
+/// :param str b: string to compare
+/// :param str algorithm: phonetic encoder
+/// :return: whether the codes match
+/// :rtype: bool
+/// :raises ValueError: if the algorithm is unknown
+#[pyfunction]
+#[pyo3(signature = (a, b, algorithm="double_metaphone"))]
+pub(crate) fn phonetic_match(a: &str, b: &str, algorithm: &str) -> PyResult<bool> {
+    Ok(Algorithm::parse(algorithm)?.matches(a, b))
+}
+
+/// phonetic_match_parallel(a, b, algorithm="double_metaphone")
+///
+/// Like :py:func:`phonetic_match`, pairwise over two lists of strings.
+/// Operates in parallel over the pairs.
+///
+/// :param list a: base strings
+/// :param list b: strings to compare
+/// :param str algorithm: phonetic encoder
+/// :return: whether the codes of each pair match
+/// :rtype: list
+/// :raises ValueError: if the algorithm is unknown
+#[pyfunction]
+#[pyo3(signature = (a, b, algorithm="double_metaphone"))]
+pub(crate) fn phonetic_match_parallel(py: Python, a: Vec<&str>, b: Vec<&str>, algorithm: &str) -> PyResult<Vec<bool>> {
+    let algorithm = Algorithm::parse(algorithm)?;
+    Ok(py.allow_threads(|| {
+        (a, b)
+            .into_par_iter()
+            .map(|(a, b)| algorithm.matches(a, b))
+            .collect()
+    }))
+}
@@ -0,0 +1,124 @@
+//! Western Airlines' Match Rating Approach.
+//!
+//! Names are reduced to a codex of at most six letters, and two codexes are
+//! compared by how many letters are left once the ones they share, read from
+//! the left and then from the right, are struck out.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use super::soundex::letters;
+
+fn is_vowel(letter: u8) -> bool {
+    matches!(letter, b'A' | b'E' | b'I' | b'O' | b'U')
+}
+
+/// Codex of `s`: its letters without the vowels after the first letter and
+/// without doubled consonants, keeping the first and last three of them.
+/// Only ASCII letters are considered.
+pub(crate) fn encode(s: &str) -> String {
+    let letters = letters(s);
+    let mut codex: Vec<u8> = letters
+        .iter()
+        .enumerate()
+        .filter(|&(i, &letter)| i == 0 || !is_vowel(letter))
+        .map(|(_, &letter)| letter)
+        .collect();
+    codex.dedup_by(|b, a| a == b && !is_vowel(*a));
+    if codex.len() > 6 {
+        codex.drain(3..codex.len() - 3);
+    }
+    String::from_utf8(codex).expect("ASCII code")
+}
+
+/// Similarity rating two codexes must reach, by their combined length.
+fn minimum_rating(length: usize) -> usize {
+    match length {
+        0..=4 => 5,
+        5..=7 => 4,
+        8..=11 => 3,
+        12 => 2,
+        _ => 1,
+    }
+}
+
+/// Whether `a` and `b` are the same name under the MRA comparison rule. Names
+/// whose codexes differ in length by three or more are never compared, and
+/// do not match.
+pub(crate) fn compare(a: &str, b: &str) -> bool {
+    let (a, b) = (encode(a).into_bytes(), encode(b).into_bytes());
+    if a.is_empty() || b.is_empty() || a.len().abs_diff(b.len()) >= 3 {
+        return false;
+    }
+    let (mut left, mut right) = (a.clone(), b.clone());
+    for i in 0..a.len().min(b.len()) {
+        if a[i] == b[i] {
+            left[i] = b' ';
+            right[i] = b' ';
+        }
+        let (i_a, i_b) = (a.len() - 1 - i, b.len() - 1 - i);
+        if a[i_a] == b[i_b] {
+            left[i_a] = b' ';
+            right[i_b] = b' ';
+        }
+    }
+    let unmatched = |codex: &[u8]| codex.iter().filter(|&&c| c != b' ').count();
+    let longest = unmatched(&left).max(unmatched(&right));
+    6usize.saturating_sub(longest) >= minimum_rating(a.len() + b.len())
+}
+
+/// mra(s)
+///
+/// Encodes a name as its Match Rating Approach codex, at most six letters
+/// long.
+///
+/// :param str s: string to encode
+/// :return: codex, or an empty string if s has no letters
+/// :rtype: str
+#[pyfunction]
+pub(crate) fn mra(s: &str) -> String {
+    encode(s)
+}
+
+/// mra_parallel(strings)
+///
+/// Like :py:func:`mra`, for a list of strings. Operates in parallel over the
+/// list.
+///
+/// :param list strings: strings to encode
+/// :return: codexes, in input order
+/// :rtype: list
+#[pyfunction]
+pub(crate) fn mra_parallel(py: Python, strings: Vec<&str>) -> Vec<String> {
+    py.allow_threads(|| strings.par_iter().map(|s| encode(s)).collect())
+}
+
+/// mra_compare(a, b)
+///
+/// Compares two names with the Match Rating Approach rule: their codexes
+/// match when few enough letters remain after striking out the shared ones,
+/// with a threshold that depends on the codex lengths. Codexes whose lengths
+/// differ by three or more never match.
+///
+/// :param str a: base string
+/// :param str b: string to compare
+/// :return: whether the names match
+/// :rtype: bool
+#[pyfunction]
+pub(crate) fn mra_compare(a: &str, b: &str) -> bool {
+    compare(a, b)
+}
+
+/// mra_compare_parallel(a, b)
+///
+/// Like :py:func:`mra_compare`, pairwise over two lists of strings. Operates
+/// in parallel over the pairs.
+///
+/// :param list a: base strings
+/// :param list b: strings to compare
+/// :return: whether each pair matches
+/// :rtype: list
+#[pyfunction]
+pub(crate) fn mra_compare_parallel(py: Python, a: Vec<&str>, b: Vec<&str>) -> Vec<bool> {
+    py.allow_threads(|| (a, b).into_par_iter().map(|(a, b)| compare(a, b)).collect())
+}
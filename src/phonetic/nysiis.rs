@@ -0,0 +1,123 @@
+//! New York State Identification and Intelligence System name code.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use super::soundex::letters;
+
+/// Length the original system truncates codes to.
+pub(crate) const MAX_LENGTH: usize = 6;
+
+fn is_vowel(letter: u8) -> bool {
+    matches!(letter, b'A' | b'E' | b'I' | b'O' | b'U')
+}
+
+fn replace_prefix(word: &mut Vec<u8>, from: &[u8], to: &[u8]) -> bool {
+    if word.starts_with(from) {
+        word.splice(..from.len(), to.iter().copied());
+        return true;
+    }
+    false
+}
+
+fn replace_suffix(word: &mut Vec<u8>, from: &[u8], to: &[u8]) -> bool {
+    if word.ends_with(from) {
+        let start = word.len() - from.len();
+        word.splice(start.., to.iter().copied());
+        return true;
+    }
+    false
+}
+
+/// NYSIIS code of `s`, truncated to `max_length` if given. Only ASCII
+/// letters are considered.
+pub(crate) fn encode(s: &str, max_length: Option<usize>) -> String {
+    let mut word = letters(s);
+    if word.is_empty() {
+        return String::new();
+    }
+    let _ = replace_prefix(&mut word, b"MAC", b"MCC")
+        || replace_prefix(&mut word, b"KN", b"NN")
+        || replace_prefix(&mut word, b"K", b"C")
+        || replace_prefix(&mut word, b"PH", b"FF")
+        || replace_prefix(&mut word, b"PF", b"FF")
+        || replace_prefix(&mut word, b"SCH", b"SSS");
+    let _ = replace_suffix(&mut word, b"EE", b"Y")
+        || replace_suffix(&mut word, b"IE", b"Y")
+        || [&b"DT"[..], b"RT", b"RD", b"NT", b"ND"]
+            .iter()
+            .any(|ending| replace_suffix(&mut word, ending, b"D"));
+
+    // Letters are rewritten in place so that later ones see the result, as
+    // in "KN" becoming "NN" and then a single "N".
+    let mut key = vec![word[0]];
+    for i in 1..word.len() {
+        let previous = word[i - 1];
+        let next = word.get(i + 1).copied().unwrap_or(b' ');
+        let after = word.get(i + 2).copied().unwrap_or(b' ');
+        let replacement: &[u8] = match word[i] {
+            b'E' if next == b'V' => b"AF",
+            letter if is_vowel(letter) => b"A",
+            b'Q' => b"G",
+            b'Z' => b"S",
+            b'M' => b"N",
+            b'K' if next == b'N' => b"NN",
+            b'K' => b"C",
+            b'S' if next == b'C' && after == b'H' => b"SSS",
+            b'P' if next == b'H' => b"FF",
+            b'H' if !is_vowel(previous) || !is_vowel(next) => std::slice::from_ref(&word[i - 1]),
+            b'W' if is_vowel(previous) => std::slice::from_ref(&word[i - 1]),
+            _ => std::slice::from_ref(&word[i]),
+        };
+        let replacement = replacement.to_vec();
+        let end = (i + replacement.len()).min(word.len());
+        word[i..end].copy_from_slice(&replacement[..end - i]);
+        if word[i] != previous {
+            key.push(word[i]);
+        }
+    }
+
+    if key.len() > 1 && key.last() == Some(&b'S') {
+        key.pop();
+    }
+    if key.ends_with(b"AY") && key.len() > 2 {
+        key.remove(key.len() - 2);
+    }
+    if key.len() > 1 && key.last() == Some(&b'A') {
+        key.pop();
+    }
+    if let Some(max_length) = max_length {
+        key.truncate(max_length);
+    }
+    String::from_utf8(key).expect("ASCII code")
+}
+
+/// nysiis(s, max_length=6)
+///
+/// Encodes a name with NYSIIS, which keeps vowel positions and handles
+/// common name prefixes and suffixes better than Soundex.
+///
+/// :param str s: string to encode
+/// :param int max_length: truncate the code to this length, or None to keep it whole
+/// :return: code, or an empty string if s has no letters
+/// :rtype: str
+#[pyfunction]
+#[pyo3(signature = (s, max_length=MAX_LENGTH))]
+pub(crate) fn nysiis(s: &str, max_length: Option<usize>) -> String {
+    encode(s, max_length)
+}
+
+/// nysiis_parallel(strings, max_length=6)
+///
+/// Like :py:func:`nysiis`, for a list of strings. Operates in parallel over
+/// the list.
+///
+/// :param list strings: strings to encode
+/// :param int max_length: truncate the codes to this length, or None to keep them whole
+/// :return: codes, in input order
+/// :rtype: list
+#[pyfunction]
+#[pyo3(signature = (strings, max_length=MAX_LENGTH))]
+pub(crate) fn nysiis_parallel(py: Python, strings: Vec<&str>, max_length: Option<usize>) -> Vec<String> {
+    py.allow_threads(|| strings.par_iter().map(|s| encode(s, max_length)).collect())
+}
@@ -0,0 +1,49 @@
+//! Refined Soundex, which splits consonants into finer groups than Soundex.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use super::soundex::letters;
+
+/// Refined Soundex digits for `A` to `Z`.
+const REFINED: &[u8; 26] = b"01360240043788015936020505";
+
+/// Unpadded code of any length: the first letter followed by the digit of
+/// every letter, including the first, with repeated digits collapsed.
+pub(crate) fn encode(s: &str) -> String {
+    let letters = letters(s);
+    let first = match letters.first() {
+        Some(&first) => first,
+        None => return String::new(),
+    };
+    let mut code = vec![first];
+    let mut last = None;
+    for &letter in &letters {
+        let current = REFINED[(letter - b'A') as usize];
+        if Some(current) != last {
+            code.push(current);
+        }
+        last = Some(current);
+    }
+    String::from_utf8(code).expect("ASCII code")
+}
+
+encoder! {
+    /// refined_soundex(s)
+    ///
+    /// Encodes a name with Refined Soundex, which splits consonants into more
+    /// groups than Soundex and does not truncate the code.
+    ///
+    /// :param str s: string to encode
+    /// :return: code, or an empty string if s has no letters
+    /// :rtype: str
+    refined_soundex;
+    /// refined_soundex_parallel(strings)
+    ///
+    /// Like :py:func:`refined_soundex`, for a list of strings. Operates in
+    /// parallel over the list.
+    ///
+    /// :param list strings: strings to encode
+    /// :return: codes, in input order
+    /// :rtype: list
+}
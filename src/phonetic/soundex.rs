@@ -0,0 +1,75 @@
+//! American Soundex.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Letters of `s` in upper case, dropping everything else.
+pub(crate) fn letters(s: &str) -> Vec<u8> {
+    s.bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase())
+        .collect()
+}
+
+/// Soundex digit of an upper-case letter; vowels map to `0`, and `H` and `W`
+/// to `None` because they do not separate letters with the same digit.
+fn digit(letter: u8) -> Option<u8> {
+    match letter {
+        b'B' | b'F' | b'P' | b'V' => Some(b'1'),
+        b'C' | b'G' | b'J' | b'K' | b'Q' | b'S' | b'X' | b'Z' => Some(b'2'),
+        b'D' | b'T' => Some(b'3'),
+        b'L' => Some(b'4'),
+        b'M' | b'N' => Some(b'5'),
+        b'R' => Some(b'6'),
+        b'H' | b'W' => None,
+        _ => Some(b'0'),
+    }
+}
+
+/// Four-character code: the first letter followed by the digits of the
+/// next consonants, skipping repeats and padding with zeros.
+pub(crate) fn encode(s: &str) -> String {
+    let letters = letters(s);
+    let first = match letters.first() {
+        Some(&first) => first,
+        None => return String::new(),
+    };
+    let mut code = vec![first];
+    let mut last = digit(first);
+    for &letter in &letters[1..] {
+        let current = match digit(letter) {
+            Some(current) => current,
+            None => continue,
+        };
+        if current != b'0' && Some(current) != last {
+            code.push(current);
+            if code.len() == 4 {
+                break;
+            }
+        }
+        last = Some(current);
+    }
+    code.resize(4, b'0');
+    String::from_utf8(code).expect("ASCII code")
+}
+
+encoder! {
+    /// soundex(s)
+    ///
+    /// Encodes a name as its American Soundex code: the first letter followed
+    /// by three digits, e.g. ``"R163"`` for both "Robert" and "Rupert". Only
+    /// ASCII letters are considered.
+    ///
+    /// :param str s: string to encode
+    /// :return: code, or an empty string if s has no letters
+    /// :rtype: str
+    soundex;
+    /// soundex_parallel(strings)
+    ///
+    /// Like :py:func:`soundex`, for a list of strings. Operates in parallel
+    /// over the list.
+    ///
+    /// :param list strings: strings to encode
+    /// :return: codes, in input order
+    /// :rtype: list
+}
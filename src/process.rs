@@ -0,0 +1,309 @@
+//! Preprocessing applied to the strings before they are scored, so that
+//! "Café" written precomposed or with a combining accent, or "STRASSE" and
+//! "straße", compare equal. Processing that lives in Python can be passed
+//! as a callable instead.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::distance::Text;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Form {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl Form {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "NFC" => Ok(Form::Nfc),
+            "NFD" => Ok(Form::Nfd),
+            "NFKC" => Ok(Form::Nfkc),
+            "NFKD" => Ok(Form::Nfkd),
+            _ => Err(exceptions::PyValueError::new_err(format!(
+                "Unknown normalization form: {}",
+                name
+            ))),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Form::Nfc => "NFC",
+            Form::Nfd => "NFD",
+            Form::Nfkc => "NFKC",
+            Form::Nfkd => "NFKD",
+        }
+    }
+
+    fn compatibility(&self) -> bool {
+        matches!(self, Form::Nfkc | Form::Nfkd)
+    }
+
+    fn normalize(&self, s: &str) -> String {
+        match self {
+            Form::Nfc => s.nfc().collect(),
+            Form::Nfd => s.nfd().collect(),
+            Form::Nfkc => s.nfkc().collect(),
+            Form::Nfkd => s.nfkd().collect(),
+        }
+    }
+}
+
+/// Processor(normalization="NFC", casefold=False, strip_diacritics=False, collapse_whitespace=False)
+///
+/// String preprocessing run in Rust before scoring, passed to the metrics as
+/// ``processor``. Diacritics are stripped first, then the string is case
+/// folded, normalized and its whitespace collapsed; normalizing after case
+/// folding recomposes what folding decomposes. It can also be called on a
+/// string to see what the metrics compare.
+///
+/// :param str normalization: Unicode normalization form, "NFC", "NFD", "NFKC"
+///     or "NFKD", None for none
+/// :param bool casefold: apply full Unicode case folding, so that "ß" and "SS"
+///     compare equal
+/// :param bool strip_diacritics: drop the combining marks of decomposed
+///     characters, so that "é" becomes "e"; letters such as "ø" that do not
+///     decompose are kept
+/// :param bool collapse_whitespace: trim the strings and replace runs of
+///     whitespace with a single space
+/// :raises ValueError: if the normalization form is unknown
+#[pyclass(module = "xdistances")]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Processor {
+    normalization: Option<Form>,
+    casefold: bool,
+    strip_diacritics: bool,
+    collapse_whitespace: bool,
+}
+
+impl Processor {
+    pub(crate) fn process(&self, s: &str) -> String {
+        let mut s = if self.strip_diacritics {
+            let compatibility = self.normalization.is_some_and(|form| form.compatibility());
+            let decomposed: String = if compatibility { s.nfkd().collect() } else { s.nfd().collect() };
+            decomposed.chars().filter(|&c| !is_combining_mark(c)).collect()
+        } else {
+            s.to_owned()
+        };
+        if self.casefold {
+            s = caseless::default_case_fold_str(&s);
+        }
+        if let Some(form) = self.normalization {
+            s = form.normalize(&s);
+        }
+        if self.collapse_whitespace {
+            s = s.split_whitespace().collect::<Vec<&str>>().join(" ");
+        }
+        s
+    }
+}
+
+/// A ``processor`` argument: a `Processor`, or a Python callable taking and
+/// returning a str or bytes, which is called with the GIL held.
+#[derive(Clone, Copy)]
+pub(crate) enum Preprocessor<'py> {
+    Rust(&'py PyCell<Processor>),
+    Python(&'py PyAny),
+}
+
+impl<'py> FromPyObject<'py> for Preprocessor<'py> {
+    fn extract(ob: &'py PyAny) -> PyResult<Self> {
+        if let Ok(processor) = ob.downcast::<PyCell<Processor>>() {
+            return Ok(Preprocessor::Rust(processor));
+        }
+        if ob.is_callable() {
+            return Ok(Preprocessor::Python(ob));
+        }
+        Err(exceptions::PyTypeError::new_err(
+            "processor must be a Processor or a callable",
+        ))
+    }
+}
+
+/// `text` run through the Python `callable`.
+fn call(callable: &PyAny, text: &Text) -> PyResult<Text<'static>> {
+    let processed = callable.call1((text.to_object(callable.py()),))?;
+    Ok(processed.extract::<Text>()?.into_owned())
+}
+
+/// `text` run through `processor`, if any. A `Processor` leaves bytes as
+/// they are.
+pub(crate) fn apply<'a>(processor: &Option<Preprocessor>, text: &'a Text) -> PyResult<Text<'a>> {
+    match (processor, text) {
+        (Some(Preprocessor::Rust(processor)), Text::Str(s)) => {
+            Ok(Text::Str(Cow::Owned(processor.borrow().process(s))))
+        }
+        (Some(Preprocessor::Python(callable)), _) => call(callable, text),
+        _ => Ok(text.borrowed()),
+    }
+}
+
+/// Both lists of texts run through `processor`, if any: a `Processor` in
+/// parallel, a callable serially and once per distinct text of either.
+pub(crate) fn apply_pair<'a, 'b>(
+    processor: &Option<Preprocessor>,
+    a: &'a [Text],
+    b: &'b [Text],
+) -> PyResult<(Vec<Text<'a>>, Vec<Text<'b>>)> {
+    match processor {
+        Some(Preprocessor::Python(callable)) => {
+            let mut cache = HashMap::new();
+            let a = call_all(callable, a, &mut cache)?;
+            let b = call_all(callable, b, &mut cache)?;
+            Ok((a, b))
+        }
+        _ => Ok((process_all(processor, a), process_all(processor, b))),
+    }
+}
+
+/// Like `apply_pair`, for callers working on str: the processed strings,
+/// with a processor returning bytes rejected.
+pub(crate) fn apply_str_pair<A: AsRef<str>, B: AsRef<str>>(
+    processor: &Option<Preprocessor>,
+    a: &[A],
+    b: &[B],
+) -> PyResult<(Vec<String>, Vec<String>)> {
+    let a: Vec<Text> = a.iter().map(|s| s.as_ref().into()).collect();
+    let b: Vec<Text> = b.iter().map(|s| s.as_ref().into()).collect();
+    let (a, b) = apply_pair(processor, &a, &b)?;
+    Ok((into_strings(a)?, into_strings(b)?))
+}
+
+/// Like `apply_str_pair`, for a single list.
+pub(crate) fn apply_strs<S: AsRef<str>>(processor: &Option<Preprocessor>, strings: &[S]) -> PyResult<Vec<String>> {
+    Ok(apply_str_pair(processor, strings, &[] as &[&str])?.0)
+}
+
+fn into_strings(texts: Vec<Text>) -> PyResult<Vec<String>> {
+    texts
+        .into_iter()
+        .map(|text| match text {
+            Text::Str(s) => Ok(s.into_owned()),
+            Text::Bytes(_) => Err(exceptions::PyTypeError::new_err("processor must return str")),
+        })
+        .collect()
+}
+
+fn process_all<'a>(processor: &Option<Preprocessor>, texts: &'a [Text]) -> Vec<Text<'a>> {
+    match processor {
+        Some(Preprocessor::Rust(processor)) => {
+            let processor = processor.borrow().clone();
+            texts
+                .par_iter()
+                .map(|text| match text {
+                    Text::Str(s) => Text::Str(Cow::Owned(processor.process(s))),
+                    Text::Bytes(_) => text.borrowed(),
+                })
+                .collect()
+        }
+        _ => texts.iter().map(Text::borrowed).collect(),
+    }
+}
+
+fn call_all<'c>(
+    callable: &PyAny,
+    texts: &'c [Text],
+    cache: &mut HashMap<&'c Text<'c>, Text<'static>>,
+) -> PyResult<Vec<Text<'static>>> {
+    texts
+        .iter()
+        .map(|text| {
+            if let Some(processed) = cache.get(text) {
+                return Ok(processed.clone());
+            }
+            let processed = call(callable, text)?;
+            cache.insert(text, processed.clone());
+            Ok(processed)
+        })
+        .collect()
+}
+
+#[pymethods]
+impl Processor {
+    #[new]
+    #[pyo3(signature = (normalization="NFC", casefold=false, strip_diacritics=false, collapse_whitespace=false))]
+    fn py_new(
+        normalization: Option<&str>,
+        casefold: bool,
+        strip_diacritics: bool,
+        collapse_whitespace: bool,
+    ) -> PyResult<Self> {
+        Ok(Processor {
+            normalization: normalization.map(Form::parse).transpose()?,
+            casefold,
+            strip_diacritics,
+            collapse_whitespace,
+        })
+    }
+
+    fn __call__(&self, s: &str) -> String {
+        self.process(s)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Processor(normalization={}, casefold={}, strip_diacritics={}, collapse_whitespace={})",
+            self.normalization.map_or("None".to_owned(), |form| format!("'{}'", form.name())),
+            if self.casefold { "True" } else { "False" },
+            if self.strip_diacritics { "True" } else { "False" },
+            if self.collapse_whitespace { "True" } else { "False" },
+        )
+    }
+
+    fn __getstate__(&self) -> (Option<&'static str>, bool, bool, bool) {
+        (
+            self.normalization.map(|form| form.name()),
+            self.casefold,
+            self.strip_diacritics,
+            self.collapse_whitespace,
+        )
+    }
+
+    fn __setstate__(&mut self, state: (Option<&str>, bool, bool, bool)) -> PyResult<()> {
+        *self = Processor::from_state(state)?;
+        Ok(())
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, (PyObject,))> {
+        let py = slf.py();
+        let state = slf.borrow().__getstate__().into_py(py);
+        Ok((slf.get_type().getattr("_from_state")?.into(), (state,)))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "_from_state")]
+    fn from_state(state: (Option<&str>, bool, bool, bool)) -> PyResult<Self> {
+        let (normalization, casefold, strip_diacritics, collapse_whitespace) = state;
+        Processor::py_new(normalization, casefold, strip_diacritics, collapse_whitespace)
+    }
+
+    #[getter]
+    fn normalization(&self) -> Option<&'static str> {
+        self.normalization.map(|form| form.name())
+    }
+
+    #[getter]
+    fn casefold(&self) -> bool {
+        self.casefold
+    }
+
+    #[getter]
+    fn strip_diacritics(&self) -> bool {
+        self.strip_diacritics
+    }
+
+    #[getter]
+    fn collapse_whitespace(&self) -> bool {
+        self.collapse_whitespace
+    }
+}
@@ -0,0 +1,392 @@
+//! Edit distances and Jaro over sequences of any hashable Python objects:
+//! lists of words, tuples of token IDs, and so on.
+//!
+//! Elements are interned to integers through a dict shared by all the
+//! sequences of a call, so that they compare by Python equality, and the
+//! metrics then run on the integers without the GIL.
+
+use std::cmp::Ordering;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+
+use crate::distance::generic;
+
+struct Interner<'py> {
+    ids: &'py PyDict,
+}
+
+impl<'py> Interner<'py> {
+    fn new(py: Python<'py>) -> Self {
+        Interner { ids: PyDict::new(py) }
+    }
+
+    /// IDs of the elements of `sequence`, equal for equal elements.
+    fn intern(&self, sequence: &'py PyAny) -> PyResult<Vec<u32>> {
+        sequence
+            .iter()?
+            .map(|element| {
+                let element = element?;
+                match self.ids.get_item_with_error(element)? {
+                    Some(id) => id.extract(),
+                    None => {
+                        let id = self.ids.len() as u32;
+                        self.ids.set_item(element, id)?;
+                        Ok(id)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn intern_all(&self, sequences: &[&'py PyAny]) -> PyResult<Vec<Vec<u32>>> {
+        sequences.iter().map(|sequence| self.intern(sequence)).collect()
+    }
+}
+
+/// Defines `generic_$name` over two sequences, wrapping `generic::$name`,
+/// and its `_parallel`, `_max_similarity` and `_min_similarity` variants.
+macro_rules! sequence_metric {
+    (
+        $(#[$doc:meta])* $name:ident -> $type:ty;
+        $(#[$parallel_doc:meta])*;
+        $(#[$max_doc:meta])*;
+        $(#[$min_doc:meta])*
+    ) => {
+        paste::item! {
+            $(#[$doc])*
+            #[pyfunction]
+            pub(crate) fn [<generic_ $name>](py: Python, a: &PyAny, b: &PyAny) -> PyResult<$type> {
+                let interner = Interner::new(py);
+                let (a, b) = (interner.intern(a)?, interner.intern(b)?);
+                Ok(py.allow_threads(|| generic::$name(&a, &b)))
+            }
+
+            $(#[$parallel_doc])*
+            #[pyfunction]
+            pub(crate) fn [<generic_ $name _parallel>](py: Python, left: Vec<&PyAny>, right: Vec<&PyAny>) -> PyResult<Vec<$type>> {
+                let interner = Interner::new(py);
+                let (left, right) = (interner.intern_all(&left)?, interner.intern_all(&right)?);
+                Ok(py.allow_threads(|| {
+                    (left, right)
+                        .into_par_iter()
+                        .map(|(x, y)| generic::$name(&x, &y))
+                        .collect()
+                }))
+            }
+
+            $(#[$max_doc])*
+            #[pyfunction]
+            pub(crate) fn [<generic_ $name _max_similarity>](py: Python, targets: Vec<&PyAny>, source_sequences: Vec<&PyAny>) -> PyResult<Vec<$type>> {
+                let interner = Interner::new(py);
+                let targets = interner.intern_all(&targets)?;
+                let source_sequences = interner.intern_all(&source_sequences)?;
+                Ok(py.allow_threads(|| {
+                    targets.par_iter()
+                        .map(|target| {
+                            source_sequences.par_iter()
+                                .map(|source| generic::$name(target, source))
+                                .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                                .unwrap_or_default()
+                        })
+                        .collect()
+                }))
+            }
+
+            $(#[$min_doc])*
+            #[pyfunction]
+            pub(crate) fn [<generic_ $name _min_similarity>](py: Python, targets: Vec<&PyAny>, source_sequences: Vec<&PyAny>) -> PyResult<Vec<$type>> {
+                let interner = Interner::new(py);
+                let targets = interner.intern_all(&targets)?;
+                let source_sequences = interner.intern_all(&source_sequences)?;
+                Ok(py.allow_threads(|| {
+                    targets.par_iter()
+                        .map(|target| {
+                            source_sequences.par_iter()
+                                .map(|source| generic::$name(target, source))
+                                .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                                .unwrap_or_default()
+                        })
+                        .collect()
+                }))
+            }
+        }
+    };
+}
+
+sequence_metric! {
+    /// generic_levenshtein(a, b)
+    ///
+    /// Calculates the minimum number of insertions, deletions, and substitutions
+    /// required to change one sequence into the other.
+    ///
+    /// :param iterable a: base sequence of hashable elements
+    /// :param iterable b: sequence to compare
+    /// :return: distance
+    /// :rtype: int
+    /// :raises TypeError: if an element is not hashable
+    levenshtein -> usize;
+    /// generic_levenshtein_parallel(left, right)
+    ///
+    /// Like :py:func:`generic_levenshtein`, pairwise over two lists of
+    /// sequences. Operates in parallel over the pairs.
+    ///
+    /// :param list left: base sequences
+    /// :param list right: sequences to compare
+    /// :return: distance of each pair
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_levenshtein_max_similarity(targets, source_sequences)
+    ///
+    /// Largest :py:func:`generic_levenshtein` between each target and the
+    /// source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: largest distance per target, 0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_levenshtein_min_similarity(targets, source_sequences)
+    ///
+    /// Smallest :py:func:`generic_levenshtein` between each target and the
+    /// source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: smallest distance per target, 0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+}
+
+sequence_metric! {
+    /// generic_normalized_levenshtein(a, b)
+    ///
+    /// Calculates a normalized score of the Levenshtein algorithm between 0.0 and
+    /// 1.0 (inclusive), where 1.0 means the sequences are the same.
+    ///
+    /// :param iterable a: base sequence of hashable elements
+    /// :param iterable b: sequence to compare
+    /// :return: similarity
+    /// :rtype: float
+    /// :raises TypeError: if an element is not hashable
+    normalized_levenshtein -> f64;
+    /// generic_normalized_levenshtein_parallel(left, right)
+    ///
+    /// Like :py:func:`generic_normalized_levenshtein`, pairwise over two lists
+    /// of sequences. Operates in parallel over the pairs.
+    ///
+    /// :param list left: base sequences
+    /// :param list right: sequences to compare
+    /// :return: similarity of each pair
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_normalized_levenshtein_max_similarity(targets, source_sequences)
+    ///
+    /// Largest :py:func:`generic_normalized_levenshtein` between each target
+    /// and the source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: largest similarity per target, 0.0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_normalized_levenshtein_min_similarity(targets, source_sequences)
+    ///
+    /// Smallest :py:func:`generic_normalized_levenshtein` between each target
+    /// and the source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: smallest similarity per target, 0.0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+}
+
+sequence_metric! {
+    /// generic_osa_distance(a, b)
+    ///
+    /// Like Levenshtein but allows for adjacent transpositions. Each subsequence
+    /// can only be edited once.
+    ///
+    /// :param iterable a: base sequence of hashable elements
+    /// :param iterable b: sequence to compare
+    /// :return: distance
+    /// :rtype: int
+    /// :raises TypeError: if an element is not hashable
+    osa_distance -> usize;
+    /// generic_osa_distance_parallel(left, right)
+    ///
+    /// Like :py:func:`generic_osa_distance`, pairwise over two lists of
+    /// sequences. Operates in parallel over the pairs.
+    ///
+    /// :param list left: base sequences
+    /// :param list right: sequences to compare
+    /// :return: distance of each pair
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_osa_distance_max_similarity(targets, source_sequences)
+    ///
+    /// Largest :py:func:`generic_osa_distance` between each target and the
+    /// source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: largest distance per target, 0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_osa_distance_min_similarity(targets, source_sequences)
+    ///
+    /// Smallest :py:func:`generic_osa_distance` between each target and the
+    /// source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: smallest distance per target, 0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+}
+
+sequence_metric! {
+    /// generic_damerau_levenshtein(a, b)
+    ///
+    /// Like optimal string alignment, but subsequences can be edited an unlimited
+    /// number of times, and the triangle inequality holds.
+    ///
+    /// :param iterable a: base sequence of hashable elements
+    /// :param iterable b: sequence to compare
+    /// :return: distance
+    /// :rtype: int
+    /// :raises TypeError: if an element is not hashable
+    damerau_levenshtein -> usize;
+    /// generic_damerau_levenshtein_parallel(left, right)
+    ///
+    /// Like :py:func:`generic_damerau_levenshtein`, pairwise over two lists of
+    /// sequences. Operates in parallel over the pairs.
+    ///
+    /// :param list left: base sequences
+    /// :param list right: sequences to compare
+    /// :return: distance of each pair
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_damerau_levenshtein_max_similarity(targets, source_sequences)
+    ///
+    /// Largest :py:func:`generic_damerau_levenshtein` between each target and
+    /// the source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: largest distance per target, 0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_damerau_levenshtein_min_similarity(targets, source_sequences)
+    ///
+    /// Smallest :py:func:`generic_damerau_levenshtein` between each target and
+    /// the source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: smallest distance per target, 0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+}
+
+sequence_metric! {
+    /// generic_normalized_damerau_levenshtein(a, b)
+    ///
+    /// Calculates a normalized score of the Damerau–Levenshtein algorithm between
+    /// 0.0 and 1.0 (inclusive), where 1.0 means the sequences are the same.
+    ///
+    /// :param iterable a: base sequence of hashable elements
+    /// :param iterable b: sequence to compare
+    /// :return: similarity
+    /// :rtype: float
+    /// :raises TypeError: if an element is not hashable
+    normalized_damerau_levenshtein -> f64;
+    /// generic_normalized_damerau_levenshtein_parallel(left, right)
+    ///
+    /// Like :py:func:`generic_normalized_damerau_levenshtein`, pairwise over
+    /// two lists of sequences. Operates in parallel over the pairs.
+    ///
+    /// :param list left: base sequences
+    /// :param list right: sequences to compare
+    /// :return: similarity of each pair
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_normalized_damerau_levenshtein_max_similarity(targets, source_sequences)
+    ///
+    /// Largest :py:func:`generic_normalized_damerau_levenshtein` between each
+    /// target and the source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: largest similarity per target, 0.0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_normalized_damerau_levenshtein_min_similarity(targets, source_sequences)
+    ///
+    /// Smallest :py:func:`generic_normalized_damerau_levenshtein` between each
+    /// target and the source sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: smallest similarity per target, 0.0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+}
+
+sequence_metric! {
+    /// generic_jaro(a, b)
+    ///
+    /// Calculates the Jaro similarity between two sequences. The returned value
+    /// is between 0.0 and 1.0 (higher value means more similar).
+    ///
+    /// :param iterable a: base sequence of hashable elements
+    /// :param iterable b: sequence to compare
+    /// :return: similarity
+    /// :rtype: float
+    /// :raises TypeError: if an element is not hashable
+    jaro -> f64;
+    /// generic_jaro_parallel(left, right)
+    ///
+    /// Like :py:func:`generic_jaro`, pairwise over two lists of sequences.
+    /// Operates in parallel over the pairs.
+    ///
+    /// :param list left: base sequences
+    /// :param list right: sequences to compare
+    /// :return: similarity of each pair
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_jaro_max_similarity(targets, source_sequences)
+    ///
+    /// Largest :py:func:`generic_jaro` between each target and the source
+    /// sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: largest similarity per target, 0.0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+    ;
+    /// generic_jaro_min_similarity(targets, source_sequences)
+    ///
+    /// Smallest :py:func:`generic_jaro` between each target and the source
+    /// sequences. Operates in parallel.
+    ///
+    /// :param list targets: target sequences
+    /// :param list source_sequences: source sequences
+    /// :return: smallest similarity per target, 0.0 without source sequences
+    /// :rtype: list
+    /// :raises TypeError: if an element is not hashable
+}
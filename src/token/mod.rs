@@ -0,0 +1,69 @@
+//! Token-level similarities, which compare multi-word strings word by word
+//! with an inner character similarity, so that "Acme Holdings Ltd" and
+//! "ACME Ltd." match on their shared words despite the extra one, and the
+//! TF-IDF weighting they share with the character n-gram matcher.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+pub(crate) mod monge_elkan;
+pub(crate) mod soft_tfidf;
+pub(crate) mod tfidf;
+
+/// Lower-cased alphanumeric words of `s`.
+pub(crate) fn tokens(s: &str) -> Vec<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Document frequencies of the terms of a corpus.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Idf {
+    pub(crate) frequencies: HashMap<String, u32>,
+    pub(crate) documents: usize,
+}
+
+impl Idf {
+    /// Counts the documents each term occurs in.
+    pub(crate) fn fit<I>(documents: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: IntoIterator<Item = String>,
+    {
+        let mut idf = Idf::default();
+        for document in documents {
+            idf.documents += 1;
+            for term in document.into_iter().collect::<HashSet<_>>() {
+                *idf.frequencies.entry(term).or_insert(0) += 1;
+            }
+        }
+        idf
+    }
+
+    /// Smoothed inverse document frequency, `ln((1 + N) / (1 + df)) + 1`, so
+    /// that terms in every document keep some weight and unseen terms get the
+    /// most.
+    pub(crate) fn idf(&self, term: &str) -> f64 {
+        let frequency = self.frequencies.get(term).copied().unwrap_or(0);
+        ((1 + self.documents) as f64 / (1 + frequency) as f64).ln() + 1.0
+    }
+
+    /// Unit-length TF-IDF vector of a document, weighting terms by
+    /// `ln(1 + tf) * idf`. Terms are sorted so that sums over the vector do
+    /// not depend on hashing.
+    pub(crate) fn weights(&self, terms: Vec<String>) -> BTreeMap<String, f64> {
+        let mut counts: BTreeMap<String, f64> = BTreeMap::new();
+        for term in terms {
+            *counts.entry(term).or_insert(0.0) += 1.0;
+        }
+        for (term, weight) in counts.iter_mut() {
+            *weight = weight.ln_1p() * self.idf(term);
+        }
+        let norm = counts.values().map(|w| w * w).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            counts.values_mut().for_each(|w| *w /= norm);
+        }
+        counts
+    }
+}
@@ -0,0 +1,97 @@
+//! Monge-Elkan similarity: the average, over the tokens of one string, of
+//! their best inner similarity to a token of the other.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use super::tokens;
+use crate::metric::Similarity;
+use crate::process::{self, Preprocessor};
+
+fn directed(a: &[String], b: &[String], metric: Similarity) -> f64 {
+    let total: f64 = a
+        .iter()
+        .map(|a| b.iter().map(|b| metric.similarity(a, b)).fold(0.0, f64::max))
+        .sum();
+    total / a.len() as f64
+}
+
+/// Monge-Elkan similarity of `a` to `b`, or the mean of both directions if
+/// `symmetric`. Strings without tokens match only each other.
+pub(crate) fn similarity(a: &str, b: &str, metric: Similarity, symmetric: bool) -> f64 {
+    let a = tokens(a);
+    let b = tokens(b);
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => return 1.0,
+        (true, false) | (false, true) => return 0.0,
+        _ => {}
+    }
+    if symmetric {
+        (directed(&a, &b, metric) + directed(&b, &a, metric)) / 2.0
+    } else {
+        directed(&a, &b, metric)
+    }
+}
+
+/// monge_elkan(a, b, metric="jaro_winkler", symmetric=False, processor=None)
+///
+/// Splits both strings into lower-cased alphanumeric tokens and averages,
+/// over the tokens of ``a``, the best ``metric`` similarity to a token of
+/// ``b``. Extra tokens in ``b`` are not penalized, so the measure is
+/// asymmetric; ``symmetric`` averages both directions.
+///
+/// :param str a: base string
+/// :param str b: string to compare
+/// :param str metric: inner similarity, any name accepted by
+///     :py:meth:`VPTree.build`
+/// :param bool symmetric: average the similarity of a to b and of b to a
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :return: similarity
+/// :rtype: float
+/// :raises ValueError: if the metric is unknown
+#[pyfunction]
+#[pyo3(signature = (a, b, metric="jaro_winkler", symmetric=false, processor=None))]
+pub(crate) fn monge_elkan(
+    a: &str,
+    b: &str,
+    metric: &str,
+    symmetric: bool,
+    processor: Option<Preprocessor>,
+) -> PyResult<f64> {
+    let metric = Similarity::parse(metric)?;
+    let (a, b) = process::apply_str_pair(&processor, &[a], &[b])?;
+    Ok(similarity(&a[0], &b[0], metric, symmetric))
+}
+
+/// monge_elkan_parallel(a, b, metric="jaro_winkler", symmetric=False, processor=None)
+///
+/// Like :py:func:`monge_elkan`, pairwise over two lists of strings. Operates
+/// in parallel over the pairs.
+///
+/// :param list a: base strings
+/// :param list b: strings to compare
+/// :param str metric: inner similarity
+/// :param bool symmetric: average the similarity of a to b and of b to a
+/// :param processor: Processor or Python callable applied to the strings, None for none
+/// :return: similarity of each pair
+/// :rtype: list
+/// :raises ValueError: if the metric is unknown
+#[pyfunction]
+#[pyo3(signature = (a, b, metric="jaro_winkler", symmetric=false, processor=None))]
+pub(crate) fn monge_elkan_parallel(
+    py: Python,
+    a: Vec<&str>,
+    b: Vec<&str>,
+    metric: &str,
+    symmetric: bool,
+    processor: Option<Preprocessor>,
+) -> PyResult<Vec<f64>> {
+    let metric = Similarity::parse(metric)?;
+    let (a, b) = process::apply_str_pair(&processor, &a, &b)?;
+    Ok(py.allow_threads(|| {
+        (a, b)
+            .into_par_iter()
+            .map(|(a, b)| similarity(&a, &b, metric, symmetric))
+            .collect()
+    }))
+}
@@ -0,0 +1,187 @@
+//! Soft-TFIDF (Cohen, Ravikumar and Fienberg, 2003): TF-IDF cosine over
+//! tokens, where a token also counts as shared with the most similar token
+//! of the other string if their inner similarity reaches a threshold.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use super::{tokens, Idf};
+use crate::metric::Similarity;
+use crate::process::{self, Preprocessor};
+
+/// SoftTfidf(corpus, metric="jaro_winkler", threshold=0.9)
+///
+/// Soft-TFIDF similarity with document frequencies counted over ``corpus``,
+/// so that rare tokens such as a surname weigh more than common ones such as
+/// "ltd". Tokens are lower-cased alphanumeric words.
+///
+/// :param list corpus: strings to count document frequencies over
+/// :param str metric: inner similarity, any name accepted by
+///     :py:meth:`VPTree.build`
+/// :param float threshold: inner similarity from which tokens count as shared
+/// :raises ValueError: if the metric is unknown
+#[pyclass(module = "xdistances")]
+pub(crate) struct SoftTfidf {
+    idf: Idf,
+    metric: Similarity,
+    threshold: f64,
+}
+
+impl SoftTfidf {
+    pub(crate) fn new(idf: Idf, metric: Similarity, threshold: f64) -> PyResult<Self> {
+        if threshold.is_nan() {
+            return Err(exceptions::PyValueError::new_err("threshold must not be NaN"));
+        }
+        Ok(SoftTfidf {
+            idf,
+            metric,
+            threshold,
+        })
+    }
+
+    /// Sum over the tokens of `a` of their weight times the weight and inner
+    /// similarity of their closest token in `b`, if close enough. Strings
+    /// without tokens match only each other.
+    pub(crate) fn similarity(&self, a: &str, b: &str) -> f64 {
+        let a = tokens(a);
+        let b = tokens(b);
+        match (a.is_empty(), b.is_empty()) {
+            (true, true) => return 1.0,
+            (true, false) | (false, true) => return 0.0,
+            _ => {}
+        }
+        let a = self.idf.weights(a);
+        let b: Vec<(String, f64)> = self.idf.weights(b).into_iter().collect();
+        let total: f64 = a
+            .iter()
+            .filter_map(|(token, weight)| {
+                let (similarity, other) = b
+                    .iter()
+                    .map(|(other, other_weight)| (self.metric.similarity(token, other), other_weight))
+                    .max_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(std::cmp::Ordering::Equal))?;
+                if similarity >= self.threshold {
+                    Some(weight * other * similarity)
+                } else {
+                    None
+                }
+            })
+            .sum();
+        total.min(1.0)
+    }
+}
+
+#[pymethods]
+impl SoftTfidf {
+    #[new]
+    #[pyo3(signature = (corpus, metric="jaro_winkler", threshold=0.9))]
+    fn py_new(py: Python, corpus: Vec<&str>, metric: &str, threshold: f64) -> PyResult<Self> {
+        let metric = Similarity::parse(metric)?;
+        let idf = py.allow_threads(|| Idf::fit(corpus.iter().map(|s| tokens(s))));
+        SoftTfidf::new(idf, metric, threshold)
+    }
+
+    /// similarity(a, b, processor=None)
+    ///
+    /// Soft-TFIDF similarity of ``a`` to ``b``. It is asymmetric when the
+    /// closest token of ``b`` differs between two tokens of ``a``.
+    ///
+    /// :param str a: base string
+    /// :param str b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :return: similarity between 0.0 and 1.0
+    /// :rtype: float
+    #[pyo3(name = "similarity", signature = (a, b, processor=None))]
+    fn py_similarity(&self, a: &str, b: &str, processor: Option<Preprocessor>) -> PyResult<f64> {
+        let (a, b) = process::apply_str_pair(&processor, &[a], &[b])?;
+        Ok(self.similarity(&a[0], &b[0]))
+    }
+
+    /// similarity_parallel(a, b, processor=None)
+    ///
+    /// Like :py:meth:`SoftTfidf.similarity`, pairwise over two lists of
+    /// strings. Operates in parallel over the pairs.
+    ///
+    /// :param list a: base strings
+    /// :param list b: strings to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :return: similarity of each pair
+    /// :rtype: list
+    #[pyo3(signature = (a, b, processor=None))]
+    fn similarity_parallel(
+        &self,
+        py: Python,
+        a: Vec<&str>,
+        b: Vec<&str>,
+        processor: Option<Preprocessor>,
+    ) -> PyResult<Vec<f64>> {
+        let (a, b) = process::apply_str_pair(&processor, &a, &b)?;
+        Ok(py.allow_threads(|| {
+            (a, b)
+                .into_par_iter()
+                .map(|(a, b)| self.similarity(&a, &b))
+                .collect()
+        }))
+    }
+
+    /// idf(token)
+    ///
+    /// Smoothed inverse document frequency of a token,
+    /// ``ln((1 + N) / (1 + df)) + 1``.
+    ///
+    /// :param str token: lower-cased token
+    /// :return: inverse document frequency
+    /// :rtype: float
+    fn idf(&self, token: &str) -> f64 {
+        self.idf.idf(token)
+    }
+
+    fn __getstate__(&self) -> (HashMap<String, u32>, usize, &'static str, f64) {
+        (
+            self.idf.frequencies.clone(),
+            self.idf.documents,
+            self.metric.name(),
+            self.threshold,
+        )
+    }
+
+    fn __setstate__(&mut self, state: (HashMap<String, u32>, usize, &str, f64)) -> PyResult<()> {
+        *self = SoftTfidf::from_state(state)?;
+        Ok(())
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, (PyObject,))> {
+        let py = slf.py();
+        let state = slf.borrow().__getstate__().into_py(py);
+        Ok((slf.get_type().getattr("_from_state")?.into(), (state,)))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "_from_state")]
+    fn from_state(state: (HashMap<String, u32>, usize, &str, f64)) -> PyResult<Self> {
+        let (frequencies, documents, metric, threshold) = state;
+        let idf = Idf {
+            frequencies,
+            documents,
+        };
+        SoftTfidf::new(idf, Similarity::parse(metric)?, threshold)
+    }
+
+    /// Number of documents in the corpus.
+    #[getter]
+    fn documents(&self) -> usize {
+        self.idf.documents
+    }
+
+    #[getter]
+    fn metric(&self) -> &'static str {
+        self.metric.name()
+    }
+
+    #[getter]
+    fn threshold(&self) -> f64 {
+        self.threshold
+    }
+}
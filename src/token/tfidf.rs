@@ -0,0 +1,221 @@
+//! TF-IDF cosine over character n-grams, for fuzzy joins of two lists.
+//!
+//! Only the n-grams of the right list are indexed, as postings of their
+//! weights, so each left string is scored against the right strings it
+//! shares an n-gram with: a sparse matrix product row by row, keeping the
+//! top-n of each row.
+
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::exceptions;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use super::Idf;
+use crate::process::{self, Preprocessor};
+
+/// Lower-cased character n-grams of `s`, with whitespace collapsed and a
+/// space added at both ends so that n-grams mark word boundaries. Strings
+/// shorter than `n` give a single gram.
+fn ngrams(s: &str, n: usize) -> Vec<String> {
+    let words: Vec<String> = s.split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let chars: Vec<char> = format!(" {} ", words.join(" ")).chars().collect();
+    if chars.len() <= n {
+        return vec![chars.into_iter().collect()];
+    }
+    chars.windows(n).map(|gram| gram.iter().collect()).collect()
+}
+
+/// TfidfMatcher
+///
+/// TF-IDF weighted cosine similarity over character n-grams, with document
+/// frequencies counted over a corpus. Build it with
+/// :py:meth:`TfidfMatcher.fit` and join two lists with
+/// :py:meth:`TfidfMatcher.match`.
+#[pyclass(module = "xdistances")]
+pub(crate) struct TfidfMatcher {
+    idf: Idf,
+    ngram: usize,
+}
+
+impl TfidfMatcher {
+    pub(crate) fn new(idf: Idf, ngram: usize) -> PyResult<Self> {
+        if ngram == 0 {
+            return Err(exceptions::PyValueError::new_err("ngram must be at least 1"));
+        }
+        Ok(TfidfMatcher { idf, ngram })
+    }
+
+    fn vector(&self, s: &str) -> BTreeMap<String, f64> {
+        self.idf.weights(ngrams(s, self.ngram))
+    }
+
+    /// Best `top_n` matches in `right` of each string in `left` scoring at
+    /// least `min_similarity`, as `(i, j, score)` ordered by `i` and then
+    /// best first.
+    pub(crate) fn matches(
+        &self,
+        left: &[String],
+        right: &[String],
+        top_n: usize,
+        min_similarity: f64,
+    ) -> Vec<(usize, usize, f64)> {
+        let vectors: Vec<BTreeMap<String, f64>> = right.par_iter().map(|s| self.vector(s)).collect();
+        let mut terms: HashMap<String, usize> = HashMap::new();
+        let mut postings: Vec<Vec<(usize, f64)>> = Vec::new();
+        for (j, vector) in vectors.into_iter().enumerate() {
+            for (term, weight) in vector {
+                let next = postings.len();
+                let id = *terms.entry(term).or_insert(next);
+                if id == next {
+                    postings.push(Vec::new());
+                }
+                postings[id].push((j, weight));
+            }
+        }
+        left.par_iter()
+            .enumerate()
+            .flat_map_iter(|(i, s)| {
+                let mut scores: HashMap<usize, f64> = HashMap::new();
+                for (term, weight) in self.vector(s) {
+                    if let Some(&id) = terms.get(&term) {
+                        for &(j, other) in &postings[id] {
+                            *scores.entry(j).or_insert(0.0) += weight * other;
+                        }
+                    }
+                }
+                let mut row: Vec<(usize, usize, f64)> = scores
+                    .into_iter()
+                    .filter(|&(_, score)| score >= min_similarity)
+                    .map(|(j, score)| (i, j, score.min(1.0)))
+                    .collect();
+                row.sort_by(|a, b| {
+                    b.2.partial_cmp(&a.2)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then(a.1.cmp(&b.1))
+                });
+                row.truncate(top_n);
+                row
+            })
+            .collect()
+    }
+}
+
+#[pymethods]
+impl TfidfMatcher {
+    /// fit(corpus, ngram=3)
+    ///
+    /// Counts the document frequencies of the character n-grams of
+    /// ``corpus``, usually both lists to join. Strings are lower-cased and
+    /// their whitespace collapsed; n-grams unseen in the corpus get the
+    /// highest weight.
+    ///
+    /// :param list corpus: strings to count n-grams over
+    /// :param int ngram: n-gram length
+    /// :return: matcher
+    /// :rtype: TfidfMatcher
+    /// :raises ValueError: if ngram is 0
+    #[staticmethod]
+    #[pyo3(signature = (corpus, ngram=3))]
+    fn fit(py: Python, corpus: Vec<&str>, ngram: usize) -> PyResult<Self> {
+        let mut matcher = TfidfMatcher::new(Idf::default(), ngram)?;
+        matcher.idf = py.allow_threads(|| Idf::fit(corpus.iter().map(|s| ngrams(s, ngram))));
+        Ok(matcher)
+    }
+
+    /// match(left, right, top_n=1, min_similarity=0.0, processor=None)
+    ///
+    /// Finds the ``top_n`` most similar strings of ``right`` for each string
+    /// of ``left`` by TF-IDF cosine similarity. Pairs sharing no n-gram are
+    /// never returned. Operates in parallel over ``left``.
+    ///
+    /// :param list left: strings to match
+    /// :param list right: strings to match against
+    /// :param int top_n: matches kept per left string
+    /// :param float min_similarity: lowest similarity returned
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :return: ``(i, j, score)`` triples of positions in left and right,
+    ///     ordered by i and then by decreasing score
+    /// :rtype: list
+    /// :raises ValueError: if top_n is 0 or min_similarity is NaN
+    #[pyo3(signature = (left, right, top_n=1, min_similarity=0.0, processor=None))]
+    fn r#match(
+        &self,
+        py: Python,
+        left: Vec<&str>,
+        right: Vec<&str>,
+        top_n: usize,
+        min_similarity: f64,
+        processor: Option<Preprocessor>,
+    ) -> PyResult<Vec<(usize, usize, f64)>> {
+        if top_n == 0 {
+            return Err(exceptions::PyValueError::new_err("top_n must be at least 1"));
+        }
+        if min_similarity.is_nan() {
+            return Err(exceptions::PyValueError::new_err("min_similarity must not be NaN"));
+        }
+        let (left, right) = process::apply_str_pair(&processor, &left, &right)?;
+        Ok(py.allow_threads(|| self.matches(&left, &right, top_n, min_similarity)))
+    }
+
+    /// similarity(a, b, processor=None)
+    ///
+    /// TF-IDF cosine similarity of two strings.
+    ///
+    /// :param str a: base string
+    /// :param str b: string to compare
+    /// :param processor: Processor or Python callable applied to the strings, None for none
+    /// :return: similarity between 0.0 and 1.0
+    /// :rtype: float
+    #[pyo3(signature = (a, b, processor=None))]
+    fn similarity(&self, a: &str, b: &str, processor: Option<Preprocessor>) -> PyResult<f64> {
+        let (a, b) = process::apply_str_pair(&processor, &[a], &[b])?;
+        let b = self.vector(&b[0]);
+        let dot: f64 = self
+            .vector(&a[0])
+            .iter()
+            .filter_map(|(term, weight)| b.get(term).map(|other| weight * other))
+            .fold(0.0, |dot, product| dot + product);
+        Ok(dot.min(1.0))
+    }
+
+    fn __getstate__(&self) -> (HashMap<String, u32>, usize, usize) {
+        (self.idf.frequencies.clone(), self.idf.documents, self.ngram)
+    }
+
+    fn __setstate__(&mut self, state: (HashMap<String, u32>, usize, usize)) -> PyResult<()> {
+        *self = TfidfMatcher::from_state(state)?;
+        Ok(())
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, (PyObject,))> {
+        let py = slf.py();
+        let state = slf.borrow().__getstate__().into_py(py);
+        Ok((slf.get_type().getattr("_from_state")?.into(), (state,)))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "_from_state")]
+    fn from_state(state: (HashMap<String, u32>, usize, usize)) -> PyResult<Self> {
+        let (frequencies, documents, ngram) = state;
+        let idf = Idf {
+            frequencies,
+            documents,
+        };
+        TfidfMatcher::new(idf, ngram)
+    }
+
+    /// Number of documents in the corpus.
+    #[getter]
+    fn documents(&self) -> usize {
+        self.idf.documents
+    }
+
+    #[getter]
+    fn ngram(&self) -> usize {
+        self.ngram
+    }
+}
@@ -1,3 +1,7 @@
+import itertools
+import math
+import pickle
+
 import pytest
 import xdistances
 
@@ -49,5 +53,640 @@ def test_jaro():
 def test_jaro_winkler():
     assert xdistances.jaro_winkler("a", "a") == 1.0
     assert xdistances.jaro_winkler("a", "b") == 0.0
-    assert xdistances.jaro_winkler("aaaa", "abbb") == 0.55
+    assert xdistances.jaro_winkler("aaaa", "abbb") == 0.5
+    assert xdistances.jaro_winkler("aaaa", "abbb", max_prefix=None, boost_threshold=0.0) == 0.55
+    assert round(xdistances.jaro_winkler("MARTHA", "MARHTA"), 4) == 0.9611
+    assert round(xdistances.jaro_winkler("DWAYNE", "DUANE"), 4) == 0.84
+    assert round(xdistances.jaro_winkler("cheeseburger", "cheese fries"), 4) == 0.8667
+    assert round(xdistances.jaro_winkler("cheeseburger", "cheese fries", max_prefix=6), 4) == 0.9111
+    assert xdistances.jaro_winkler_parallel(["aaaa"], ["abbb"], boost_threshold=0.0) == [0.55]
+    assert xdistances.jaro_winkler_max_similarity(["aaaa"], ["abbb", "b"], boost_threshold=0.0) == [0.55]
+    with pytest.raises(ValueError):
+        xdistances.jaro_winkler("a", "b", prefix_weight=-0.1)
+    with pytest.raises(ValueError):
+        xdistances.jaro_winkler("a", "b", boost_threshold=float("nan"))
+    with pytest.raises(ValueError):
+        xdistances.jaro_winkler_parallel(["a"], ["b"], boost_threshold=1.5)
+
+
+def test_editex():
+    assert xdistances.editex("cat", "hat") == 2
+    assert xdistances.editex("Niall", "Neil") == 2
+    assert xdistances.editex("aluminum", "Catalan") == 12
+    assert xdistances.editex("", "") == 0
+    assert xdistances.normalized_editex("Niall", "Neil") == 0.8
+    assert xdistances.normalized_editex("", "") == 1.0
+    assert xdistances.editex_parallel(["cat", "Niall"], ["hat", "Neil"]) == [2, 2]
+    assert xdistances.editex_min_similarity(["cat"], ["hat", "kat"]) == [1]
+    assert xdistances.normalized_editex_max_similarity(["Neil"], ["Niall", "Neil"]) == [1.0]
+
+
+def test_sift4():
+    a, b = "This is the first string", "And this is another string"
+    assert xdistances.sift4_general(a, b) == 11
+    assert xdistances.sift4_simple(a, b) == 13
+    assert xdistances.sift4_general("abcdef", "badcfe") == 3
+    assert xdistances.sift4_simple("kitten", "kitten") == 0
+    assert xdistances.sift4_general("", "abc") == 3
+    assert xdistances.sift4_general(a, b, max_distance=3) > 3
+    assert xdistances.sift4_simple_parallel([a, "kitten"], [b, "sitting"]) == [13, 3]
+    assert xdistances.sift4_general_min_similarity([a], [b, a]) == [0]
+    assert xdistances.sift4_general_max_similarity([a], [b, a]) == [11]
+
+
+def test_bag_distance():
+    assert xdistances.bag_distance("kitten", "sitting") == 3
+    assert xdistances.bag_distance("abc", "cba") == 0
+    assert xdistances.bag_distance("", "abc") == 3
+    assert xdistances.bag_distance_parallel(["ab", "abc"], ["abd", "a"]) == [1, 2]
+    assert xdistances.bag_distance_min_similarity(["kitten"], ["sitting", "mitten"]) == [1]
+    assert xdistances.bag_distance_max_similarity(["kitten"], ["sitting", "mitten"]) == [3]
+
+
+def test_qgram_similarities():
+    assert xdistances.sorensen_dice("night", "nacht") == 0.25
+    assert xdistances.sorensen_dice("a b", "ab") == 1.0
+    assert xdistances.sorensen_dice("ab", "abc", q=3) == 0.0
+    assert round(xdistances.sorensen_dice("ab", "abc", q=3, pad=True), 4) == 0.4444
+    assert xdistances.sorensen_dice("café", "cafe") == 4 / 7
+    assert xdistances.sorensen_dice("café", "cafe", count_grams=True) == 2 / 3
+    assert xdistances.sorensen_dice_parallel(["café"], ["cafe"], count_grams=True) == [2 / 3]
+    assert xdistances.jaccard("night", "nacht") == 1 / 7
+    assert xdistances.jaccard("aaa", "aa") == 0.5
+    assert xdistances.jaccard("aaa", "aa", multiset=False) == 1.0
+    assert xdistances.overlap_coefficient("night", "nightly") == 1.0
+    assert xdistances.cosine("night", "nacht") == 0.25
+    assert xdistances.tversky("night", "nacht", 0.5, 0.5) == xdistances.sorensen_dice("night", "nacht")
+    assert xdistances.tversky("night", "nightly", alpha=1.0, beta=0.0) == 1.0
+    assert xdistances.tversky("nightly", "night", alpha=1.0, beta=0.0) == 2 / 3
+    assert xdistances.jaccard_parallel(["night", "ab"], ["nacht", "ab"], q=1) == [3 / 7, 1.0]
+    assert xdistances.cosine_max_similarity(["night"], ["nacht", "night"]) == [1.0]
+    assert xdistances.tversky_min_similarity(["night"], ["nacht", "nightly"]) == [1 / 7]
+    with pytest.raises(ValueError):
+        xdistances.jaccard("a", "b", q=0)
+    with pytest.raises(ValueError):
+        xdistances.tversky("a", "b", alpha=-1.0)
+
+
+def test_ncd():
+    a = "GET /api/v1/users/123 HTTP/1.1 200 0.012s Mozilla/5.0"
+    b = "GET /api/v1/users/456 HTTP/1.1 200 0.015s Mozilla/5.0"
+    c = "ERROR database connection refused at db-primary:5432"
+    for compressor in ["zlib", "lzma", "entropy"]:
+        assert xdistances.ncd(a, b, compressor) < xdistances.ncd(a, c, compressor)
+        assert xdistances.ncd(a, c, compressor) == xdistances.ncd(c, a, compressor)
+        assert xdistances.ncd(a, a, compressor) < xdistances.ncd(a, b, compressor)
+        assert xdistances.ncd("", "", compressor) == 0.0
+    assert xdistances.ncd_parallel([a, a], [b, c]) == [xdistances.ncd(a, b), xdistances.ncd(a, c)]
+    assert xdistances.ncd_cdist([a, c], [a, b, c], "entropy") == [
+        [xdistances.ncd(x, y, "entropy") for y in [a, b, c]] for x in [a, c]
+    ]
+    with pytest.raises(ValueError):
+        xdistances.ncd(a, b, "gzip")
+
+
+def test_processor():
+    nfc = xdistances.Processor()
+    assert xdistances.levenshtein("Caf\u00e9", "Cafe\u0301") == 2
+    assert xdistances.levenshtein("Caf\u00e9", "Cafe\u0301", processor=nfc) == 0
+    fold = xdistances.Processor(casefold=True)
+    assert fold("STRASSE") == fold("stra\u00dfe") == "strasse"
+    assert fold("\u01f0") == "\u01f0"
+    assert xdistances.jaro_winkler("STRASSE", "stra\u00dfe", processor=fold) == 1.0
+    loose = xdistances.Processor("NFKC", casefold=True, strip_diacritics=True, collapse_whitespace=True)
+    assert loose("  \uff23af\u00e9\t  D\u00c9J\u00c0   vu ") == "cafe deja vu"
+    assert loose("\u00f8") == "\u00f8"
+    assert xdistances.levenshtein_parallel(["\u00c9cole", "Ecole"], ["ecole", "ECOLE"], processor=loose) == [0, 0]
+    assert xdistances.levenshtein_min_similarity(["Caf\u00e9"], ["cafe", "tea"], processor=loose) == [0]
+    assert xdistances.sorensen_dice("D\u00c9J\u00c0 VU", "deja vu", processor=loose) == 1.0
+    assert xdistances.sift4_general("\u00c9COLE", "ecole", processor=loose) == 0
+    assert xdistances.hamming("\u00df", "SS", processor=fold) == 0
+    assert xdistances.Processor(normalization=None)("Cafe\u0301") == "Cafe\u0301"
+    copy = pickle.loads(pickle.dumps(loose))
+    assert (copy.normalization, copy.casefold, copy.strip_diacritics, copy.collapse_whitespace) == ("NFKC", True, True, True)
+    with pytest.raises(ValueError):
+        xdistances.Processor("NFX")
+
+
+def test_unit():
+    thumbs, toned = "\U0001f44d ok", "\U0001f44d\U0001f3fd ok"
+    assert xdistances.levenshtein(thumbs, toned) == 1
+    assert xdistances.levenshtein(thumbs, toned, unit="byte") == 4
+    namaste, namaskar = "\u0928\u092e\u0938\u094d\u0924\u0947", "\u0928\u092e\u0938\u094d\u0915\u093e\u0930"
+    assert xdistances.levenshtein(namaste, namaskar) == 3
+    assert xdistances.levenshtein(namaste, namaskar, unit="grapheme") == 2
+    flags = "\U0001f1eb\U0001f1f7\U0001f1e9\U0001f1ea", "\U0001f1e9\U0001f1ea\U0001f1eb\U0001f1f7"
+    assert xdistances.hamming(*flags, unit="grapheme") == 2
+    assert xdistances.osa_distance(*flags, unit="grapheme") == 1
+    assert xdistances.damerau_levenshtein(*flags, unit="grapheme") == 1
+    assert xdistances.normalized_levenshtein(*flags, unit="grapheme") == 0.0
+    assert xdistances.sorensen_dice(*flags, unit="grapheme") == 0.0
+    assert xdistances.jaro("abc", "abd", unit="byte") == xdistances.jaro("abc", "abd")
+    assert xdistances.jaro_winkler(thumbs, toned, unit="grapheme") < xdistances.jaro_winkler(thumbs, toned)
+    assert xdistances.sift4_general(thumbs, toned, unit="grapheme") == 1
+    assert xdistances.bag_distance(thumbs, toned, unit="grapheme") == 1
+    assert xdistances.levenshtein_parallel([thumbs], [toned], unit="byte") == [4]
+    assert xdistances.levenshtein_min_similarity([thumbs], [toned, "ok"], unit="grapheme") == [1]
+    assert xdistances.normalized_damerau_levenshtein("", "", unit="grapheme") == 1.0
+    with pytest.raises(ValueError):
+        xdistances.levenshtein("a", "b", unit="word")
+
+
+def test_python_callbacks():
+    calls = []
+
+    def strip_digits(s):
+        calls.append(s)
+        return "".join(c for c in s if not c.isdigit())
+
+    assert xdistances.levenshtein("abc1", "abc22", processor=strip_digits) == 0
+    calls.clear()
+    assert xdistances.levenshtein_max_similarity(["ab1", "ab1"], ["ab2", "ab1", "xy"], processor=strip_digits) == [2, 2]
+    assert sorted(calls) == ["ab1", "ab2", "xy"]
+    assert xdistances.jaccard_parallel(["a b"], ["ab"], processor=str.upper) == [1.0]
+    assert xdistances.levenshtein(b"AB", "ab", processor=lambda s: s.lower()) == 0
+    assert xdistances.cdist(["abc"], ["abc", "abd"]) == [[1.0, 1 - 1 / 3]]
+    assert xdistances.cdist(["abc"], ["ABC"], scorer="jaro", processor=xdistances.Processor(casefold=True)) == [[1.0]]
+    assert xdistances.cdist(["ab", "c"], ["abc"], scorer=lambda a, b: len(a) + len(b)) == [[5.0], [4.0]]
+    with pytest.raises(ZeroDivisionError):
+        xdistances.cdist(["a"], ["b"], scorer=lambda a, b: 1 / 0)
+    with pytest.raises(TypeError):
+        xdistances.levenshtein("a", "b", processor=1)
+    with pytest.raises(TypeError):
+        xdistances.levenshtein("a", "b", processor=lambda s: 1)
+    with pytest.raises(ValueError):
+        xdistances.cdist(["a"], ["b"], scorer="klingon")
+
+
+def test_extract():
+    choices = ["Atlanta Falcons", "New York Jets", "New York Giants", "Dallas Cowboys"]
+    assert xdistances.extract_one("new york jets", choices, processor=str.lower) == ("New York Jets", 1.0, 1)
+    assert xdistances.extract_one("cowboys", choices, scorer="jaro_winkler", min_similarity=0.99) is None
+    best = xdistances.extract("new york", choices, limit=2, processor=str.lower)
+    assert [(choice, key) for choice, _, key in best] == [("New York Jets", 1), ("New York Giants", 2)]
+    assert len(xdistances.extract("x", choices, limit=None)) == 4
+    assert xdistances.extract("ab", ["ab", "ab"]) == [("ab", 1.0, 0), ("ab", 1.0, 1)]
+    teams = {"atl": "Atlanta Falcons", "nyj": "New York Jets", "tbd": None, "nan": float("nan")}
+    assert xdistances.extract_one("Falcons", teams, scorer=lambda a, b: float("Falcons" in b))[2] == "atl"
+    assert [key for _, _, key in xdistances.extract("New York", teams, limit=None)] == ["nyj", "atl"]
+    assert xdistances.extract_one("a", []) is None
+    with pytest.raises(ValueError):
+        xdistances.extract("a", ["b"], scorer="klingon")
+    with pytest.raises(TypeError):
+        xdistances.extract("a", [1])
+
+
+def test_extract_series():
+    pd = pytest.importorskip("pandas")
+    teams = pd.Series(["Atlanta Falcons", None, "New York Jets"], index=["atl", "tbd", "nyj"])
+    assert xdistances.extract_one("new york jets", teams, processor=str.lower) == ("New York Jets", 1.0, "nyj")
+
+
+def test_dedupe():
+    names = ["Acme Corp", "ACME Corp.", "Globex", "Acme Corp", "acme corp", "Initech"]
+    assert xdistances.dedupe(names, processor=str.lower) == [0, 0, 1, 0, 0, 2]
+    assert xdistances.dedupe(names, processor=str.lower, canonical=True) == [
+        "Acme Corp", "Acme Corp", "Globex", "Acme Corp", "Acme Corp", "Initech"
+    ]
+    assert xdistances.dedupe(names) == [0, 1, 2, 0, 3, 4]
+    assert xdistances.dedupe(["abcd", "abce", "abfe"], threshold=0.75) == [0, 0, 0]
+    assert xdistances.dedupe(["abcd", "abce", "abfe"], threshold=0.75, metric="jaccard", exhaustive=True) == [0, 1, 2]
+    assert xdistances.dedupe([]) == []
+    with pytest.raises(ValueError):
+        xdistances.dedupe(["a"], threshold=float("nan"))
+    with pytest.raises(ValueError):
+        xdistances.dedupe(["a"], metric="klingon")
+
+
+def test_cluster():
+    fruits = ["apple", "appel", "aple", "banana", "bananna", "cherry"]
+    assert xdistances.cluster(fruits, threshold=0.6) == [0, 0, 0, 1, 1, 2]
+    assert xdistances.cluster(fruits, method="complete", threshold=0.6) == [0, 0, 0, 1, 1, 2]
+    assert xdistances.cluster(fruits, method="single", threshold=0.6) == xdistances.dedupe(fruits, threshold=0.6)
+    assert xdistances.cluster(["abcd", "abce", "abfe"], method="single", threshold=0.75) == [0, 0, 0]
+    assert xdistances.cluster(["abcd", "abce", "abfe"], method="complete", threshold=0.75) == [0, 0, 1]
+    assert xdistances.cluster(["abcd", "abce", "abfe"], method="average", threshold=0.6) == [0, 0, 0]
+    assert xdistances.cluster(fruits * 2, method="dbscan", threshold=0.6, min_samples=4) == [0, 0, 0, 1, 1, -1] * 2
+    assert xdistances.cluster(["zz", "apple", "appel"], method="dbscan", threshold=0.6, min_samples=2) == [-1, 0, 0]
+    assert xdistances.cluster(["APPLE", "apple"], processor=str.lower, threshold=1.0) == [0, 0]
+    assert xdistances.cluster([]) == []
+    with pytest.raises(ValueError):
+        xdistances.cluster(["a"], method="ward")
+    with pytest.raises(ValueError):
+        xdistances.cluster(["a"], threshold=float("nan"))
+
+
+def test_bytes():
+    assert xdistances.hamming(b"\x00\x1f\xa0\xff", b"\x00\x1f\xa1\xfe") == 2
+    assert xdistances.levenshtein(b"kitten", b"sitting") == xdistances.levenshtein("kitten", "sitting")
+    assert xdistances.levenshtein("é", b"\xc3\xa9") == 0
+    assert xdistances.levenshtein("a\r\nb", "a\nb", unit="grapheme") == 1
+    assert xdistances.damerau_levenshtein(b"\xffab", b"\xffba") == 1
+    assert xdistances.normalized_damerau_levenshtein(b"ab", b"ba") == 0.5
+    assert xdistances.jaro_winkler(b"\x01\x02\x03\x04", b"\x01\x02\x03\x05") == xdistances.jaro_winkler("abcd", "abce")
+    assert xdistances.jaccard(b"ab cd", b"abcd") == 1.0
+    assert xdistances.sift4_simple(b"\x80\x81", b"\x81") == 1
+    assert xdistances.editex(b"niall", "neal") == xdistances.editex("niall", "neal")
+    assert xdistances.ncd(b"\x00" * 64, b"\x00" * 64) == xdistances.ncd("\x00" * 64, "\x00" * 64)
+    processor = xdistances.Processor(casefold=True)
+    assert xdistances.levenshtein(b"ABC", b"abc", processor=processor) == 3
+    assert xdistances.levenshtein_parallel([b"abc", "abc"], [b"abd", b"abc"]) == [1, 0]
+    assert xdistances.levenshtein_max_similarity([b"abc"], [b"abd", b"xyz"]) == [3]
+    with pytest.raises(TypeError):
+        xdistances.levenshtein(1, "a")
+
+
+def test_generic():
+    assert xdistances.generic_levenshtein(["the", "quick", "fox"], ["the", "slow", "fox"]) == 1
+    assert xdistances.generic_levenshtein((1, 2, 3), iter([1, 3])) == 1
+    assert xdistances.generic_levenshtein([1, True, None], [1.0, 1, None]) == 0
+    assert xdistances.generic_osa_distance([1, 2, 3], [2, 1, 3]) == 1
+    assert xdistances.generic_damerau_levenshtein([("a", 1), ("b", 2)], [("b", 2), ("c", 3), ("a", 1)]) == 2
+    assert xdistances.generic_normalized_levenshtein([], []) == 1.0
+    assert xdistances.generic_normalized_damerau_levenshtein([1, 2], [2, 1]) == 0.5
+    assert xdistances.generic_jaro(list("martha"), list("marhta")) == xdistances.jaro("martha", "marhta")
+    assert xdistances.generic_levenshtein_parallel([[1, 2], ["a"]], [[1, 3], ["a"]]) == [1, 0]
+    assert xdistances.generic_jaro_max_similarity([[1, 2, 3]], [[1, 2, 3], [4]]) == [1.0]
+    assert xdistances.generic_levenshtein_min_similarity([[1, 2, 3]], [[1, 2], [4]]) == [1]
+    assert xdistances.generic_levenshtein_max_similarity([[1]], []) == [0]
+    with pytest.raises(TypeError):
+        xdistances.generic_levenshtein([[1]], [[1]])
+
+
+def test_monge_elkan():
+    assert xdistances.monge_elkan("ACME Ltd.", "Acme Holdings Ltd") == 1.0
+    assert round(xdistances.monge_elkan("Acme Holdings Ltd", "ACME Ltd."), 4) == 0.8796
+    assert round(xdistances.monge_elkan("Acme Holdings Ltd", "ACME Ltd.", symmetric=True), 4) == 0.9398
+    assert xdistances.monge_elkan("", "") == 1.0
+    assert xdistances.monge_elkan("Acme", "") == 0.0
+    assert xdistances.monge_elkan_parallel(
+        ["Paul Johnson", "Acme"], ["Johnson Paul", "Acme Ltd"], metric="normalized_levenshtein"
+    ) == [1.0, 1.0]
+    processor = xdistances.Processor(strip_diacritics=True)
+    assert xdistances.monge_elkan("Café Ltd", "Cafe Ltd", processor=processor) == 1.0
+    assert xdistances.monge_elkan_parallel(["Café Ltd"], ["Cafe Ltd"], processor=processor) == [1.0]
+    assert xdistances.monge_elkan("Café", "Cafe", processor=lambda s: s.replace("é", "e")) == 1.0
+    with pytest.raises(ValueError):
+        xdistances.monge_elkan("a", "b", metric="klingon")
+    with pytest.raises(TypeError):
+        xdistances.monge_elkan("a", "b", processor=str.encode)
+
+
+def test_soft_tfidf():
+    corpus = ["Acme Holdings Ltd", "Acme Ltd", "Globex Ltd", "Initech Ltd", "Wayne Enterprises"]
+    matcher = xdistances.SoftTfidf(corpus)
+    assert matcher.documents == 5
+    assert matcher.idf("ltd") < matcher.idf("acme") < matcher.idf("unseen")
+    assert matcher.similarity("Acme Ltd", "ACME ltd.") == 1.0
+    assert matcher.similarity("Acme Holdngs", "Acme Holdings Ltd") > matcher.similarity("Globex Ltd", "Acme Ltd")
+    assert matcher.similarity("", "") == 1.0
+    assert matcher.similarity_parallel(["Acme", "Acme"], ["acme", "Initech"]) == [1.0, 0.0]
+    processor = xdistances.Processor(strip_diacritics=True)
+    assert matcher.similarity("Acmé Ltd", "Acme Ltd", processor=processor) == 1.0
+    assert matcher.similarity_parallel(["Acmé"], ["Acme"], processor=processor) == [1.0]
+    strict = xdistances.SoftTfidf(corpus, threshold=1.0)
+    assert strict.similarity("Acme Holdngs", "Acme Holdings") < matcher.similarity("Acme Holdngs", "Acme Holdings")
+    copy = pickle.loads(pickle.dumps(matcher))
+    assert (copy.metric, copy.threshold) == ("jaro_winkler", 0.9)
+    assert copy.similarity("Acme Holdngs", "Acme Holdings Ltd") == matcher.similarity("Acme Holdngs", "Acme Holdings Ltd")
+    with pytest.raises(ValueError):
+        xdistances.SoftTfidf(corpus, metric="klingon")
+    with pytest.raises(ValueError, match="threshold must not be NaN"):
+        xdistances.SoftTfidf(corpus, threshold=float("nan"))
+
+
+def test_tfidf_matcher():
+    left = ["Acme Holdings Ltd", "Globex Corporation", "Initech", "Wayne Enterprises", ""]
+    right = ["ACME Holdings Limited", "Globex Corp", "Initech LLC", "Wayne Ent.", "Umbrella Corp"]
+    matcher = xdistances.TfidfMatcher.fit(left + right)
+    assert (matcher.documents, matcher.ngram) == (10, 3)
+    matches = matcher.match(left, right)
+    assert [(i, j) for i, j, _ in matches] == [(0, 0), (1, 1), (2, 2), (3, 3)]
+    assert all(0.0 < score <= 1.0 for _, _, score in matches)
+    assert matches[2][2] == matcher.similarity("Initech", "Initech LLC")
+    assert matcher.similarity("Initech", "  INITECH ") == 1.0
+    processor = xdistances.Processor(strip_diacritics=True)
+    assert matcher.similarity("Initéch", "Initech", processor=processor) == 1.0
+    assert matcher.match(["Initéch"], right, processor=processor)[0][:2] == (0, 2)
+    assert math.copysign(1.0, matcher.similarity("", "")) == 1.0
+    two = matcher.match(left, right, top_n=2)
+    assert [(i, j) for i, j, _ in two if i == 1] == [(1, 1), (1, 4)]
+    assert [(i, j) for i, j, _ in matcher.match(left, right, top_n=2, min_similarity=0.5)] == [
+        (0, 0), (1, 1), (2, 2), (3, 3),
+    ]
+    copy = pickle.loads(pickle.dumps(matcher))
+    assert copy.match(left, right, top_n=2) == two
+    with pytest.raises(ValueError):
+        matcher.match(left, right, top_n=0)
+    with pytest.raises(ValueError, match="min_similarity must not be NaN"):
+        matcher.match(left, right, min_similarity=float("nan"))
+    with pytest.raises(ValueError):
+        xdistances.TfidfMatcher.fit(left, ngram=0)
+
 
+def test_soundex():
+    assert xdistances.soundex("Robert") == "R163"
+    assert xdistances.soundex("Rupert") == "R163"
+    assert xdistances.soundex("Ashcraft") == "A261"
+    assert xdistances.soundex("Tymczak") == "T522"
+    assert xdistances.soundex("Pfister") == "P236"
+    assert xdistances.soundex("123") == ""
+    assert xdistances.refined_soundex("jumped") == "J408106"
+    assert xdistances.soundex_parallel(["Rubin", "Honeyman"]) == ["R150", "H555"]
+    assert xdistances.refined_soundex_parallel(["The", "fox"]) == ["T60", "F205"]
+
+
+def test_metaphone():
+    assert xdistances.metaphone("Schmidt") == "SKMTT"
+    assert xdistances.metaphone("Knight") == "NT"
+    assert xdistances.metaphone("Catherine") == "K0RN"
+    assert xdistances.metaphone_parallel(["Xavier", "Wright"]) == ["SFR", "RT"]
+
+
+def test_double_metaphone():
+    assert xdistances.double_metaphone("Smith") == ("SM0", "XMT")
+    assert xdistances.double_metaphone("Schmidt") == ("XMT", "SMT")
+    assert xdistances.double_metaphone("Czerny") == ("SRN", "XRN")
+    assert xdistances.double_metaphone("Focaccia") == ("FKX", "FKX")
+    assert xdistances.double_metaphone("Jose") == ("HS", "HS")
+    assert xdistances.double_metaphone("Wasserman", max_length=6) == ("ASRMN", "FSRMN")
+    assert xdistances.double_metaphone_parallel(["Tagliaro"]) == [("TKLR", "TLR")]
+
+
+def test_nysiis():
+    assert xdistances.nysiis("Macintosh") == "MCANT"
+    assert xdistances.nysiis("Knuth") == "NAT"
+    assert xdistances.nysiis("Mitchell") == "MATCAL"
+    assert xdistances.nysiis("Phillipson") == "FALAPS"
+    assert xdistances.nysiis("Phillipson", max_length=None) == "FALAPSAN"
+    assert xdistances.nysiis_parallel(["Bishop", ""]) == ["BASAP", ""]
+
+
+def test_mra():
+    assert xdistances.mra("Byrne") == "BYRN"
+    assert xdistances.mra("Christopher") == "CHRPHR"
+    assert xdistances.mra_parallel(["Smith", "Kathryn"]) == ["SMTH", "KTHRYN"]
+    assert xdistances.mra_compare("Byrne", "Boern")
+    assert xdistances.mra_compare("Catherine", "Kathryn")
+    assert not xdistances.mra_compare("Smith", "Jones")
+    assert xdistances.mra_compare_parallel(["Smith", "Smith"], ["Smyth", "Jones"]) == [True, False]
+
+
+def test_caverphone():
+    assert xdistances.caverphone("Stevenson") == "STFNSN1111"
+    assert xdistances.caverphone("Peter") == "PTA1111111"
+    assert xdistances.caverphone("Mclaverty") == "MKLFTA1111"
+    assert xdistances.caverphone_parallel(["Henrichsen", "Tomlinson"]) == ["HNRKSN1111", "TMLNSN1111"]
+
+
+def test_daitch_mokotoff():
+    assert xdistances.daitch_mokotoff("Auerbach") == {"097400", "097500"}
+    assert xdistances.daitch_mokotoff("Moskowitz") == {"645740"}
+    assert xdistances.daitch_mokotoff("Moskovitz") == {"645740"}
+    assert xdistances.daitch_mokotoff("Schwarzenegger") == {"474659", "479465"}
+    assert xdistances.daitch_mokotoff("") == set()
+    assert xdistances.daitch_mokotoff_parallel(["Lipshitz", "Ohrbach"]) == [{"874400"}, {"097400", "097500"}]
+
+
+def test_approx_beider_morse():
+    assert xdistances.approx_beider_morse_languages("Schwarzenegger") == ["german"]
+    assert xdistances.approx_beider_morse_languages("Wojciechowski") == ["polish"]
+    assert xdistances.approx_beider_morse_languages("Иванов") == ["russian"]
+    assert xdistances.approx_beider_morse_languages("אברהם") == ["hebrew"]
+    assert xdistances.approx_beider_morse_languages("Martin") == ["any"]
+    assert xdistances.approx_beider_morse("Ivanov") == xdistances.approx_beider_morse("Иванов")
+    assert xdistances.approx_beider_morse("Abraham") & xdistances.approx_beider_morse("אברהם")
+    assert xdistances.approx_beider_morse("Schwarzenegger") & xdistances.approx_beider_morse("Shvartsenegger")
+    assert xdistances.approx_beider_morse("Cohen") == {"kn", "tsn"}
+    assert xdistances.approx_beider_morse("Cohen", ["hungarian"]) == {"tsn"}
+    assert xdistances.approx_beider_morse("") == set()
+    assert xdistances.approx_beider_morse_parallel(["Ivanov", "Ivanova"], ["russian"]) == [{"avnf"}, {"avnf"}]
+    with pytest.raises(ValueError):
+        xdistances.approx_beider_morse("Ivanov", ["klingon"])
+
+
+def test_phonetic_match():
+    assert xdistances.phonetic_match("Smith", "Schmidt")
+    assert xdistances.phonetic_match("Catherine", "Kathryn")
+    assert xdistances.phonetic_match("Robert", "Rupert", "soundex")
+    assert not xdistances.phonetic_match("Robert", "Rupert", "metaphone")
+    assert not xdistances.phonetic_match("", "", "soundex")
+    assert xdistances.phonetic_match("Byrne", "Boern", "mra")
+    assert xdistances.phonetic_match("Knuth", "Nuth", "nysiis")
+    assert xdistances.phonetic_match("Auerbach", "Ohrbach", "daitch_mokotoff")
+    assert xdistances.phonetic_match("Rabinowitz", "Rabinovitz", "approx_beider_morse")
+    assert xdistances.phonetic_match_parallel(["Smith", "Smith"], ["Smyth", "Jones"]) == [True, False]
+    with pytest.raises(ValueError):
+        xdistances.phonetic_match("a", "b", "klingon")
+
+
+def test_qgram_index():
+    index = xdistances.QGramIndex.build(["kitten", "sitting", "mitten", "bitter"])
+    assert len(index) == 4
+    assert index.search("kitten", 1) == [(0, "kitten", 0), (2, "mitten", 1)]
+    assert index.search("kitten", 0) == [(0, "kitten", 0)]
+    assert index.search_parallel(["mitten", "sitting"], 0) == [
+        [(2, "mitten", 0)],
+        [(1, "sitting", 0)],
+    ]
+    assert [hit[0] for hit in index.search("kitten", float("inf"))] == [0, 2, 3, 1]
+    assert len(index.search("kitten", 1e19)) == 4
+    with pytest.raises(ValueError):
+        index.search("kitten", -1)
+
+
+def test_qgram_index_sorensen_dice():
+    index = xdistances.QGramIndex.build(
+        ["night", "nacht", "nightly"], pad=False, metric="sorensen_dice"
+    )
+    assert index.search("night", 0.8) == [(0, "night", 1.0), (2, "nightly", 0.8)]
+    score = xdistances.sorensen_dice("café au lait", "cafe au lait")
+    index = xdistances.QGramIndex.build(["cafe au lait"], pad=False, metric="sorensen_dice")
+    assert index.search("café au lait", 0.5) == [(0, "cafe au lait", score)]
+    lsh = xdistances.MinHashLSH.build(["cafe au lait"], num_perm=16, bands=16, shingle_size=1)
+    assert lsh.query("café au lait", 0.5, "sorensen_dice") == [(0, "cafe au lait", score)]
+    with pytest.raises(ValueError):
+        xdistances.QGramIndex.build(["night"], q=3, metric="sorensen_dice")
+
+
+def test_symspell():
+    speller = xdistances.SymSpell(2)
+    for word, frequency in [("hello", 10), ("help", 12), ("the", 500), ("there", 100)]:
+        speller.add(word, frequency)
+    assert len(speller) == 4
+    assert speller.lookup("helo") == [("help", 1, 12), ("hello", 1, 10)]
+    assert speller.lookup("helo", "top") == [("help", 1, 12)]
+    assert speller.lookup("hello", "all") == [("hello", 0, 10), ("help", 2, 12)]
+    assert speller.lookup("teh", max_distance=1) == [("the", 1, 500)]
+    with pytest.raises(ValueError):
+        speller.lookup("teh", max_distance=3)
+
+
+def test_symspell_compound():
+    speller = xdistances.SymSpell(2)
+    for word in ["the", "quick", "brown", "fox", "lazy", "dog", "there"]:
+        speller.add(word)
+    assert speller.lookup_compound("the quikc brwn fox")[:2] == ("the quick brown fox", 2)
+    assert speller.lookup_compound("th ere")[:2] == ("there", 1)
+    assert speller.word_segmentation("thelazydog")[:2] == ("the lazy dog", "the lazy dog")
+
+
+def test_levenshtein_automaton():
+    automaton = xdistances.LevenshteinAutomaton("kitten", 1)
+    assert automaton.distance("mitten") == 1
+    assert automaton.distance("sitting") is None
+    words = sorted(["bitten", "kitchen", "kitten", "kitty", "mitten", "sitting"])
+    assert automaton.search_sorted(words) == [(0, "bitten", 1), (2, "kitten", 0), (4, "mitten", 1)]
+    assert xdistances.LevenshteinAutomaton("ca", 1, transpositions=True).distance("ac") == 1
+    with pytest.raises(ValueError):
+        automaton.search_sorted(["b", "a"])
+
+
+def test_levenshtein_automaton_fst(tmp_path):
+    path = str(tmp_path / "words.fst")
+    assert xdistances.build_fst_set(["product", "produce", "apple", "product"], path) == 3
+    automaton = xdistances.LevenshteinAutomaton("prdo", 1, transpositions=True, prefix=True)
+    assert automaton.search_fst(path) == [("produce", 1), ("product", 1)]
+    with open(str(tmp_path / "broken.fst"), "wb") as f:
+        f.write(b"not an fst")
+    with pytest.raises(ValueError):
+        automaton.search_fst(str(tmp_path / "broken.fst"))
+    with pytest.raises(OSError):
+        automaton.search_fst(str(tmp_path / "missing.fst"))
+
+
+def test_minhash():
+    assert len(xdistances.minhash("hello world")) == 128
+    assert xdistances.minhash("hello world") == xdistances.minhash("hello world")
+    assert xdistances.minhash("hello world", num_perm=16, seed=2) != xdistances.minhash(
+        "hello world", num_perm=16, seed=3
+    )
+    assert xdistances.minhash_parallel(["a", "b"], num_perm=4) == [
+        xdistances.minhash("a", num_perm=4),
+        xdistances.minhash("b", num_perm=4),
+    ]
+
+
+def test_minhash_lsh():
+    documents = [
+        "the quick brown fox jumps over the lazy dog",
+        "the quick brown fox jumped over the lazy dog",
+        "lorem ipsum dolor sit amet",
+        "lorem ipsum dolor sit amet!",
+        "something else entirely",
+    ]
+    lsh = xdistances.MinHashLSH.build(documents, num_perm=128, bands=32)
+    assert lsh.candidate_pairs() == [(0, 1), (2, 3)]
+    copies = xdistances.MinHashLSH.build(["abcd"] * 3, num_perm=16, bands=4)
+    assert copies.candidate_pairs() == [(0, 1), (0, 2), (1, 2)]
+    assert copies.candidate_pairs(max_bucket_size=2) == []
+    assert lsh.near_duplicates(0.9, max_bucket_size=1) == []
+    assert [(i, j) for i, j, _ in lsh.near_duplicates(0.9)] == [(2, 3)]
+    assert [(i, j) for i, j, _ in lsh.near_duplicates(0.9, "sorensen_dice")] == [(0, 1), (2, 3)]
+    assert [i for i, _, _ in lsh.query("lorem ipsum dolor sit amet", 0.9)] == [2, 3]
+    with pytest.raises(ValueError):
+        xdistances.MinHashLSH.build(documents, num_perm=128, bands=30)
+
+
+def test_vptree():
+    strings = ["kitten", "sitting", "mitten", "bitten", "smitten", "kitchen"]
+    tree = xdistances.VPTree.build(strings)
+    assert len(tree) == 6
+    assert tree.knn("kitten", 2) == [(0, "kitten", 1.0), (2, "mitten", 0.8333333333333334)]
+    assert [i for i, _, _ in tree.range("kitten", 0.8)] == [0, 2, 3]
+    assert tree.knn_parallel(["mitten"], 1) == [[(2, "mitten", 1.0)]]
+    with pytest.raises(ValueError):
+        xdistances.VPTree.build(strings, metric="hamming")
+
+
+def test_vptree_exhaustive():
+    strings = ["martha", "marhta", "dixon", "dicksonx", "jellyfish", "smellyfish"]
+    tree = xdistances.VPTree.build(strings, metric="jaro_winkler", exhaustive=True)
+    expected = [i for i, s in enumerate(strings) if xdistances.jaro_winkler("marhta", s) >= 0.7]
+    assert sorted(i for i, _, _ in tree.range("marhta", 0.7)) == expected
+
+
+def test_index_save_load(tmp_path):
+    words = ["".join(chars) for chars in itertools.product("abcd", repeat=4)]
+    indexes = [
+        (xdistances.QGramIndex.build(words), lambda index: index.search("abca", 1)),
+        (
+            xdistances.MinHashLSH.build(words, num_perm=16, bands=8, shingle_size=2),
+            lambda lsh: lsh.candidate_pairs(),
+        ),
+        (xdistances.VPTree.build(words), lambda tree: (tree.knn("abca", 5), tree.range("abcd", 0.7))),
+    ]
+    for index, query in indexes:
+        path = str(tmp_path / type(index).__name__)
+        index.save(path)
+        for mmap, verify in itertools.product([True, False], repeat=2):
+            loaded = type(index).load(path, mmap=mmap, verify=verify)
+            assert loaded.mapped == mmap
+            assert len(loaded) == len(index)
+            assert query(loaded) == query(index)
+
+
+def test_symspell_save_load(tmp_path):
+    path = str(tmp_path / "words.idx")
+    speller = xdistances.SymSpell(2)
+    for word, frequency in [("hello", 10), ("help", 12), ("the", 500)]:
+        speller.add(word, frequency)
+    speller.save(path)
+    loaded = xdistances.SymSpell.load(path)
+    assert loaded.lookup("helo") == [("help", 1, 12), ("hello", 1, 10)]
+    loaded.add("hello", 5)
+    loaded.add("helot", 1)
+    assert len(loaded) == 4 and "helot" in loaded
+    assert loaded.lookup("helo") == [("hello", 1, 15), ("help", 1, 12), ("helot", 1, 1)]
+    loaded.save(path)
+    assert xdistances.SymSpell.load(path, mmap=False).lookup("helo") == loaded.lookup("helo")
+
+
+def test_index_load_invalid(tmp_path):
+    path = str(tmp_path / "tree.idx")
+    xdistances.VPTree.build(["kitten", "sitting"]).save(path)
+    with pytest.raises(ValueError):
+        xdistances.QGramIndex.load(path)
+    with open(path, "r+b") as f:
+        f.seek(-1, 2)
+        last = f.read(1)
+        f.seek(-1, 2)
+        f.write(bytes([last[0] ^ 1]))
+    with pytest.raises(ValueError, match="checksum"):
+        xdistances.VPTree.load(path, verify=True)
+    with open(path, "wb") as f:
+        f.write(b"not an index")
+    with pytest.raises(ValueError):
+        xdistances.VPTree.load(path)
+
+
+def test_pickle():
+    words = ["kitten", "sitting", "mitten", "bitten", "smitten", "kitchen"]
+    speller = xdistances.SymSpell(2)
+    for word in words:
+        speller.add(word, len(word))
+    objects = [
+        (xdistances.QGramIndex.build(words), lambda index: index.search("kitten", 2)),
+        (speller, lambda speller: speller.lookup("kiten", "all")),
+        (
+            xdistances.LevenshteinAutomaton("kitten", 2, transpositions=True),
+            lambda automaton: (automaton.transpositions, automaton.search_sorted(sorted(words))),
+        ),
+        (
+            xdistances.MinHashLSH.build(words, num_perm=16, bands=8),
+            lambda lsh: lsh.query("kitten", 0.1),
+        ),
+        (
+            xdistances.VPTree.build(words, metric="jaro"),
+            lambda tree: (tree.metric, tree.knn("kitten", 3)),
+        ),
+    ]
+    for obj, query in objects:
+        copy = pickle.loads(pickle.dumps(obj))
+        assert type(copy) is type(obj)
+        assert query(copy) == query(obj)
+    tree = xdistances.VPTree.build(["a"])
+    tree.__setstate__(objects[4][0].__getstate__())
+    assert len(tree) == len(words)
+    with pytest.raises(ValueError):
+        tree.__setstate__(b"garbage")